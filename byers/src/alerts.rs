@@ -0,0 +1,102 @@
+//! Operational alerts, posted to every channel configured with `ops_alerts`.
+
+use fred::pool::RedisPool;
+use poise::serenity_prelude::{
+    ChannelId, CreateEmbed, CreateEmbedFooter, CreateMessage, Http, Timestamp,
+};
+use tracing::{error, warn};
+
+use crate::prelude::Error;
+use judeharley::{
+    alerts::{self, Alert, Severity},
+    sea_orm::DatabaseConnection,
+    ServerChannelConfig,
+};
+
+/// Raises an alert on behalf of Byers, it comes back through Redis like
+/// everyone else's.
+pub async fn raise(
+    redis_pool: &RedisPool,
+    severity: Severity,
+    key: impl Into<String>,
+    title: impl Into<String>,
+    message: impl Into<String>,
+) {
+    alerts::raise(
+        redis_pool,
+        Alert::new("byers", severity, key, title, message),
+    )
+    .await;
+}
+
+fn alert_embed(alert: &Alert) -> CreateEmbed {
+    let (label, color) = match alert.severity {
+        Severity::Info => ("Info", (0, 200, 83)),
+        Severity::Warning => ("Warning", (255, 165, 0)),
+        Severity::Error => ("Error", (255, 0, 0)),
+        Severity::Critical => ("Critical", (139, 0, 0)),
+    };
+
+    // embed descriptions are limited to 4096 characters
+    let message = if alert.message.chars().count() > 4096 {
+        format!(
+            "{}...",
+            alert.message.chars().take(4093).collect::<String>()
+        )
+    } else {
+        alert.message.clone()
+    };
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("[{}] {}", label, alert.title))
+        .description(message)
+        .color(color)
+        .footer(CreateEmbedFooter::new(&alert.source));
+    if let Ok(timestamp) = Timestamp::from_unix_timestamp(alert.raised_at.timestamp()) {
+        embed = embed.timestamp(timestamp);
+    }
+
+    embed
+}
+
+pub async fn send_ops_alert(
+    http: &Http,
+    db: &DatabaseConnection,
+    embed: CreateEmbed,
+) -> Result<(), Error> {
+    let channels = ServerChannelConfig::get_all_ops_alert_channels(db).await?;
+    if channels.is_empty() {
+        warn!("No ops alert channel is configured, dropping alert");
+    }
+
+    for channel in channels {
+        if let Err(e) = ChannelId::new(channel.id as u64)
+            .send_message(http, CreateMessage::new().embed(embed.clone()))
+            .await
+        {
+            error!(channel_id = channel.id, error = %e, "failed to send ops alert");
+        }
+    }
+
+    Ok(())
+}
+
+/// Posts an alert received on [`alerts::ALERTS_CHANNEL`] to the ops channels.
+pub async fn forward_alert(http: &Http, db: &DatabaseConnection, payload: &str) {
+    let alert = match serde_json::from_str::<Alert>(payload) {
+        Ok(alert) => alert,
+        Err(e) => {
+            warn!(error = %e, "received a malformed alert");
+            return;
+        }
+    };
+
+    if let Err(e) = send_ops_alert(http, db, alert_embed(&alert)).await {
+        error!(
+            source = %alert.source,
+            key = %alert.key,
+            error = %e,
+            "failed to forward alert"
+        );
+    }
+}
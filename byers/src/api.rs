@@ -0,0 +1,493 @@
+//! Versioned, read-only public API.
+//!
+//! Every successful response wraps its payload in `{"data": ...}`, lists also
+//! carry a `pagination` object. Errors are `{"error": "..."}` with a matching
+//! HTTP status code.
+
+use axum::{
+    extract::{rejection::QueryRejection, FromRef, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use judeharley::{
+    communication::{ByersUnixStream, LiquidsoapCommunication, QueueItem},
+    health,
+    sea_orm::DatabaseConnection,
+    JudeHarleyError, PlayedSongs, Songs,
+};
+
+const DEFAULT_PER_PAGE: u64 = 25;
+const MAX_PER_PAGE: u64 = 100;
+const DEFAULT_CHART_LIMIT: u64 = 10;
+const MAX_CHART_LIMIT: u64 = 50;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "lumiRadio API"),
+    paths(list_songs, get_song, history, charts, queue),
+    components(schemas(
+        Song,
+        SongDetail,
+        HistoryEntry,
+        ChartEntry,
+        ChartPeriod,
+        QueueEntry,
+        Pagination,
+        ErrorBody,
+        SongPage,
+        HistoryPage,
+        SongDetailResponse,
+        ChartResponse,
+        QueueResponse,
+    )),
+    tags(
+        (name = "songs", description = "The song catalogue"),
+        (name = "radio", description = "What has been and will be playing")
+    )
+)]
+pub struct ApiDoc;
+
+pub fn v1_router<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    DatabaseConnection: FromRef<S>,
+{
+    Router::new()
+        .route("/songs", get(list_songs))
+        .route("/songs/:id", get(get_song))
+        .route("/history", get(history))
+        .route("/charts", get(charts))
+        .route("/queue", get(queue))
+        .route("/openapi.json", get(openapi))
+}
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct ErrorBody {
+    error: String,
+}
+
+pub enum ApiError {
+    BadRequest(String),
+    NotFound(&'static str),
+    Internal,
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(value: QueryRejection) -> Self {
+        Self::BadRequest(value.body_text())
+    }
+}
+
+impl From<JudeHarleyError> for ApiError {
+    fn from(value: JudeHarleyError) -> Self {
+        if let JudeHarleyError::InvalidSearch(e) = value {
+            return Self::BadRequest(format!("invalid search: {}", e));
+        }
+
+        error!("API request failed: {}", value);
+        Self::Internal
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::NotFound(what) => (StatusCode::NOT_FOUND, format!("{} not found", what)),
+            ApiError::Internal => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            ),
+        };
+
+        (status, Json(ErrorBody { error })).into_response()
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+#[aliases(
+    SongDetailResponse = Envelope<SongDetail>,
+    ChartResponse = Envelope<Vec<ChartEntry>>,
+    QueueResponse = Envelope<Vec<QueueEntry>>
+)]
+pub struct Envelope<T> {
+    data: T,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+#[aliases(SongPage = Paginated<Song>, HistoryPage = Paginated<HistoryEntry>)]
+pub struct Paginated<T> {
+    data: Vec<T>,
+    pagination: Pagination,
+}
+
+#[derive(Serialize, ToSchema, Debug, PartialEq, Eq)]
+pub struct Pagination {
+    /// The current page, starting at 1
+    page: u64,
+    per_page: u64,
+    total_items: u64,
+    total_pages: u64,
+}
+
+impl Pagination {
+    fn new(page: u64, per_page: u64, total_items: u64) -> Self {
+        Self {
+            page,
+            per_page,
+            total_items,
+            total_pages: total_items.div_ceil(per_page),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams, Debug, Default)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    /// The page to return, starting at 1
+    page: Option<u64>,
+    /// Items per page, at most 100
+    per_page: Option<u64>,
+}
+
+impl PageParams {
+    /// Returns the 1-based page and the clamped page size.
+    fn resolve(&self) -> (u64, u64) {
+        let page = self.page.unwrap_or(1).max(1);
+        let per_page = self
+            .per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE);
+
+        (page, per_page)
+    }
+}
+
+#[derive(Deserialize, IntoParams, Debug)]
+#[into_params(parameter_in = Query)]
+pub struct SongSearchParams {
+    /// Search over title, artist and album, with filters like
+    /// `artist:"Toby Fox" duration:<3m tag:genre=chiptune -remix`
+    q: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct Song {
+    id: String,
+    title: String,
+    artist: String,
+    album: String,
+    /// The artist the album is filed under, if it differs per song
+    album_artist: Option<String>,
+    track_number: Option<i32>,
+    disc_number: Option<i32>,
+    year: Option<i32>,
+    genre: Option<String>,
+    /// Duration in seconds
+    duration: f64,
+    bitrate: i32,
+}
+
+impl From<Songs> for Song {
+    fn from(value: Songs) -> Self {
+        Self {
+            id: value.file_hash,
+            title: value.title,
+            artist: value.artist,
+            album: value.album,
+            album_artist: value.album_artist,
+            track_number: value.track_number,
+            disc_number: value.disc_number,
+            year: value.year,
+            genre: value.genre,
+            duration: value.duration,
+            bitrate: value.bitrate,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct SongDetail {
+    #[serde(flatten)]
+    song: Song,
+    played: i64,
+    requested: i64,
+    last_played_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct HistoryEntry {
+    played_at: DateTime<Utc>,
+    /// `null` if the song has been removed from the library since
+    song: Option<Song>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct ChartEntry {
+    position: usize,
+    plays: i64,
+    song: Song,
+}
+
+#[derive(Deserialize, ToSchema, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartPeriod {
+    Day,
+    #[default]
+    Week,
+    Month,
+    AllTime,
+}
+
+impl ChartPeriod {
+    fn since(self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            ChartPeriod::Day => Some(now - Duration::days(1)),
+            ChartPeriod::Week => Some(now - Duration::weeks(1)),
+            ChartPeriod::Month => Some(now - Duration::days(30)),
+            ChartPeriod::AllTime => None,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct QueueEntry {
+    title: String,
+    artist: String,
+    album: Option<String>,
+    /// `prioq` for songs queued by staff, `srq` for song requests
+    queue: String,
+}
+
+impl From<QueueItem> for QueueEntry {
+    fn from(value: QueueItem) -> Self {
+        Self {
+            title: value.title,
+            artist: value.artist,
+            album: value.album,
+            queue: value.queue,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams, Debug)]
+#[into_params(parameter_in = Query)]
+pub struct ChartParams {
+    /// The period to count plays in, defaults to `week`
+    period: Option<ChartPeriod>,
+    /// Number of songs, at most 50
+    limit: Option<u64>,
+}
+
+/// Lists the song catalogue, optionally filtered by a search query
+#[utoipa::path(
+    get,
+    path = "/api/v1/songs",
+    tag = "songs",
+    params(SongSearchParams, PageParams),
+    responses(
+        (status = 200, body = SongPage),
+        (status = 400, body = ErrorBody, description = "Invalid query parameters"),
+    )
+)]
+async fn list_songs(
+    State(db): State<DatabaseConnection>,
+    search: Result<Query<SongSearchParams>, QueryRejection>,
+    page: Result<Query<PageParams>, QueryRejection>,
+) -> Result<Json<Paginated<Song>>, ApiError> {
+    let (Query(search), Query(page)) = (search?, page?);
+    let (page, per_page) = page.resolve();
+    let (songs, total) = Songs::get_page(search.q.as_deref(), page - 1, per_page, &db).await?;
+
+    Ok(Json(Paginated {
+        data: songs.into_iter().map(Into::into).collect(),
+        pagination: Pagination::new(page, per_page, total),
+    }))
+}
+
+/// Shows a single song with its play and request counts
+#[utoipa::path(
+    get,
+    path = "/api/v1/songs/{id}",
+    tag = "songs",
+    params(("id" = String, Path, description = "The song's ID")),
+    responses(
+        (status = 200, body = SongDetailResponse),
+        (status = 404, body = ErrorBody, description = "Unknown song"),
+    )
+)]
+async fn get_song(
+    State(db): State<DatabaseConnection>,
+    Path(id): Path<String>,
+) -> Result<Json<Envelope<SongDetail>>, ApiError> {
+    let song = Songs::get_by_hash(&id, &db)
+        .await?
+        .filter(|song| !song.hidden)
+        .ok_or(ApiError::NotFound("Song"))?;
+
+    let played = song.played(&db).await?;
+    let requested = song.requested(&db).await?;
+    let last_played_at = song.last_played_at(&db).await?.map(|t| t.and_utc());
+
+    Ok(Json(Envelope {
+        data: SongDetail {
+            song: song.into(),
+            played,
+            requested,
+            last_played_at,
+        },
+    }))
+}
+
+/// Lists the songs that have been played, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/history",
+    tag = "radio",
+    params(PageParams),
+    responses(
+        (status = 200, body = HistoryPage),
+        (status = 400, body = ErrorBody, description = "Invalid query parameters"),
+    )
+)]
+async fn history(
+    State(db): State<DatabaseConnection>,
+    page: Result<Query<PageParams>, QueryRejection>,
+) -> Result<Json<Paginated<HistoryEntry>>, ApiError> {
+    let Query(page) = page?;
+    let (page, per_page) = page.resolve();
+    let (plays, total) = PlayedSongs::get_history_page(page - 1, per_page, &db).await?;
+
+    Ok(Json(Paginated {
+        data: plays
+            .into_iter()
+            .map(|(play, song)| HistoryEntry {
+                played_at: play.played_at.and_utc(),
+                song: song.map(Into::into),
+            })
+            .collect(),
+        pagination: Pagination::new(page, per_page, total),
+    }))
+}
+
+/// Lists the most played songs
+#[utoipa::path(
+    get,
+    path = "/api/v1/charts",
+    tag = "radio",
+    params(ChartParams),
+    responses(
+        (status = 200, body = ChartResponse),
+        (status = 400, body = ErrorBody, description = "Invalid query parameters"),
+    )
+)]
+async fn charts(
+    State(db): State<DatabaseConnection>,
+    params: Result<Query<ChartParams>, QueryRejection>,
+) -> Result<Json<Envelope<Vec<ChartEntry>>>, ApiError> {
+    let Query(params) = params?;
+    let since = params
+        .period
+        .unwrap_or_default()
+        .since(Utc::now())
+        .map(|t| t.naive_utc());
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_CHART_LIMIT)
+        .clamp(1, MAX_CHART_LIMIT);
+
+    let most_played = PlayedSongs::get_most_played(since, limit, &db).await?;
+
+    Ok(Json(Envelope {
+        data: most_played
+            .into_iter()
+            .enumerate()
+            .map(|(i, (song, plays))| ChartEntry {
+                position: i + 1,
+                plays,
+                song: song.into(),
+            })
+            .collect(),
+    }))
+}
+
+/// Lists the songs that are queued up to play next
+#[utoipa::path(
+    get,
+    path = "/api/v1/queue",
+    tag = "radio",
+    responses((status = 200, body = QueueResponse))
+)]
+async fn queue() -> Result<Json<Envelope<Vec<QueueEntry>>>, ApiError> {
+    // over a connection of its own, so neither a hung Liquidsoap nor a busy
+    // API can block the shared one
+    let queue = tokio::time::timeout(health::CHECK_TIMEOUT, async {
+        let mut stream = ByersUnixStream::new().await?;
+        stream.song_requests().await
+    })
+    .await
+    .map_err(|_| {
+        error!("API request failed: Liquidsoap didn't list the queue in time");
+        ApiError::Internal
+    })??;
+
+    Ok(Json(Envelope {
+        data: queue.into_iter().map(Into::into).collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_params() {
+        assert_eq!(PageParams::default().resolve(), (1, DEFAULT_PER_PAGE));
+
+        let params = PageParams {
+            page: Some(0),
+            per_page: Some(1000),
+        };
+        assert_eq!(params.resolve(), (1, MAX_PER_PAGE));
+
+        let params = PageParams {
+            page: Some(3),
+            per_page: Some(0),
+        };
+        assert_eq!(params.resolve(), (3, 1));
+    }
+
+    #[test]
+    fn test_pagination() {
+        assert_eq!(Pagination::new(1, 25, 0).total_pages, 0);
+        assert_eq!(Pagination::new(1, 25, 25).total_pages, 1);
+        assert_eq!(Pagination::new(1, 25, 26).total_pages, 2);
+    }
+
+    #[test]
+    fn test_openapi_document() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        for path in [
+            "/api/v1/songs",
+            "/api/v1/songs/{id}",
+            "/api/v1/history",
+            "/api/v1/charts",
+            "/api/v1/queue",
+        ] {
+            assert!(doc["paths"][path]["get"].is_object(), "missing {}", path);
+        }
+        assert!(doc["components"]["schemas"]["SongPage"].is_object());
+    }
+}
@@ -1,9 +1,27 @@
+use std::net::SocketAddr;
+
 use serde::Deserialize;
 
 fn default_environment() -> String {
     "development".into()
 }
 
+fn default_dead_air_grace_period() -> u64 {
+    120
+}
+
+fn default_public_url() -> String {
+    "https://discord.lumirad.io".into()
+}
+
+fn default_jingles_path() -> String {
+    "/jingles".into()
+}
+
+fn default_listenbrainz_url() -> String {
+    judeharley::maintenance::scrobbling::DEFAULT_BASE_URL.into()
+}
+
 #[derive(Deserialize, Debug)]
 pub struct AppConfig {
     pub discord_token: String,
@@ -18,6 +36,26 @@ pub struct AppConfig {
     pub environment: String,
     #[serde(default = "Default::default")]
     pub sentry_debug: bool,
+
+    /// Seconds a song may run past its duration before the stream counts as stuck
+    #[serde(default = "default_dead_air_grace_period")]
+    pub dead_air_grace_period: u64,
+
+    /// ListenBrainz compatible server tokens are validated against
+    #[serde(default = "default_listenbrainz_url")]
+    pub listenbrainz_url: String,
+
+    /// URL the web server is reachable at, used for links in feeds
+    #[serde(default = "default_public_url")]
+    pub public_url: String,
+
+    /// Directory jingles are uploaded to, shared with frohike and Liquidsoap
+    #[serde(default = "default_jingles_path")]
+    pub jingles_path: String,
+
+    /// Address to serve Prometheus metrics on, e.g. `0.0.0.0:9000`. Keep it
+    /// off the public web server's port, metrics are only served if it's set
+    pub metrics_address: Option<SocketAddr>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
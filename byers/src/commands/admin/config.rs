@@ -4,9 +4,7 @@ use poise::{
 };
 
 use crate::prelude::*;
-use judeharley::{
-    sea_orm::Set, Cans, ServerChannelConfig, ServerConfig, ServerRoleConfig, Users
-};
+use judeharley::{sea_orm::Set, Cans, ServerChannelConfig, ServerConfig, ServerRoleConfig, Users};
 
 /// Configuration-related commands
 #[poise::command(
@@ -162,7 +160,8 @@ pub async fn delete_role_config(
     Ok(())
 }
 
-/// Configures a channel for watchtime and point accumulation
+/// Configures a channel for points, reminders, alerts, announcements and song reports
+#[allow(clippy::too_many_arguments)]
 #[poise::command(slash_command, owners_only, ephemeral, guild_only)]
 pub async fn manage_channel(
     ctx: ApplicationContext<'_>,
@@ -170,18 +169,28 @@ pub async fn manage_channel(
     #[description = "Allow point accumulation"] allow_point_accumulation: bool,
     #[description = "Allow watch time accumulation"] allow_watch_time_accumulation: bool,
     #[description = "Remind people to hydrate in here"] hydration_reminder: bool,
+    #[description = "Post operational alerts in here"] ops_alerts: bool,
+    #[description = "Announce scheduled programming in here"] announcements: bool,
+    #[description = "Post song reports for review in here"] song_reports: bool,
 ) -> Result<(), Error> {
     let data = ctx.data;
 
     let channel_config = ServerChannelConfig::get_or_insert(channel.id().get(), &data.db).await?;
-    
 
-    channel_config.update(judeharley::entities::server_channel_config::ActiveModel {
-        allow_point_accumulation: Set(allow_point_accumulation),
-        allow_watch_time_accumulation: Set(allow_watch_time_accumulation),
-        hydration_reminder: Set(hydration_reminder),
-        ..Default::default()
-    }, &data.db).await?;
+    channel_config
+        .update(
+            judeharley::entities::server_channel_config::ActiveModel {
+                allow_point_accumulation: Set(allow_point_accumulation),
+                allow_watch_time_accumulation: Set(allow_watch_time_accumulation),
+                hydration_reminder: Set(hydration_reminder),
+                ops_alerts: Set(ops_alerts),
+                announcements: Set(announcements),
+                song_reports: Set(song_reports),
+                ..Default::default()
+            },
+            &data.db,
+        )
+        .await?;
 
     ctx.send(
         CreateReply::default().embed(
@@ -201,7 +210,14 @@ pub async fn manage_channel(
                     "Remind people to hydrate",
                     hydration_reminder.to_string(),
                     true,
-                ),
+                )
+                .field("Post operational alerts", ops_alerts.to_string(), true)
+                .field(
+                    "Announce scheduled programming",
+                    announcements.to_string(),
+                    true,
+                )
+                .field("Review song reports", song_reports.to_string(), true),
         ),
     )
     .await?;
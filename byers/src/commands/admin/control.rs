@@ -1,8 +1,13 @@
 use std::path::PathBuf;
 
-use crate::commands::autocomplete_songs;
+use crate::commands::{autocomplete_albums, autocomplete_songs, find_album};
 use crate::prelude::*;
-use judeharley::{communication::LiquidsoapCommunication, Songs, Users};
+use crate::queued_albums::{parse_start, queue_songs};
+use ellipse::Ellipse;
+use judeharley::{
+    communication::LiquidsoapCommunication, controllers::queued_albums::NewQueuedAlbum,
+    format_duration, DiscordTimestamp, QueuedAlbums, Songs, Users,
+};
 use poise::{serenity_prelude::CreateEmbed, CreateReply};
 
 /// Reconnects the Liquidsoap command socket
@@ -109,6 +114,13 @@ pub async fn song_info(
         tags_str
     };
 
+    let unknown = || "-".to_string();
+    let position = match (song.disc_number, song.track_number) {
+        (Some(disc), Some(track)) => format!("{}-{}", disc, track),
+        (None, Some(track)) => track.to_string(),
+        _ => unknown(),
+    };
+
     ctx.send(
         CreateReply::default()
             .embed(
@@ -121,6 +133,16 @@ pub async fn song_info(
                     .field("Title", &song.title, true)
                     .field("Artist", &song.artist, true)
                     .field("Album", &song.album, true)
+                    .field("Album Artist", song.album_artist(), true)
+                    .field("Track", position, true)
+                    .field(
+                        "Year",
+                        song.year
+                            .map(|year| year.to_string())
+                            .unwrap_or_else(unknown),
+                        true,
+                    )
+                    .field("Genre", song.genre.clone().unwrap_or_else(unknown), true)
                     .field("Bitrate", song.bitrate.to_string(), true)
                     .field("File Path", &song.file_path, true)
                     .field("ID", &song.file_hash, true)
@@ -246,7 +268,8 @@ pub async fn queue(
     song: String,
 ) -> Result<(), Error> {
     let data = ctx.data;
-    let Some(song) = Songs::get_by_hash(&song, &ctx.data.db).await? else {
+    let song = Songs::get_by_hash(&song, &ctx.data.db).await?;
+    let Some(song) = song.filter(|song| !song.hidden) else {
         ctx.send(CreateReply::default().content("Song not found."))
             .await?;
         return Ok(());
@@ -271,6 +294,89 @@ pub async fn queue(
     Ok(())
 }
 
+/// Queues a whole album in track order, now or at a scheduled time
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn queue_album(
+    ctx: ApplicationContext<'_>,
+    #[description = "The album to queue"]
+    #[autocomplete = "autocomplete_albums"]
+    album: String,
+    #[description = "When to start in UTC, `HH:MM` or `YYYY-MM-DD HH:MM`, right away if empty"]
+    starts_at: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data;
+    let Some(album) = find_album(&album, &data.db).await? else {
+        ctx.send(CreateReply::default().content("Album not found."))
+            .await?;
+        return Ok(());
+    };
+    let songs = Songs::get_by_album(&album.artist, &album.album, &data.db).await?;
+    if songs.is_empty() {
+        ctx.send(CreateReply::default().content("Album not found."))
+            .await?;
+        return Ok(());
+    }
+    let starts_at = match starts_at {
+        Some(starts_at) => match parse_start(&starts_at, chrono::Utc::now().naive_utc()) {
+            Ok(starts_at) => Some(starts_at),
+            Err(e) => {
+                ctx.send(CreateReply::default().content(format!("Invalid start time: {}.", e)))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let runtime = songs.iter().map(|song| song.duration).sum::<f64>();
+    let tracks = songs
+        .iter()
+        .map(|song| {
+            let position = match (song.disc_number, song.track_number) {
+                (Some(disc), Some(track)) => format!("{}-{}. ", disc, track),
+                (None, Some(track)) => format!("{}. ", track),
+                _ => String::new(),
+            };
+            format!("{}{} - {}", position, song.artist, song.title)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let embed = CreateEmbed::new()
+        .description(tracks.as_str().truncate_ellipse(4000))
+        .field("Tracks", songs.len().to_string(), true)
+        .field("Runtime", format_duration(runtime as u64), true);
+    let embed = match starts_at {
+        Some(starts_at) => {
+            let queued = QueuedAlbums::create(
+                NewQueuedAlbum {
+                    album: album.album.clone(),
+                    song_ids: songs.iter().map(|song| song.file_hash.clone()).collect(),
+                    starts_at,
+                    requested_by: Some(ctx.author().id.get() as i64),
+                },
+                &data.db,
+            )
+            .await?;
+            embed
+                .title(format!("Album scheduled: {}", album.album))
+                .field("Starts", queued.starts_at.long_date_short_time(), true)
+        }
+        None => {
+            let user = Users::get_or_insert(ctx.author().id.get(), &data.db).await?;
+            {
+                let mut comms = data.comms.lock().await;
+                queue_songs(&mut comms, &songs, Some(&user), &data.db).await?;
+            }
+            embed.title(format!("Album queued: {}", album.album))
+        }
+    };
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
 #[derive(Debug, poise::ChoiceParameter, strum::Display)]
 pub enum SkipType {
     #[name = "The current song"]
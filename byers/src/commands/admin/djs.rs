@@ -0,0 +1,130 @@
+use poise::{
+    serenity_prelude::{CreateEmbed, User},
+    CreateReply,
+};
+
+use crate::prelude::*;
+use judeharley::{controllers::live_djs::NewLiveDj, live, DiscordTimestamp, LiveDjs, Users};
+
+/// Manages the DJs who may stream live to the harbor
+#[poise::command(
+    slash_command,
+    ephemeral,
+    owners_only,
+    subcommands("issue", "list", "revoke"),
+    subcommand_required
+)]
+pub async fn djs(_: ApplicationContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn reply(ctx: ApplicationContext<'_>, embed: CreateEmbed) -> Result<(), Error> {
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Issues harbor credentials to a DJ, replacing their old ones
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn issue(
+    ctx: ApplicationContext<'_>,
+    #[description = "The DJ"] user: User,
+    #[description = "Name shown while they are on air"] name: String,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Invalid name")
+                .description("The DJ needs a name."),
+        )
+        .await;
+    }
+
+    Users::get_or_insert(user.id.get(), &data.db).await?;
+    let mut username = live::username_for(&name);
+    if LiveDjs::get_by_username(&username, &data.db)
+        .await?
+        .is_some_and(|dj| dj.user_id != user.id.get() as i64)
+    {
+        username = format!("{}-{}", username, user.id);
+    }
+
+    let password = live::generate_password();
+    let dj = LiveDjs::issue(
+        NewLiveDj {
+            user_id: user.id.get(),
+            name,
+            username,
+            password_hash: live::hash_password(&password)?,
+        },
+        &data.db,
+    )
+    .await?;
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title(format!("Credentials for {}", dj.name))
+            .description(format!(
+                "Username: `{}`\nPassword: ||`{}`||\n\nThe password is only shown once, pass it on to <@{}>. They can stream to the harbor's `/live` mount after running `/live start`.",
+                dj.username, password, dj.user_id
+            )),
+    )
+    .await
+}
+
+/// Lists the DJs with harbor credentials
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn list(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let djs = LiveDjs::get_all(&data.db).await?;
+    let description = if djs.is_empty() {
+        "No DJs have credentials.".to_string()
+    } else {
+        djs.iter()
+            .map(|dj| {
+                format!(
+                    "**{}** (<@{}>), username `{}`, issued {}",
+                    dj.name,
+                    dj.user_id,
+                    dj.username,
+                    dj.created_at.relative_time()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    reply(
+        ctx,
+        CreateEmbed::new().title("DJs").description(description),
+    )
+    .await
+}
+
+/// Revokes a DJ's harbor credentials
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn revoke(
+    ctx: ApplicationContext<'_>,
+    #[description = "The DJ"] user: User,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let description = if LiveDjs::revoke(user.id.get(), &data.db).await? {
+        format!("<@{}> can no longer stream to the harbor.", user.id)
+    } else {
+        format!("<@{}> has no credentials.", user.id)
+    };
+
+    reply(
+        ctx,
+        CreateEmbed::new().title("DJs").description(description),
+    )
+    .await
+}
@@ -0,0 +1,238 @@
+use std::path::{Path, PathBuf};
+
+use poise::{
+    serenity_prelude::{Attachment, CreateEmbed},
+    CreateReply,
+};
+
+use crate::prelude::*;
+use judeharley::{jingles, Jingles, SUPPORTED_AUDIO_FORMATS};
+
+/// Jingles bigger than this are most likely songs uploaded by accident.
+const MAX_UPLOAD_SIZE: u32 = 10 * 1024 * 1024;
+
+/// Manages the jingles and station IDs played between songs
+#[poise::command(
+    slash_command,
+    ephemeral,
+    owners_only,
+    subcommands("list", "upload", "remove", "set_enabled"),
+    subcommand_required
+)]
+pub async fn jingles(_: ApplicationContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn reply(ctx: ApplicationContext<'_>, embed: CreateEmbed) -> Result<(), Error> {
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Keeps only the characters that are safe in a file name, so uploads can't
+/// escape the jingles directory.
+fn sanitize_file_name(name: &str) -> String {
+    let name = Path::new(name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+/// Where a jingle's file is on this machine.
+fn local_path(jingle: &Jingles, jingles_path: &Path) -> Option<PathBuf> {
+    Path::new(&jingle.file_path)
+        .strip_prefix("/jingles")
+        .ok()
+        .map(|relative| jingles_path.join(relative))
+}
+
+/// Lists all jingles
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn list(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let jingles = Jingles::get_all(&data.db).await?;
+    let description = if jingles.is_empty() {
+        "There are no jingles, upload one or put it into the jingles directory.".to_string()
+    } else {
+        jingles
+            .iter()
+            .map(|jingle| {
+                format!(
+                    "**#{}** {} {} ({:.0}s), played {} time(s)",
+                    jingle.id,
+                    if jingle.enabled { "✅" } else { "⏸️" },
+                    jingle.title,
+                    jingle.duration,
+                    jingle.play_count
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    reply(
+        ctx,
+        CreateEmbed::new().title("Jingles").description(description),
+    )
+    .await
+}
+
+/// Uploads a jingle into the jingles directory
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn upload(
+    ctx: ApplicationContext<'_>,
+    #[description = "The jingle, an mp3, flac, ogg or wav file"] file: Attachment,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let file_name = sanitize_file_name(&file.filename);
+    let is_audio = Path::new(&file_name).extension().is_some_and(|extension| {
+        SUPPORTED_AUDIO_FORMATS.contains(&extension.to_string_lossy().to_lowercase().as_str())
+    });
+    if !is_audio {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Unsupported file")
+                .description(format!(
+                    "Jingles have to be one of {}.",
+                    SUPPORTED_AUDIO_FORMATS.join(", ")
+                )),
+        )
+        .await;
+    }
+    if file.size > MAX_UPLOAD_SIZE {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("File too big")
+                .description("Jingles can be up to 10 MiB."),
+        )
+        .await;
+    }
+
+    let path = data.jingles_path.join(&file_name);
+    if tokio::fs::try_exists(&path).await? {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Jingle exists")
+                .description(format!("There already is a jingle called `{}`.", file_name)),
+        )
+        .await;
+    }
+
+    ctx.defer_ephemeral().await?;
+    let bytes = file.download().await?;
+    tokio::fs::create_dir_all(&data.jingles_path).await?;
+    tokio::fs::write(&path, bytes).await?;
+
+    let summary = jingles::sync(&data.db, &data.jingles_path).await?;
+    let description = if summary.added > 0 {
+        format!("`{}` will be played between songs.", file_name)
+    } else {
+        format!(
+            "`{}` was saved, but couldn't be read as audio. Check the file and remove it if it's broken.",
+            file_name
+        )
+    };
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title("Jingle uploaded")
+            .description(description),
+    )
+    .await
+}
+
+/// Removes a jingle and deletes its file
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn remove(
+    ctx: ApplicationContext<'_>,
+    #[description = "The jingle's ID, see /admin jingles list"] id: i32,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let Some(jingle) = Jingles::get(id, &data.db).await? else {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Jingles")
+                .description(format!("There is no jingle #{}.", id)),
+        )
+        .await;
+    };
+
+    if let Some(path) = local_path(&jingle, &data.jingles_path) {
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Jingles::delete(jingle.id, &data.db).await?;
+
+    reply(
+        ctx,
+        CreateEmbed::new().title("Jingles").description(format!(
+            "Jingle #{} ({}) has been removed.",
+            id, jingle.title
+        )),
+    )
+    .await
+}
+
+/// Pauses or resumes a jingle without deleting it
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn set_enabled(
+    ctx: ApplicationContext<'_>,
+    #[description = "The jingle's ID, see /admin jingles list"] id: i32,
+    #[description = "Whether it is played between songs"] enabled: bool,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let description = if !Jingles::set_enabled(id, enabled, &data.db).await? {
+        format!("There is no jingle #{}.", id)
+    } else if enabled {
+        format!("Jingle #{} has been enabled.", id)
+    } else {
+        format!("Jingle #{} has been disabled.", id)
+    };
+
+    reply(
+        ctx,
+        CreateEmbed::new().title("Jingles").description(description),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("station id.mp3"), "station_id.mp3");
+        assert_eq!(sanitize_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_file_name(".hidden.ogg"), "hidden.ogg");
+        assert_eq!(
+            sanitize_file_name("lumiRadio-ID_2.flac"),
+            "lumiRadio-ID_2.flac"
+        );
+    }
+}
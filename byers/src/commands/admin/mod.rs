@@ -1,14 +1,27 @@
 use crate::commands::admin::control::{
-    control_cmd, generate_playlist, queue, reconnect, reindex, skip, song_info, song_tag, volume,
+    control_cmd, generate_playlist, queue, queue_album, reconnect, reindex, skip, song_info,
+    song_tag, volume,
 };
 
+use crate::commands::admin::djs::djs;
 use crate::commands::admin::import::import_manually;
+use crate::commands::admin::jingles::jingles;
+use crate::commands::admin::reports::most_skipped;
+use crate::commands::admin::song::song;
+use crate::commands::admin::status::status;
+use crate::commands::admin::webhooks::webhooks;
 use crate::prelude::*;
 
 pub mod config;
 pub mod control;
+pub mod djs;
 pub mod import;
+pub mod jingles;
+pub mod reports;
+pub mod song;
+pub mod status;
 pub mod user;
+pub mod webhooks;
 
 /// Admin commands
 #[poise::command(
@@ -20,12 +33,19 @@ pub mod user;
         "control_cmd",
         "skip",
         "queue",
+        "queue_album",
         "reconnect",
         "song_info",
         "import_manually",
         "reindex",
         "generate_playlist",
         "song_tag",
+        "song",
+        "most_skipped",
+        "status",
+        "webhooks",
+        "jingles",
+        "djs",
     ),
     subcommand_required
 )]
@@ -0,0 +1,54 @@
+use chrono::{Duration, Utc};
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+
+use crate::prelude::*;
+use judeharley::PlayedSongs;
+
+/// Lists the songs that get skipped the most
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn most_skipped(
+    ctx: ApplicationContext<'_>,
+    #[description = "Only count plays of the last N days"]
+    #[min = 1]
+    days: Option<u32>,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let since = days.map(|days| (Utc::now() - Duration::days(days.into())).naive_utc());
+    let most_skipped = PlayedSongs::get_most_skipped(since, 15, &data.db).await?;
+
+    let description = if most_skipped.is_empty() {
+        "No songs have been skipped yet.".to_string()
+    } else {
+        most_skipped
+            .into_iter()
+            .enumerate()
+            .map(|(i, (song, counts))| {
+                format!(
+                    "{}. {} - {}: skipped {} of {} plays ({:.0}%)",
+                    i + 1,
+                    song.artist,
+                    song.title,
+                    counts.skips,
+                    counts.plays,
+                    counts.skips as f64 / counts.plays as f64 * 100.0
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let title = match days {
+        Some(days) => format!("Most skipped songs of the last {} days", days),
+        None => "Most skipped songs".to_string(),
+    };
+
+    ctx.send(
+        CreateReply::default()
+            .embed(CreateEmbed::new().title(title).description(description))
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,419 @@
+use std::path::{Path, PathBuf};
+
+use ellipse::Ellipse;
+use poise::{
+    serenity_prelude::{Attachment, AutocompleteChoice, CreateEmbed},
+    CreateReply,
+};
+use tracing_unwrap::ResultExt;
+
+use crate::prelude::*;
+use judeharley::{
+    communication::{ByersUnixStream, LiquidsoapCommunication},
+    controllers::song_metadata_overrides::{FieldChange, MetadataEdit},
+    maintenance::{indexing, overrides::parse_sidecar},
+    sea_orm::DatabaseConnection,
+    DiscordTimestamp, JudeHarleyError, SongMetadataEdits, SongMetadataOverrides, Songs,
+};
+
+/// Sidecar files are text, anything bigger is most likely the wrong file.
+const MAX_SIDECAR_SIZE: u32 = 1024 * 1024;
+
+/// Edits song metadata on top of the files' tags
+#[poise::command(
+    slash_command,
+    ephemeral,
+    owners_only,
+    subcommands("edit", "history", "import"),
+    subcommand_required
+)]
+pub async fn song(_: ApplicationContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn reply(ctx: ApplicationContext<'_>, embed: CreateEmbed) -> Result<(), Error> {
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Songs to edit, including hidden ones which are only found by their path.
+async fn autocomplete_editable_songs(
+    ctx: ApplicationContext<'_>,
+    partial: &str,
+) -> impl Iterator<Item = AutocompleteChoice> {
+    let data = ctx.data;
+
+    let songs = match Songs::search(partial, 20, &data.db).await {
+        Err(JudeHarleyError::InvalidSearch(_)) => vec![],
+        songs => songs.expect_or_log("Failed to query database"),
+    };
+    let hidden = SongMetadataOverrides::search_hidden(partial, 5, &data.db)
+        .await
+        .expect_or_log("Failed to query database");
+
+    songs
+        .into_iter()
+        .map(|song| {
+            AutocompleteChoice::new(
+                format!("{} - {}", song.artist, song.title)
+                    .as_str()
+                    .truncate_ellipse(97),
+                song.file_hash,
+            )
+        })
+        .chain(hidden.into_iter().map(|song| {
+            AutocompleteChoice::new(
+                format!("(hidden) {}", song.file_path)
+                    .as_str()
+                    .truncate_ellipse(97),
+                song.song_id,
+            )
+        }))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// A song whose metadata can be edited, hidden songs that weren't indexed
+/// since they were hidden are only known by their overrides.
+pub(crate) struct EditTarget {
+    pub song_id: String,
+    pub file_path: String,
+    pub name: String,
+}
+
+impl EditTarget {
+    fn from_song(song: Songs) -> Self {
+        let name = format!("{} - {}", song.artist, song.title);
+        Self {
+            name: if song.hidden {
+                format!("{} (hidden)", name)
+            } else {
+                name
+            },
+            song_id: song.file_hash,
+            file_path: song.file_path,
+        }
+    }
+
+    fn from_hidden(song: SongMetadataOverrides) -> Self {
+        Self {
+            name: format!("{} (hidden)", song.file_path),
+            song_id: song.song_id,
+            file_path: song.file_path,
+        }
+    }
+
+    pub async fn by_id(song_id: &str, db: &DatabaseConnection) -> Result<Option<Self>, Error> {
+        if let Some(song) = Songs::get_by_hash(song_id, db).await? {
+            return Ok(Some(Self::from_song(song)));
+        }
+
+        Ok(SongMetadataOverrides::get(song_id, db)
+            .await?
+            .map(Self::from_hidden))
+    }
+
+    async fn by_path(file_path: &str, db: &DatabaseConnection) -> Result<Option<Self>, Error> {
+        if let Some(song) = Songs::get(file_path, db).await? {
+            return Ok(Some(Self::from_song(song)));
+        }
+
+        Ok(SongMetadataOverrides::get_by_path(file_path, db)
+            .await?
+            .map(Self::from_hidden))
+    }
+
+    /// Indexes the song's file again so its overrides take effect.
+    async fn reindex(&self, db: &DatabaseConnection) -> Result<(), Error> {
+        indexing::reindex_file(db, Path::new(&self.file_path), Path::new("/music")).await?;
+
+        Ok(())
+    }
+
+    /// Makes `changes` to the song's overrides take effect.
+    pub async fn apply(
+        &self,
+        changes: &[FieldChange],
+        data: &Data<ByersUnixStream>,
+    ) -> Result<(), Error> {
+        self.reindex(&data.db).await?;
+        if changes.iter().any(|change| change.field == "hidden") {
+            reload_playlist(data).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn describe_value(value: Option<&str>) -> String {
+    value
+        .map(|value| format!("`{}`", value))
+        .unwrap_or_else(|| "the file's tag".to_string())
+}
+
+fn describe_change(field: &str, old_value: Option<&str>, new_value: Option<&str>) -> String {
+    format!(
+        "**{}**: {} → {}",
+        field,
+        describe_value(old_value),
+        describe_value(new_value)
+    )
+}
+
+/// Hidden songs are left out of the playlist, so it has to be written again.
+async fn reload_playlist(data: &Data<ByersUnixStream>) -> Result<(), Error> {
+    let playlist_path = PathBuf::from("/music/playlist.m3u");
+    indexing::create_playlist(&data.db, &playlist_path).await?;
+    data.comms
+        .lock()
+        .await
+        .send_wait("playlist.m3u.reload")
+        .await?;
+
+    Ok(())
+}
+
+/// Overrides a song's title, artist or album, or hides it
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn edit(
+    ctx: ApplicationContext<'_>,
+    #[description = "The song to edit"]
+    #[autocomplete = "autocomplete_editable_songs"]
+    song: String,
+    #[description = "The title to show instead of the file's"] title: Option<String>,
+    #[description = "The artist to show instead of the file's"] artist: Option<String>,
+    #[description = "The album to show instead of the file's"] album: Option<String>,
+    #[description = "Whether the song is left out of search, requests and the rotation"]
+    hidden: Option<bool>,
+    #[description = "Removes all overrides, going back to the file's tags"] reset: Option<bool>,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let Some(target) = EditTarget::by_id(&song, &data.db).await? else {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Song Metadata")
+                .description("Song not found."),
+        )
+        .await;
+    };
+
+    let user_id = Some(ctx.author().id.get() as i64);
+    let changes = if reset.unwrap_or_default() {
+        SongMetadataOverrides::reset(&target.song_id, user_id, &data.db).await?
+    } else {
+        let edit = MetadataEdit {
+            title,
+            artist,
+            album,
+            hidden,
+        };
+        SongMetadataOverrides::edit(&target.song_id, &target.file_path, &edit, user_id, &data.db)
+            .await?
+    };
+
+    if changes.is_empty() {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Song Metadata")
+                .description(format!("Nothing changed about {}.", target.name)),
+        )
+        .await;
+    }
+
+    ctx.defer_ephemeral().await?;
+    target.apply(&changes, data).await?;
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title("Song Metadata")
+            .description(format!(
+                "Changed {}:\n\n{}",
+                target.name,
+                changes
+                    .iter()
+                    .map(|change| describe_change(
+                        change.field,
+                        change.old_value.as_deref(),
+                        change.new_value.as_deref()
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )),
+    )
+    .await
+}
+
+/// Shows who changed a song's metadata and how
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn history(
+    ctx: ApplicationContext<'_>,
+    #[description = "The song to show the edits of"]
+    #[autocomplete = "autocomplete_editable_songs"]
+    song: String,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let Some(target) = EditTarget::by_id(&song, &data.db).await? else {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Song Metadata")
+                .description("Song not found."),
+        )
+        .await;
+    };
+
+    let edits = SongMetadataEdits::for_song(&target.song_id, 15, &data.db).await?;
+    let description = if edits.is_empty() {
+        format!("{} has never been edited.", target.name)
+    } else {
+        edits
+            .iter()
+            .map(|edit| {
+                format!(
+                    "{} {}: {}",
+                    edit.created_at.relative_time(),
+                    edit.user_id
+                        .map(|user_id| format!("<@{}>", user_id))
+                        .unwrap_or_else(|| "Someone".to_string()),
+                    describe_change(
+                        &edit.field,
+                        edit.old_value.as_deref(),
+                        edit.new_value.as_deref()
+                    )
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title(
+                format!("Edits of {}", target.name)
+                    .as_str()
+                    .truncate_ellipse(253),
+            )
+            .description(description),
+    )
+    .await
+}
+
+/// Overrides the metadata of many songs from a TOML or CSV sidecar file
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn import(
+    ctx: ApplicationContext<'_>,
+    #[description = "A .toml or .csv file listing the songs by id or path"] file: Attachment,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    if file.size > MAX_SIDECAR_SIZE {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("File too big")
+                .description("Sidecar files can be up to 1 MiB."),
+        )
+        .await;
+    }
+
+    ctx.defer_ephemeral().await?;
+    let contents = String::from_utf8_lossy(&file.download().await?).to_string();
+    let entries = match parse_sidecar(&file.filename, &contents) {
+        Ok(entries) => entries,
+        Err(e) => {
+            return reply(
+                ctx,
+                CreateEmbed::new()
+                    .title("Song Metadata")
+                    .description(e.to_string()),
+            )
+            .await;
+        }
+    };
+
+    let user_id = Some(ctx.author().id.get() as i64);
+    let mut updated = 0;
+    let mut hidden_changed = false;
+    let mut missing = vec![];
+    for entry in &entries {
+        let target = match (&entry.id, &entry.path) {
+            (Some(id), _) => EditTarget::by_id(id, &data.db).await?,
+            (None, Some(path)) => EditTarget::by_path(path, &data.db).await?,
+            (None, None) => None,
+        };
+        let Some(target) = target else {
+            missing.push(entry.id.clone().or(entry.path.clone()).unwrap_or_default());
+            continue;
+        };
+
+        let changes = SongMetadataOverrides::edit(
+            &target.song_id,
+            &target.file_path,
+            &entry.edit(),
+            user_id,
+            &data.db,
+        )
+        .await?;
+        if changes.is_empty() {
+            continue;
+        }
+
+        target.reindex(&data.db).await?;
+        updated += 1;
+        hidden_changed |= changes.iter().any(|change| change.field == "hidden");
+    }
+    if hidden_changed {
+        reload_playlist(data).await?;
+    }
+
+    let mut description = format!(
+        "Updated {} of the {} songs in `{}`.",
+        updated,
+        entries.len(),
+        file.filename
+    );
+    if !missing.is_empty() {
+        description.push_str(&format!(
+            "\n\n{} songs weren't found:\n{}",
+            missing.len(),
+            missing
+                .iter()
+                .map(|song| format!("- `{}`", song))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+    }
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title("Song Metadata")
+            .description(description.as_str().truncate_ellipse(4000)),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_change() {
+        assert_eq!(
+            describe_change("title", None, Some("Showtime (Original Mix)")),
+            "**title**: the file's tag → `Showtime (Original Mix)`"
+        );
+        assert_eq!(
+            describe_change("hidden", Some("true"), Some("false")),
+            "**hidden**: `true` → `false`"
+        );
+    }
+}
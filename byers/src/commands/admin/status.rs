@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+
+use crate::prelude::*;
+use judeharley::{
+    communication::{ByersUnixStream, LiquidsoapCommunication},
+    format_duration,
+    health::{self, DependencyStatus},
+    DiscordTimestamp, IndexerRuns, PlayedSongs, Songs,
+};
+
+fn dependency_field(dependency: &DependencyStatus) -> (String, String, bool) {
+    let name = format!(
+        "{} {}",
+        if dependency.healthy { "✅" } else { "❌" },
+        dependency.name
+    );
+
+    let mut lines = vec![];
+    if let Some(error) = &dependency.error {
+        lines.push(format!("Error: {}", error));
+    }
+    if let Some(version) = &dependency.version {
+        lines.push(format!("Version: {}", version));
+    }
+    if let Some(uptime) = dependency.uptime_seconds {
+        lines.push(format!("Uptime: {}", format_duration(uptime)));
+    }
+    lines.push(format!("Latency: {} ms", dependency.latency_ms));
+
+    (name, lines.join("\n"), true)
+}
+
+async fn current_track(data: &Data<ByersUnixStream>) -> Result<String, Error> {
+    let Some(latest) = PlayedSongs::get_latest(&data.db).await? else {
+        return Ok("Nothing has been played yet.".to_string());
+    };
+    let Some(song) = Songs::get_by_hash(&latest.song_id, &data.db).await? else {
+        return Ok(format!(
+            "Unknown song `{}`, started {}",
+            latest.song_id,
+            latest.played_at.relative_time()
+        ));
+    };
+
+    let age = (Utc::now().naive_utc() - latest.played_at)
+        .num_seconds()
+        .max(0) as u64;
+    let mut description = format!(
+        "{} - {}\nStarted {} ({} of {})",
+        song.artist,
+        song.title,
+        latest.played_at.relative_time(),
+        format_duration(age),
+        format_duration(song.duration as u64)
+    );
+    if age > song.duration as u64 + 60 {
+        description.push_str("\n⚠️ Overdue, Liquidsoap may have stopped reporting plays");
+    }
+
+    Ok(description)
+}
+
+/// Counts the queued requests per queue, over a connection of its own so a
+/// hung Liquidsoap can't block the shared one.
+async fn queue_lengths() -> Result<String, Error> {
+    let queue = tokio::time::timeout(health::CHECK_TIMEOUT, async {
+        let mut stream = ByersUnixStream::new().await?;
+        stream.song_requests().await
+    })
+    .await??;
+
+    let mut lengths = BTreeMap::from([("prioq".to_string(), 0), ("srq".to_string(), 0)]);
+    for item in queue {
+        *lengths.entry(item.queue).or_insert(0) += 1;
+    }
+
+    Ok(lengths
+        .into_iter()
+        .map(|(queue, length)| format!("{}: {}", queue, length))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+async fn last_indexer_run(data: &Data<ByersUnixStream>) -> Result<String, Error> {
+    let Some(run) = IndexerRuns::get_latest(&data.db).await? else {
+        return Ok("The library has not been indexed yet.".to_string());
+    };
+
+    let Some(finished_at) = run.finished_at else {
+        return Ok(format!("Running since {}", run.started_at.relative_time()));
+    };
+
+    let mut description = format!(
+        "Finished {}, took {}\nIndexed {} files, {} failed",
+        finished_at.relative_time(),
+        format_duration((finished_at - run.started_at).num_seconds().max(0) as u64),
+        run.indexed_files.unwrap_or_default(),
+        run.failed_files.unwrap_or_default()
+    );
+    if let Some(error) = run.error {
+        description.push_str(&format!("\n❌ {}", error));
+    }
+
+    Ok(description)
+}
+
+fn or_error(result: Result<String, Error>) -> String {
+    result.unwrap_or_else(|e| format!("❌ {}", e))
+}
+
+/// Shows the state of the radio and everything it depends on
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn status(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+    ctx.defer_ephemeral().await?;
+
+    let (dependencies, current_track, queue_lengths, last_indexer_run) = tokio::join!(
+        health::check_dependencies(&data.db, &data.redis_pool),
+        current_track(data),
+        queue_lengths(),
+        last_indexer_run(data)
+    );
+
+    let healthy = dependencies.iter().all(|d| d.healthy);
+    let embed = CreateEmbed::new()
+        .title(if healthy {
+            "All systems operational"
+        } else {
+            "Something is broken"
+        })
+        .color(if healthy { (0, 200, 83) } else { (255, 0, 0) })
+        .description(format!(
+            "Byers {}, up for {}",
+            env!("CARGO_PKG_VERSION"),
+            format_duration(health::uptime().as_secs())
+        ))
+        .fields(dependencies.iter().map(dependency_field))
+        .field("Now Playing", or_error(current_track), false)
+        .field("Queues", or_error(queue_lengths), true)
+        .field("Last Indexer Run", or_error(last_indexer_run), true);
+
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
@@ -27,6 +27,8 @@ pub enum UserParameter {
     Migrated,
 }
 
+// the grist commands aren't registered until grist is back
+#[allow(dead_code)]
 #[derive(Debug, Clone, poise::ChoiceParameter, strum::Display)]
 pub enum UserGristParameter {
     #[name = "Amber Grist"]
@@ -0,0 +1,230 @@
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+
+use crate::prelude::*;
+use judeharley::{
+    webhooks::{self, WebhookEvent},
+    DiscordTimestamp, WebhookDeliveries, Webhooks,
+};
+
+/// Manages outgoing webhooks for radio events
+#[poise::command(
+    slash_command,
+    ephemeral,
+    owners_only,
+    subcommands("add", "list", "remove", "set_enabled", "deliveries"),
+    subcommand_required
+)]
+pub async fn webhooks(_: ApplicationContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn reply(ctx: ApplicationContext<'_>, embed: CreateEmbed) -> Result<(), Error> {
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+fn all_events() -> String {
+    WebhookEvent::ALL
+        .iter()
+        .map(|event| event.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Adds a webhook, its signing secret is only shown once
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn add(
+    ctx: ApplicationContext<'_>,
+    #[description = "The URL events are POSTed to"] url: String,
+    #[description = "Comma separated events, e.g. song.started,request.queued (default: all)"]
+    events: Option<String>,
+    #[description = "Secret to sign payloads with (default: random)"] secret: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let is_http = reqwest::Url::parse(&url)
+        .is_ok_and(|parsed| parsed.scheme() == "http" || parsed.scheme() == "https");
+    if !is_http {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Invalid URL")
+                .description("The URL must be an absolute http or https URL."),
+        )
+        .await;
+    }
+
+    let events = match webhooks::parse_events(&events.unwrap_or_else(all_events)) {
+        Ok(events) => events
+            .iter()
+            .map(|event| event.as_str())
+            .collect::<Vec<_>>()
+            .join(","),
+        Err(e) => {
+            return reply(
+                ctx,
+                CreateEmbed::new()
+                    .title("Invalid events")
+                    .description(format!("{}, known events are `{}`.", e, all_events())),
+            )
+            .await;
+        }
+    };
+
+    let secret = secret
+        .filter(|secret| !secret.is_empty())
+        .unwrap_or_else(webhooks::generate_secret);
+    let webhook = Webhooks::create(&url, &events, &secret, &data.db).await?;
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title(format!("Webhook #{} added", webhook.id))
+            .description(format!(
+                "Events: `{}`\nSecret: ||`{}`||\n\nEvery request carries an `X-LumiRadio-Timestamp` header and an `X-LumiRadio-Signature: sha256=<hex>` header, the HMAC-SHA256 of `{{timestamp}}.{{body}}` keyed with the secret.",
+                webhook.events, secret
+            )),
+    )
+    .await
+}
+
+/// Lists all webhooks
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn list(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let webhooks = Webhooks::get_all(&data.db).await?;
+    let description = if webhooks.is_empty() {
+        "No webhooks are configured.".to_string()
+    } else {
+        webhooks
+            .iter()
+            .map(|webhook| {
+                format!(
+                    "**#{}** {} {}\nEvents: `{}`, added {}",
+                    webhook.id,
+                    if webhook.enabled { "✅" } else { "⏸️" },
+                    webhook.url,
+                    webhook.events,
+                    webhook.created_at.relative_time()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    };
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title("Webhooks")
+            .description(description),
+    )
+    .await
+}
+
+/// Removes a webhook along with its delivery log
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn remove(
+    ctx: ApplicationContext<'_>,
+    #[description = "The webhook's ID"] id: i32,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let description = if Webhooks::delete(id, &data.db).await? {
+        format!("Webhook #{} has been removed.", id)
+    } else {
+        format!("There is no webhook #{}.", id)
+    };
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title("Webhooks")
+            .description(description),
+    )
+    .await
+}
+
+/// Pauses or resumes deliveries to a webhook
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn set_enabled(
+    ctx: ApplicationContext<'_>,
+    #[description = "The webhook's ID"] id: i32,
+    #[description = "Whether events are delivered to it"] enabled: bool,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let description = if !Webhooks::set_enabled(id, enabled, &data.db).await? {
+        format!("There is no webhook #{}.", id)
+    } else if enabled {
+        format!("Webhook #{} has been enabled.", id)
+    } else {
+        format!(
+            "Webhook #{} has been disabled, its pending deliveries wait until it's enabled again.",
+            id
+        )
+    };
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title("Webhooks")
+            .description(description),
+    )
+    .await
+}
+
+/// Shows the latest deliveries to a webhook
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn deliveries(
+    ctx: ApplicationContext<'_>,
+    #[description = "The webhook's ID"] id: i32,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let Some(webhook) = Webhooks::get(id, &data.db).await? else {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Webhooks")
+                .description(format!("There is no webhook #{}.", id)),
+        )
+        .await;
+    };
+
+    let deliveries = WebhookDeliveries::get_latest_for_webhook(webhook.id, 10, &data.db).await?;
+    let description = if deliveries.is_empty() {
+        "Nothing has been delivered yet.".to_string()
+    } else {
+        deliveries
+            .iter()
+            .map(|delivery| {
+                let mut line = format!(
+                    "`{}` {} {}, {} attempt(s)",
+                    delivery.event,
+                    delivery.status,
+                    delivery.created_at.relative_time(),
+                    delivery.attempts
+                );
+                if let Some(status) = delivery.response_status {
+                    line.push_str(&format!(", last response {}", status));
+                }
+                if let Some(error) = &delivery.last_error {
+                    line.push_str(&format!("\n↳ {}", error));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title(format!("Deliveries to webhook #{}", webhook.id))
+            .description(format!("{}\n\n{}", webhook.url, description)),
+    )
+    .await
+}
@@ -0,0 +1,419 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use ellipse::Ellipse;
+use futures::StreamExt;
+use poise::serenity_prelude::{
+    ButtonStyle, ComponentInteractionDataKind, CreateActionRow, CreateButton, CreateEmbed,
+    CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+use poise::CreateReply;
+
+use crate::commands::{
+    autocomplete_albums, find_album, live::requests_paused, songs::request_song,
+};
+use crate::event_handlers::message::update_activity;
+use crate::prelude::*;
+use judeharley::{format_duration, IndexedAlbums, PlayedSongs, Songs, Users};
+
+const TRACKS_PER_PAGE: usize = 10;
+
+/// Album browsing
+#[poise::command(slash_command, subcommands("search", "show"), subcommand_required)]
+pub async fn album(_: ApplicationContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Formats a track's length like `3:07` or `1:02:03`.
+fn format_length(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3_600, seconds % 3_600 / 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Where a song is on its album, falling back to its place in the listing.
+fn position(song: &Songs, index: usize) -> String {
+    match (song.disc_number, song.track_number) {
+        (Some(disc), Some(track)) => format!("{}-{}", disc, track),
+        (None, Some(track)) => track.to_string(),
+        _ => (index + 1).to_string(),
+    }
+}
+
+fn track_line(song: &Songs, index: usize, plays: i64, favourite: bool) -> String {
+    // compilations list each song's own artist
+    let title = if song.artist != song.album_artist() {
+        format!("{} - {}", song.artist, song.title)
+    } else {
+        song.title.clone()
+    };
+
+    format!(
+        "**{}.** {} ({}) · {} play{}{}",
+        position(song, index),
+        title,
+        format_length(song.duration),
+        plays,
+        if plays == 1 { "" } else { "s" },
+        if favourite { " ⭐" } else { "" }
+    )
+}
+
+struct AlbumView {
+    album: String,
+    songs: Vec<Songs>,
+    lines: Vec<String>,
+    requests_open: bool,
+}
+
+impl AlbumView {
+    fn pages(&self) -> usize {
+        self.songs.len().div_ceil(TRACKS_PER_PAGE)
+    }
+
+    fn page_range(&self, page: usize) -> std::ops::Range<usize> {
+        let start = page * TRACKS_PER_PAGE;
+        start..(start + TRACKS_PER_PAGE).min(self.songs.len())
+    }
+
+    fn embed(&self, page: usize) -> CreateEmbed {
+        let runtime = self.songs.iter().map(|song| song.duration).sum::<f64>();
+        let mut embed = CreateEmbed::new()
+            .title(self.album.as_str().truncate_ellipse(253))
+            .description(self.lines[self.page_range(page)].join("\n"))
+            .footer(CreateEmbedFooter::new(format!(
+                "Page {}/{} · {} tracks · {}",
+                page + 1,
+                self.pages(),
+                self.songs.len(),
+                format_duration(runtime as u64)
+            )));
+        if let Some(first) = self.songs.first() {
+            embed = embed.field("Artist", first.album_artist(), true);
+            if let Some(year) = first.year {
+                embed = embed.field("Year", year.to_string(), true);
+            }
+        }
+
+        embed
+    }
+
+    fn components(&self, page: usize) -> Vec<CreateActionRow> {
+        let mut components = vec![];
+        if self.requests_open {
+            let options = self
+                .page_range(page)
+                .map(|index| {
+                    let song = &self.songs[index];
+                    CreateSelectMenuOption::new(
+                        format!("{}. {}", position(song, index), song.title)
+                            .as_str()
+                            .truncate_ellipse(97),
+                        &song.file_hash,
+                    )
+                })
+                .collect();
+            components.push(CreateActionRow::SelectMenu(
+                CreateSelectMenu::new("album_request", CreateSelectMenuKind::String { options })
+                    .placeholder("Request a track")
+                    .min_values(1)
+                    .max_values(1),
+            ));
+        }
+        if self.pages() > 1 {
+            components.push(CreateActionRow::Buttons(vec![
+                CreateButton::new("album_previous")
+                    .label("Previous")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page == 0),
+                CreateButton::new("album_next")
+                    .label("Next")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page + 1 >= self.pages()),
+            ]));
+        }
+
+        components
+    }
+}
+
+async fn album_not_found(ctx: ApplicationContext<'_>, album: &str) -> Result<(), Error> {
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::new()
+                .title("Albums")
+                .description(format!("There is no album called \"{}\".", album)),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn show_album(ctx: ApplicationContext<'_>, album: &IndexedAlbums) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let songs = Songs::get_by_album(&album.artist, &album.album, &data.db).await?;
+    if songs.is_empty() {
+        return album_not_found(ctx, &album.album).await;
+    }
+
+    let user = Users::get_or_insert(ctx.author().id.get(), &data.db).await?;
+    let favourites = user
+        .list_favourites(&data.db)
+        .await?
+        .into_iter()
+        .map(|song| song.file_hash)
+        .collect::<HashSet<_>>();
+    let plays: HashMap<String, i64> = PlayedSongs::count_by_songs(
+        songs.iter().map(|song| song.file_hash.clone()).collect(),
+        &data.db,
+    )
+    .await?;
+    let lines = songs
+        .iter()
+        .enumerate()
+        .map(|(index, song)| {
+            track_line(
+                song,
+                index,
+                plays.get(&song.file_hash).copied().unwrap_or(0),
+                favourites.contains(&song.file_hash),
+            )
+        })
+        .collect();
+    let view = AlbumView {
+        album: album.album.clone(),
+        songs,
+        lines,
+        requests_open: requests_paused(&data.db).await?.is_none(),
+    };
+
+    let mut page = 0;
+    let handle = ctx
+        .send(
+            CreateReply::default()
+                .embed(view.embed(page))
+                .components(view.components(page)),
+        )
+        .await?;
+    let message = handle.message().await?;
+    while let Some(mci) = message
+        .await_component_interactions(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(120))
+        .stream()
+        .next()
+        .await
+    {
+        match mci.data.custom_id.as_str() {
+            "album_previous" | "album_next" => {
+                page = if mci.data.custom_id == "album_next" {
+                    (page + 1).min(view.pages() - 1)
+                } else {
+                    page.saturating_sub(1)
+                };
+                mci.create_response(
+                    ctx.serenity_context(),
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .embed(view.embed(page))
+                            .components(view.components(page)),
+                    ),
+                )
+                .await?;
+            }
+            "album_request" => {
+                let ComponentInteractionDataKind::StringSelect { values } = &mci.data.kind else {
+                    continue;
+                };
+                let Some(song) = values.first().cloned() else {
+                    continue;
+                };
+                mci.create_response(
+                    ctx.serenity_context(),
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new().components(vec![]),
+                    ),
+                )
+                .await?;
+
+                return request_song(ctx, song).await;
+            }
+            _ => {}
+        }
+    }
+
+    handle
+        .edit(
+            poise::Context::Application(ctx),
+            CreateReply::default().components(vec![]),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Searches the albums in the library
+#[poise::command(slash_command)]
+pub async fn search(
+    ctx: ApplicationContext<'_>,
+    #[description = "The album to search for"] search: String,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    update_activity(data, ctx.author().id, ctx.channel_id()).await?;
+
+    let albums = IndexedAlbums::search(&search, 25, &data.db).await?;
+    if albums.is_empty() {
+        ctx.send(
+            CreateReply::default().embed(
+                CreateEmbed::new()
+                    .title("Album Search")
+                    .description("No albums were found matching your search."),
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let description = albums
+        .iter()
+        .enumerate()
+        .map(|(i, album)| format!("{}. {} - {}", i + 1, album.artist, album.album))
+        .collect::<Vec<_>>()
+        .join("\n");
+    // album names can be longer than option values may be
+    let options = albums
+        .iter()
+        .enumerate()
+        .map(|(i, album)| {
+            CreateSelectMenuOption::new(
+                format!("{} - {}", album.artist, album.album)
+                    .as_str()
+                    .truncate_ellipse(97),
+                i.to_string(),
+            )
+        })
+        .collect();
+    let handle = ctx
+        .send(
+            CreateReply::default()
+                .embed(
+                    CreateEmbed::new()
+                        .title("Album Search")
+                        .description(format!(
+                            "Albums matching `{}`:\n\n{}\n\nSelect one below within 2 minutes to see its tracks.",
+                            search, description
+                        )),
+                )
+                .components(vec![CreateActionRow::SelectMenu(
+                    CreateSelectMenu::new("album_show", CreateSelectMenuKind::String { options })
+                        .placeholder("Select an album")
+                        .min_values(1)
+                        .max_values(1),
+                )]),
+        )
+        .await?;
+    let message = handle.message().await?;
+    let Some(mci) = message
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(120))
+        .await
+    else {
+        handle
+            .edit(
+                poise::Context::Application(ctx),
+                CreateReply::default().components(vec![]),
+            )
+            .await?;
+
+        return Ok(());
+    };
+
+    let album = match &mci.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } => values
+            .first()
+            .and_then(|value| value.parse::<usize>().ok())
+            .and_then(|i| albums.get(i)),
+        _ => None,
+    }
+    .ok_or(anyhow::anyhow!("Failed to find album"))?;
+    mci.create_response(
+        ctx.serenity_context(),
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new().components(vec![]),
+        ),
+    )
+    .await?;
+
+    show_album(ctx, album).await
+}
+
+/// Shows an album's tracks, and lets you request one of them
+#[poise::command(slash_command)]
+pub async fn show(
+    ctx: ApplicationContext<'_>,
+    #[description = "The album to show"]
+    #[rest]
+    #[autocomplete = "autocomplete_albums"]
+    album: String,
+) -> Result<(), Error> {
+    update_activity(ctx.data, ctx.author().id, ctx.channel_id()).await?;
+
+    match find_album(&album, &ctx.data.db).await? {
+        Some(album) => show_album(ctx, &album).await,
+        None => album_not_found(ctx, &album).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(title: &str, artist: &str, track: Option<i32>) -> Songs {
+        Songs {
+            file_path: format!("/music/{title}.mp3"),
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: "Homestuck Vol. 5".to_string(),
+            played: 0,
+            requested: 0,
+            tsvector: None,
+            duration: 187.4,
+            file_hash: title.to_string(),
+            bitrate: 320,
+            track_number: track,
+            disc_number: None,
+            year: Some(2010),
+            genre: None,
+            album_artist: Some("Homestuck".to_string()),
+            hidden: false,
+        }
+    }
+
+    #[test]
+    fn test_format_length() {
+        assert_eq!(format_length(187.4), "3:07");
+        assert_eq!(format_length(59.6), "1:00");
+        assert_eq!(format_length(3_723.0), "1:02:03");
+    }
+
+    #[test]
+    fn test_track_line() {
+        assert_eq!(
+            track_line(&song("Descend", "Homestuck", Some(14)), 0, 1, true),
+            "**14.** Descend (3:07) · 1 play ⭐"
+        );
+        assert_eq!(
+            track_line(&song("Sburban Jungle", "Toby Fox", None), 2, 0, false),
+            "**3.** Toby Fox - Sburban Jungle (3:07) · 0 plays"
+        );
+    }
+}
@@ -8,10 +8,7 @@ use rand::{thread_rng, Rng};
 /// Chirps back at the person who chirps
 #[poise::command(slash_command)]
 pub async fn chirp(ctx: ApplicationContext<'_>) -> Result<(), Error> {
-    
-    if let Some(guild_id) = ctx.guild_id() {
-        update_activity(ctx.data, ctx.author().id, ctx.channel_id(), guild_id).await?;
-    }
+    update_activity(ctx.data, ctx.author().id, ctx.channel_id()).await?;
 
     // if author is @canadajones68
     if ctx.author().id == 329162131096338434 {
@@ -0,0 +1,109 @@
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+use tracing::error;
+
+use crate::prelude::*;
+use judeharley::{DiscordTimestamp, ListenbrainzAccounts, ScrobbleQueue, Users};
+
+/// Scrobble what you listen to on the radio to ListenBrainz
+#[poise::command(slash_command, subcommands("link", "unlink", "status"))]
+pub async fn listenbrainz(_: ApplicationContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn reply(ctx: ApplicationContext<'_>, title: &str, description: String) -> Result<(), Error> {
+    ctx.send(
+        CreateReply::default()
+            .embed(CreateEmbed::new().title(title).description(description))
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Link your ListenBrainz account, songs played while you are active are scrobbled to it
+#[poise::command(slash_command, ephemeral)]
+pub async fn link(
+    ctx: ApplicationContext<'_>,
+    #[description = "Your user token, found on your ListenBrainz settings page"] token: String,
+) -> Result<(), Error> {
+    let data = ctx.data;
+    ctx.defer_ephemeral().await?;
+
+    let token = token.trim();
+    let user_name = match data.listenbrainz.validate_token(token).await {
+        Ok(Some(user_name)) => user_name,
+        Ok(None) => {
+            return reply(
+                ctx,
+                "Invalid token",
+                "ListenBrainz did not accept that token, please copy it from https://listenbrainz.org/settings/ and try again.".to_string(),
+            )
+            .await;
+        }
+        Err(e) => {
+            error!(error = %e, "failed to validate ListenBrainz token");
+            return reply(
+                ctx,
+                "ListenBrainz is unavailable",
+                "Your token could not be checked right now, please try again later.".to_string(),
+            )
+            .await;
+        }
+    };
+
+    let user = Users::get_or_insert(ctx.author().id.get(), &data.db).await?;
+    ListenbrainzAccounts::link(user.id as u64, token, &user_name, &data.db).await?;
+
+    reply(
+        ctx,
+        "ListenBrainz linked",
+        format!(
+            "Songs played while you are chatting in here are now scrobbled to **{}**.",
+            user_name
+        ),
+    )
+    .await
+}
+
+/// Stop scrobbling to ListenBrainz and forget your token
+#[poise::command(slash_command, ephemeral)]
+pub async fn unlink(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+    let user_id = ctx.author().id.get();
+
+    if !ListenbrainzAccounts::unlink(user_id, &data.db).await? {
+        return reply(
+            ctx,
+            "Not linked",
+            "You have not linked a ListenBrainz account.".to_string(),
+        )
+        .await;
+    }
+    ScrobbleQueue::delete_for_user(user_id, &data.db).await?;
+
+    reply(
+        ctx,
+        "ListenBrainz unlinked",
+        "Your token has been removed and nothing will be scrobbled anymore.".to_string(),
+    )
+    .await
+}
+
+/// Shows which ListenBrainz account you have linked
+#[poise::command(slash_command, ephemeral)]
+pub async fn status(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let description = match ListenbrainzAccounts::get(ctx.author().id.get(), &data.db).await? {
+        Some(account) => format!(
+            "Scrobbling to **{}** since {}.",
+            account.user_name,
+            account.linked_at.relative_time()
+        ),
+        None => "You have not linked a ListenBrainz account, use `/listenbrainz link` to do so."
+            .to_string(),
+    };
+
+    reply(ctx, "ListenBrainz", description).await
+}
@@ -0,0 +1,194 @@
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+use tracing::warn;
+
+use crate::prelude::*;
+use judeharley::{
+    communication::LiquidsoapCommunication, sea_orm::DatabaseConnection, DiscordTimestamp, LiveDjs,
+    LiveSessions, PlayedSongs,
+};
+
+/// Live DJ sessions
+#[poise::command(
+    slash_command,
+    subcommands("start", "stop", "status"),
+    subcommand_required
+)]
+pub async fn live(_: ApplicationContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn reply(ctx: ApplicationContext<'_>, embed: CreateEmbed) -> Result<(), Error> {
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Why requests can't be made right now, if a DJ is on air.
+pub async fn requests_paused(db: &DatabaseConnection) -> Result<Option<String>, Error> {
+    Ok(LiveSessions::current(db).await?.map(|session| {
+        format!(
+            "{} is live right now, requests are paused until the show ends.",
+            session.dj_name
+        )
+    }))
+}
+
+/// Goes on air, your encoder may connect to the harbor until you stop
+#[poise::command(slash_command, ephemeral)]
+pub async fn start(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let Some(dj) = LiveDjs::get(ctx.author().id.get(), &data.db).await? else {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Not a DJ")
+                .description("You need harbor credentials to go live, ask an admin for some."),
+        )
+        .await;
+    };
+    if let Some(session) = LiveSessions::current(&data.db).await? {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Already live")
+                .description(format!(
+                    "{} has been live since {}.",
+                    session.dj_name,
+                    session.started_at.relative_time()
+                )),
+        )
+        .await;
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    {
+        let mut comms = data.comms.lock().await;
+        comms.set_live(true).await?;
+    }
+    // only record the session once Liquidsoap is live, and go back to
+    // automation if that fails
+    let session = match LiveSessions::start(ctx.author().id.get(), &dj.name, now, &data.db).await {
+        Ok(session) => session,
+        Err(e) => {
+            let mut comms = data.comms.lock().await;
+            if let Err(e) = comms.set_live(false).await {
+                warn!(error = %e, "failed to go back to automation after a failed live start");
+            }
+            return Err(e.into());
+        }
+    };
+    // the live input cuts off the song that was playing
+    if let Err(e) = PlayedSongs::finish_latest(now, &data.db).await {
+        warn!(error = %e, "failed to finish the play cut off by a live session");
+    }
+
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title("You're on air!")
+            .description(format!(
+                "Connect your encoder to the harbor's `/live` mount with the username `{}`. Song requests are paused until you run `/live stop`.",
+                dj.username
+            ))
+            .field("Session", format!("#{}", session.id), true)
+            .field("DJ", &session.dj_name, true),
+    )
+    .await
+}
+
+/// Ends the live session and goes back to automation
+#[poise::command(slash_command, ephemeral)]
+pub async fn stop(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let Some(session) = LiveSessions::current(&data.db).await? else {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Not live")
+                .description("Nobody is live right now."),
+        )
+        .await;
+    };
+    let is_owner = ctx.framework().options().owners.contains(&ctx.author().id);
+    if session.user_id != Some(ctx.author().id.get() as i64) && !is_owner {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Not your show")
+                .description(format!(
+                    "Only {} or an admin can end this show.",
+                    session.dj_name
+                )),
+        )
+        .await;
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    {
+        let mut comms = data.comms.lock().await;
+        comms.set_live(false).await?;
+    }
+    // the harbor turns the DJ away once the session is over, so kick them
+    // only after it ended
+    let session = session.end(now, &data.db).await?;
+    {
+        let mut comms = data.comms.lock().await;
+        if let Err(e) = comms.kick_dj().await {
+            warn!(error = %e, "failed to kick the DJ off the harbor");
+        }
+    }
+
+    let minutes = (now - session.started_at).num_minutes();
+    reply(
+        ctx,
+        CreateEmbed::new()
+            .title("Back to automation")
+            .description(format!(
+                "{}'s show ended after {} minutes, requests are open again.",
+                session.dj_name, minutes
+            )),
+    )
+    .await
+}
+
+/// Shows who is live, if anyone
+#[poise::command(slash_command)]
+pub async fn status(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let embed = match LiveSessions::current(&data.db).await? {
+        Some(session) => {
+            let harbor = {
+                let mut comms = data.comms.lock().await;
+                comms.harbor_status().await?
+            };
+            CreateEmbed::new()
+                .title(format!("{} is live!", session.dj_name))
+                .description(format!(
+                    "On air since {}, song requests are paused.",
+                    session.started_at.relative_time()
+                ))
+                .field("Harbor", harbor.trim().to_string(), false)
+        }
+        None => {
+            let mut embed = CreateEmbed::new()
+                .title("Automation")
+                .description("Nobody is live, the rotation and song requests are on air.");
+            if let Some(last) = LiveSessions::get_latest(1, &data.db).await?.first() {
+                embed = embed.field(
+                    "Last show",
+                    format!("{}, {}", last.dj_name, last.started_at.relative_time()),
+                    false,
+                );
+            }
+            embed
+        }
+    };
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,300 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use ellipse::Ellipse;
+use futures::StreamExt;
+use poise::serenity_prelude::{
+    ButtonStyle, CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter,
+    CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+use poise::CreateReply;
+
+use crate::commands::autocomplete_songs;
+use crate::event_handlers::message::update_activity;
+use crate::prelude::*;
+use judeharley::{
+    controllers::song_lyrics::{SOURCE_LRC, SOURCE_TAG},
+    maintenance::lyrics::{self, LyricLine},
+    PlayedSongs, Songs,
+};
+
+const MAX_LINES_PER_PAGE: usize = 30;
+/// Leaves some of the 4096 characters of a description for the highlight.
+const MAX_CHARS_PER_PAGE: usize = 3_800;
+const MAX_LINE_LENGTH: usize = 500;
+
+/// Splits the lines into pages of at most [`MAX_LINES_PER_PAGE`] lines and
+/// [`MAX_CHARS_PER_PAGE`] characters.
+fn paginate(lines: &[LyricLine]) -> Vec<Range<usize>> {
+    let mut pages = vec![];
+    let (mut start, mut chars) = (0, 0);
+    for (index, line) in lines.iter().enumerate() {
+        let length = line.text.chars().count().min(MAX_LINE_LENGTH) + 1;
+        if index > start
+            && (index - start >= MAX_LINES_PER_PAGE || chars + length > MAX_CHARS_PER_PAGE)
+        {
+            pages.push(start..index);
+            (start, chars) = (index, 0);
+        }
+        chars += length;
+    }
+    if start < lines.len() {
+        pages.push(start..lines.len());
+    }
+
+    pages
+}
+
+fn render_line(line: &LyricLine, current: bool) -> String {
+    let text = line.text.as_str().truncate_ellipse(MAX_LINE_LENGTH);
+    match (current, text.is_empty()) {
+        // synced lyrics mark instrumental parts with empty lines
+        (true, true) => "**▶ ♪**".to_string(),
+        (true, false) => format!("**▶ {}**", text),
+        (false, _) => text.to_string(),
+    }
+}
+
+fn describe_source(source: &str) -> &'static str {
+    match source {
+        SOURCE_TAG => "the file's tags",
+        SOURCE_LRC => "an .lrc file",
+        _ => "a .txt file",
+    }
+}
+
+struct LyricsView {
+    title: String,
+    source: String,
+    synced: bool,
+    lines: Vec<LyricLine>,
+    pages: Vec<Range<usize>>,
+    /// When the song started if it's playing right now, along with how long
+    /// it is.
+    playing: Option<(NaiveDateTime, f64)>,
+}
+
+impl LyricsView {
+    /// The line being sung right now, if the song is playing and its lyrics
+    /// are synced.
+    fn current_line(&self, now: NaiveDateTime) -> Option<usize> {
+        let (started_at, duration) = self.playing.filter(|_| self.synced)?;
+        let elapsed = (now - started_at).num_milliseconds() as f64 / 1000.0;
+        if elapsed > duration {
+            return None;
+        }
+
+        lyrics::current_line(&self.lines, elapsed)
+    }
+
+    fn page_of(&self, line: usize) -> usize {
+        self.pages
+            .iter()
+            .position(|page| page.contains(&line))
+            .unwrap_or(0)
+    }
+
+    fn embed(&self, page: usize, current: Option<usize>) -> CreateEmbed {
+        let description = self.pages[page]
+            .clone()
+            .map(|index| render_line(&self.lines[index], current == Some(index)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        CreateEmbed::new()
+            .title(self.title.as_str().truncate_ellipse(253))
+            .description(description)
+            .footer(CreateEmbedFooter::new(format!(
+                "Page {}/{} · From {}{}",
+                page + 1,
+                self.pages.len(),
+                describe_source(&self.source),
+                if self.synced { " · Synced" } else { "" }
+            )))
+    }
+
+    fn components(&self, page: usize) -> Vec<CreateActionRow> {
+        let mut buttons = vec![];
+        if self.pages.len() > 1 {
+            buttons.push(
+                CreateButton::new("lyrics_previous")
+                    .label("Previous")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page == 0),
+            );
+            buttons.push(
+                CreateButton::new("lyrics_next")
+                    .label("Next")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page + 1 >= self.pages.len()),
+            );
+        }
+        if self.synced && self.playing.is_some() {
+            buttons.push(
+                CreateButton::new("lyrics_now")
+                    .label("Now")
+                    .style(ButtonStyle::Primary),
+            );
+        }
+
+        if buttons.is_empty() {
+            vec![]
+        } else {
+            vec![CreateActionRow::Buttons(buttons)]
+        }
+    }
+}
+
+async fn reply(ctx: ApplicationContext<'_>, description: String) -> Result<(), Error> {
+    ctx.send(
+        CreateReply::default().embed(CreateEmbed::new().title("Lyrics").description(description)),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Shows the lyrics of the current or the specified song
+#[poise::command(slash_command)]
+pub async fn lyrics(
+    ctx: ApplicationContext<'_>,
+    #[description = "The song to show the lyrics of, defaults to the one playing"]
+    #[autocomplete = "autocomplete_songs"]
+    song: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    update_activity(data, ctx.author().id, ctx.channel_id()).await?;
+
+    let song = match &song {
+        Some(song) => Songs::get_by_hash(song, &data.db).await?,
+        None => Songs::last_played(&data.db).await?,
+    };
+    let Some(song) = song else {
+        return reply(ctx, "Could not find that song!".to_string()).await;
+    };
+
+    let name = format!("{} - {}", song.artist, song.title);
+    let Some(lyrics) = song.lyrics(&data.db).await? else {
+        return reply(ctx, format!("There are no lyrics for {}.", name)).await;
+    };
+    let lines = lyrics.lines();
+    if lines.is_empty() {
+        return reply(ctx, format!("There are no lyrics for {}.", name)).await;
+    }
+
+    let playing = PlayedSongs::get_latest(&data.db)
+        .await?
+        .filter(|play| play.song_id == song.file_hash && play.ended_at.is_none())
+        .map(|play| (play.played_at, song.duration));
+    let view = LyricsView {
+        title: name,
+        source: lyrics.source,
+        synced: lyrics.synced,
+        pages: paginate(&lines),
+        lines,
+        playing,
+    };
+
+    let current = view.current_line(chrono::Utc::now().naive_utc());
+    let mut page = current.map(|line| view.page_of(line)).unwrap_or(0);
+    let handle = ctx
+        .send(
+            CreateReply::default()
+                .embed(view.embed(page, current))
+                .components(view.components(page)),
+        )
+        .await?;
+    if view.components(page).is_empty() {
+        return Ok(());
+    }
+
+    let message = handle.message().await?;
+    while let Some(mci) = message
+        .await_component_interactions(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(120))
+        .stream()
+        .next()
+        .await
+    {
+        let current = view.current_line(chrono::Utc::now().naive_utc());
+        page = match mci.data.custom_id.as_str() {
+            "lyrics_previous" => page.saturating_sub(1),
+            "lyrics_next" => (page + 1).min(view.pages.len() - 1),
+            "lyrics_now" => current.map(|line| view.page_of(line)).unwrap_or(page),
+            _ => continue,
+        };
+        mci.create_response(
+            ctx.serenity_context(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(view.embed(page, current))
+                    .components(view.components(page)),
+            ),
+        )
+        .await?;
+    }
+
+    handle
+        .edit(
+            poise::Context::Application(ctx),
+            CreateReply::default().components(vec![]),
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> LyricLine {
+        LyricLine {
+            time: None,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_paginate() {
+        assert!(paginate(&[]).is_empty());
+        assert_eq!(
+            paginate(&vec![line("Sburb"); 61]),
+            vec![0..30, 30..60, 60..61]
+        );
+
+        let long = vec![line(&"a".repeat(1_000)); 9];
+        assert_eq!(paginate(&long), vec![0..7, 7..9]);
+    }
+
+    #[test]
+    fn test_render_line() {
+        assert_eq!(render_line(&line("Showtime"), false), "Showtime");
+        assert_eq!(render_line(&line("Showtime"), true), "**▶ Showtime**");
+        assert_eq!(render_line(&line(""), true), "**▶ ♪**");
+    }
+
+    #[test]
+    fn test_current_line() {
+        let lines = lyrics::parse_lrc("[00:00.00]one\n[00:10.00]two\n[00:20.00]three");
+        let started_at = NaiveDateTime::default();
+        let mut view = LyricsView {
+            title: "Toby Fox - Showtime".to_string(),
+            source: SOURCE_LRC.to_string(),
+            synced: true,
+            pages: paginate(&lines),
+            lines,
+            playing: Some((started_at, 30.0)),
+        };
+
+        let at = |seconds| started_at + chrono::Duration::seconds(seconds);
+        assert_eq!(view.current_line(at(15)), Some(1));
+        assert_eq!(view.current_line(at(45)), None);
+
+        view.playing = None;
+        assert_eq!(view.current_line(at(15)), None);
+    }
+}
@@ -5,18 +5,24 @@ use tracing_unwrap::ResultExt;
 use crate::event_handlers::message::update_activity;
 use crate::prelude::*;
 use ellipse::Ellipse;
-use judeharley::{Songs, Users};
+use judeharley::{sea_orm::DatabaseConnection, IndexedAlbums, JudeHarleyError, Songs, Users};
 
 pub mod add_stuff;
 pub mod admin;
+pub mod album;
+pub mod chirp;
 pub mod context;
 pub mod currency;
 pub mod help;
+pub mod listenbrainz;
+pub mod live;
+pub mod lyrics;
 pub mod minigames;
+pub mod schedule;
 pub mod songs;
+pub mod stats;
 pub mod version;
 pub mod youtube;
-pub mod chirp;
 
 /// Displays the link to the radio
 #[poise::command(slash_command)]
@@ -59,9 +65,11 @@ pub async fn autocomplete_songs(
             .await
             .expect_or_log("Failed to query database")
     } else {
-        Songs::search(partial, &data.db)
-        .await
-        .expect_or_log("Failed to query database")
+        match Songs::search(partial, 20, &data.db).await {
+            // the query is still being typed
+            Err(JudeHarleyError::InvalidSearch(_)) => vec![],
+            songs => songs.expect_or_log("Failed to query database"),
+        }
     };
 
     songs.into_iter().take(20).map(|song| {
@@ -88,9 +96,10 @@ pub async fn autocomplete_favourite_songs(
             .await
             .expect_or_log("Failed to query database")
     } else {
-        Songs::search_favourited_songs(partial, &user, &data.db)
-            .await
-            .expect_or_log("Failed to query database")
+        match Songs::search_favourited_songs(partial, &user, 20, &data.db).await {
+            Err(JudeHarleyError::InvalidSearch(_)) => vec![],
+            songs => songs.expect_or_log("Failed to query database"),
+        }
     };
 
     songs.into_iter().take(20).map(|song| {
@@ -102,3 +111,40 @@ pub async fn autocomplete_favourite_songs(
         )
     })
 }
+
+pub async fn autocomplete_albums(
+    ctx: Context<'_>,
+    partial: &str,
+) -> impl Iterator<Item = poise::serenity_prelude::AutocompleteChoice> {
+    let data = ctx.data();
+
+    let albums = IndexedAlbums::search(partial, 25, &data.db)
+        .await
+        .expect_or_log("Failed to query database");
+
+    // album names can be longer than choice values may be, so the album's ID
+    // is the value
+    albums.into_iter().map(|album| {
+        AutocompleteChoice::new(
+            format!("{} - {}", album.artist, album.album)
+                .as_str()
+                .truncate_ellipse(97),
+            album.id.to_string(),
+        )
+    })
+}
+
+/// The album an option filled in by [`autocomplete_albums`] refers to, or the
+/// first match if something else was typed in.
+pub async fn find_album(
+    value: &str,
+    db: &DatabaseConnection,
+) -> Result<Option<IndexedAlbums>, Error> {
+    if let Ok(id) = value.parse::<i32>() {
+        if let Some(album) = IndexedAlbums::get(id, db).await? {
+            return Ok(Some(album));
+        }
+    }
+
+    Ok(IndexedAlbums::search(value, 1, db).await?.pop())
+}
@@ -0,0 +1,203 @@
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+
+use crate::prelude::*;
+use judeharley::{
+    controllers::schedule_blocks::NewScheduleBlock,
+    schedule::{self, Recurrence},
+    DiscordTimestamp, ScheduleBlocks, Songs,
+};
+
+/// Scheduled programming, like themed hours
+#[poise::command(
+    slash_command,
+    subcommands("list", "add", "remove"),
+    subcommand_required
+)]
+pub async fn schedule(_: ApplicationContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn reply(ctx: ApplicationContext<'_>, embed: CreateEmbed) -> Result<(), Error> {
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// What a block plays, for embeds.
+pub fn describe_programme(block: &ScheduleBlocks) -> String {
+    let mut parts = vec![];
+    let albums = block.album_list();
+    if !albums.is_empty() {
+        parts.push(format!("Albums: {}", albums.join(", ")));
+    }
+    if let Some(filter) = block.filter.as_deref().filter(|f| !f.trim().is_empty()) {
+        parts.push(format!("Songs matching \"{}\"", filter));
+    }
+
+    parts.join("\n")
+}
+
+/// Lists the scheduled programming blocks
+#[poise::command(slash_command)]
+pub async fn list(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let blocks = ScheduleBlocks::get_all(&data.db).await?;
+    if blocks.is_empty() {
+        ctx.send(
+            CreateReply::default().embed(
+                CreateEmbed::new()
+                    .title("Schedule")
+                    .description("Nothing is scheduled, the radio is on shuffle all day!"),
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    let active = schedule::active_block(&blocks, now).map(|(block, _)| block.id);
+    let mut embed = CreateEmbed::new().title("Schedule");
+    // embeds are limited to 25 fields
+    for block in blocks.iter().take(25) {
+        let when = if active == Some(block.id) {
+            "**On air now**".to_string()
+        } else {
+            match block.next_start(now) {
+                Some(start) => format!("Next: {}", start.long_date_short_time()),
+                None => "Never again".to_string(),
+            }
+        };
+        embed = embed.field(
+            format!("#{} {}", block.id, block.name),
+            format!(
+                "`{}` (UTC) for {} minutes\n{}\n{}",
+                block.recurrence,
+                block.duration,
+                when,
+                describe_programme(block)
+            ),
+            false,
+        );
+    }
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Schedules a programming block that only plays certain songs while it runs
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn add(
+    ctx: ApplicationContext<'_>,
+    #[description = "Name of the block, e.g. Vast Error Hour"] name: String,
+    #[description = "When it starts, as `minute hour day month weekday` in UTC, e.g. 0 20 * * fri"]
+    recurrence: String,
+    #[description = "How many minutes it lasts"]
+    #[min = 1]
+    #[max = 10080]
+    duration: i32,
+    #[description = "Semicolon separated albums it plays"] albums: Option<String>,
+    #[description = "Plays songs whose artist, album or title contain this"] filter: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let recurrence = recurrence.trim().to_string();
+    if let Err(e) = recurrence.parse::<Recurrence>() {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Invalid recurrence")
+                .description(format!("`{}`: {}", recurrence, e)),
+        )
+        .await;
+    }
+    if !(1..=schedule::MAX_DURATION).contains(&duration) {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Invalid duration")
+                .description("Blocks last between a minute and a week."),
+        )
+        .await;
+    }
+
+    let albums = albums
+        .unwrap_or_default()
+        .split(';')
+        .map(|album| album.trim().to_string())
+        .filter(|album| !album.is_empty())
+        .collect::<Vec<_>>();
+    let filter = filter
+        .map(|filter| filter.trim().to_string())
+        .filter(|filter| !filter.is_empty());
+    if albums.is_empty() && filter.is_none() {
+        return reply(
+            ctx,
+            CreateEmbed::new()
+                .title("Nothing to play")
+                .description("Give the block some albums or a filter to pick its songs."),
+        )
+        .await;
+    }
+
+    let block = ScheduleBlocks::create(
+        NewScheduleBlock {
+            name,
+            recurrence,
+            duration,
+            albums,
+            filter,
+            created_by: Some(ctx.author().id.get() as i64),
+        },
+        &data.db,
+    )
+    .await?;
+
+    let songs = Songs::get_all(&data.db)
+        .await?
+        .iter()
+        .filter(|song| block.includes(song))
+        .count();
+    let now = chrono::Utc::now().naive_utc();
+    let next = block
+        .next_start(now)
+        .map(|start| start.long_date_short_time())
+        .unwrap_or_else(|| "never".to_string());
+    let mut embed = CreateEmbed::new()
+        .title("Block scheduled")
+        .field("ID", block.id.to_string(), true)
+        .field("Name", &block.name, true)
+        .field("Next run", next, true)
+        .field("Songs", songs.to_string(), true)
+        .description(describe_programme(&block));
+    if songs == 0 {
+        embed = embed.footer(poise::serenity_prelude::CreateEmbedFooter::new(
+            "No songs match, the rotation plays everything while the block runs.",
+        ));
+    }
+
+    reply(ctx, embed).await
+}
+
+/// Removes a programming block
+#[poise::command(slash_command, ephemeral, owners_only)]
+pub async fn remove(
+    ctx: ApplicationContext<'_>,
+    #[description = "ID of the block, see /schedule list"] id: i32,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let embed = if ScheduleBlocks::delete(id, &data.db).await? {
+        CreateEmbed::new()
+            .title("Block removed")
+            .description(format!("Block #{} is no longer scheduled.", id))
+    } else {
+        CreateEmbed::new()
+            .title("Block not found")
+            .description(format!("There is no block #{}.", id))
+    };
+
+    reply(ctx, embed).await
+}
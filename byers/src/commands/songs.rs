@@ -8,13 +8,17 @@ use poise::serenity_prelude::{
 };
 use poise::CreateReply;
 
-use crate::commands::{autocomplete_favourite_songs, autocomplete_songs};
+use crate::commands::lyrics::lyrics;
+use crate::commands::{autocomplete_favourite_songs, autocomplete_songs, live::requests_paused};
 use crate::event_handlers::message::update_activity;
 use crate::prelude::*;
+use crate::song_reports::{post_for_review, REPORT_REWARD};
 use judeharley::{
     communication::LiquidsoapCommunication,
+    controllers::song_reports::NewSongReport,
     cooldowns::{is_on_cooldown, set_cooldown, UserCooldownKey},
-    DiscordTimestamp, SongRequests, Songs, Users,
+    DiscordTimestamp, FeedTokens, JudeHarleyError, LiveSessions, PlayedSongs, SongReports,
+    SongRequests, Songs, Users,
 };
 
 /// Song-related commands
@@ -28,7 +32,10 @@ use judeharley::{
         "search",
         "favourite",
         "unfavourite",
-        "request_favourite"
+        "request_favourite",
+        "favourites_feed",
+        "report",
+        "lyrics"
     ),
     subcommand_required
 )]
@@ -36,19 +43,125 @@ pub async fn song(_: ApplicationContext<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-/// Displays the last 10 songs played
+/// How many reports a user can have waiting for review at once.
+const MAX_PENDING_REPORTS: u64 = 5;
+
+async fn report_reply(ctx: ApplicationContext<'_>, description: &str) -> Result<(), Error> {
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::new()
+                .title("Song Report")
+                .description(description),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Suggests a correction to a song's metadata or reports its file as broken
+#[poise::command(slash_command, ephemeral)]
+pub async fn report(
+    ctx: ApplicationContext<'_>,
+    #[description = "The song to report, defaults to the one playing"]
+    #[autocomplete = "autocomplete_songs"]
+    song: Option<String>,
+    #[description = "The correct title"] title: Option<String>,
+    #[description = "The correct artist"] artist: Option<String>,
+    #[description = "The correct album"] album: Option<String>,
+    #[description = "Whether the file is broken, e.g. cut off or silent"] broken: Option<bool>,
+    #[description = "Anything the reviewers should know"] note: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data;
+
+    let non_empty = |value: Option<String>| {
+        value
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let (title, artist, album) = (non_empty(title), non_empty(artist), non_empty(album));
+    let broken = broken.unwrap_or_default();
+    if title.is_none() && artist.is_none() && album.is_none() && !broken {
+        return report_reply(
+            ctx,
+            "Tell us the correct title, artist or album, or that the file is broken.",
+        )
+        .await;
+    }
+
+    let song = match song {
+        Some(song) => Songs::get_by_hash(&song, &data.db).await?,
+        None => Songs::last_played(&data.db).await?,
+    };
+    let Some(song) = song else {
+        return report_reply(ctx, "Could not find that song!").await;
+    };
+
+    let user_id = ctx.author().id.get() as i64;
+    if SongReports::count_pending_by_user(user_id, &data.db).await? >= MAX_PENDING_REPORTS {
+        return report_reply(
+            ctx,
+            &format!(
+                "You already have {} reports waiting for review, please wait until they're looked at.",
+                MAX_PENDING_REPORTS
+            ),
+        )
+        .await;
+    }
+
+    let report = SongReports::create(
+        NewSongReport {
+            song_id: song.file_hash.clone(),
+            user_id,
+            title,
+            artist,
+            album,
+            broken,
+            note: non_empty(note),
+        },
+        &data.db,
+    )
+    .await?;
+    post_for_review(&ctx.serenity_context().http, &data.db, &report, &song).await?;
+
+    report_reply(
+        ctx,
+        &format!(
+            "Thanks! Your report of {} - {} will be reviewed, helpful reports can earn you up to {} Boondollars.",
+            song.artist, song.title, REPORT_REWARD
+        ),
+    )
+    .await
+}
+
+/// Displays the last 10 songs played, and live shows in between
 #[poise::command(slash_command)]
 pub async fn history(ctx: ApplicationContext<'_>) -> Result<(), Error> {
     let data = ctx.data;
 
     update_activity(data, ctx.author().id, ctx.channel_id()).await?;
 
-    let last_songs = Songs::last_10_songs(&data.db).await?;
+    let (plays, _) = PlayedSongs::get_history_page(0, 10, &data.db).await?;
+    let sessions = LiveSessions::get_latest(10, &data.db).await?;
+
+    let mut entries = plays
+        .into_iter()
+        .filter_map(|(play, song)| {
+            song.map(|song| (play.played_at, format!("{} - {}", song.album, song.title)))
+        })
+        .chain(
+            sessions
+                .into_iter()
+                .map(|session| (session.started_at, format!("Live: {}", session.dj_name))),
+        )
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
 
-    let description = last_songs
+    let description = entries
         .into_iter()
+        .take(10)
         .enumerate()
-        .map(|(i, song)| format!("{}. {} - {}\n", i + 1, song.album, song.title))
+        .map(|(i, (_, entry))| format!("{}. {}\n", i + 1, entry))
         .collect::<Vec<_>>()
         .join("\n");
 
@@ -134,6 +247,35 @@ pub async fn unfavourite(
     Ok(())
 }
 
+/// Gets a link to an Atom feed of your favourite songs
+#[poise::command(slash_command, ephemeral)]
+pub async fn favourites_feed(
+    ctx: ApplicationContext<'_>,
+    #[description = "Replace the link, the old one stops working"] reset: Option<bool>,
+) -> Result<(), Error> {
+    let data = ctx.data;
+    let user = Users::get_or_insert(ctx.author().id.get(), &data.db).await?;
+
+    let feed_token = if reset.unwrap_or(false) {
+        FeedTokens::regenerate(user.id as u64, &data.db).await?
+    } else {
+        FeedTokens::get_or_create(user.id as u64, &data.db).await?
+    };
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::new()
+                .title("Favourites Feed")
+                .description(format!(
+                    "Add this link to your feed reader to follow your favourite songs:\n{}/feeds/favourites/{}\n\nAnyone with the link can see your favourites, use `reset` to replace it.",
+                    data.public_url, feed_token.token
+                )),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
 /// Displays the currently playing song
 #[poise::command(slash_command)]
 pub async fn playing(ctx: ApplicationContext<'_>) -> Result<(), Error> {
@@ -228,11 +370,20 @@ pub async fn search(
 
     let user = Users::get_or_insert(ctx.author().id.get(), &data.db).await?;
 
-    let suggestions = Songs::search(&search, &data.db)
-        .await?
-        .into_iter()
-        .take(20)
-        .collect::<Vec<_>>();
+    let suggestions = match Songs::search(&search, 20, &data.db).await {
+        Err(JudeHarleyError::InvalidSearch(e)) => {
+            ctx.send(
+                CreateReply::default().embed(
+                    CreateEmbed::new()
+                        .title("Song Search")
+                        .description(format!("Your search couldn't be understood: {}", e)),
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+        suggestions => suggestions?,
+    };
 
     if suggestions.is_empty() {
         ctx.send(
@@ -256,6 +407,7 @@ pub async fn search(
 
     let user_cooldown = UserCooldownKey::new(ctx.author().id.get() as i64, "song_request");
     let has_cooldown = is_on_cooldown(&data.redis_pool, user_cooldown).await?;
+    let paused = requests_paused(&data.db).await?;
     let mut song_selection = vec![];
     for song in &suggestions {
         if !song.is_on_cooldown(&data.db).await? {
@@ -271,7 +423,9 @@ pub async fn search(
     let mut description = format!(
         "Here are the top {results} results for your search for `{search}`.\n\n```\n{suggestion_str}\n```"
     );
-    if let Some(over) = has_cooldown.as_ref() {
+    if let Some(paused) = paused.as_ref() {
+        description.push_str(&format!("\n\n{}", paused));
+    } else if let Some(over) = has_cooldown.as_ref() {
         description.push_str(&format!(
             "\n\nYou can request a song again {}.",
             over.relative_time()
@@ -284,7 +438,7 @@ pub async fn search(
             .title("Song Search")
             .description(description),
     );
-    let reply = if has_cooldown.is_none() {
+    let reply = if has_cooldown.is_none() && paused.is_none() {
         reply.components(vec![CreateActionRow::SelectMenu(
             CreateSelectMenu::new(
                 "song_request",
@@ -369,7 +523,7 @@ pub async fn search(
     Ok(())
 }
 
-async fn request_song(ctx: ApplicationContext<'_>, song: String) -> Result<(), Error> {
+pub(crate) async fn request_song(ctx: ApplicationContext<'_>, song: String) -> Result<(), Error> {
     let data = ctx.data();
 
     update_activity(data, ctx.author().id, ctx.channel_id()).await?;
@@ -386,9 +540,23 @@ async fn request_song(ctx: ApplicationContext<'_>, song: String) -> Result<(), E
         return Ok(());
     }
 
+    if let Some(paused) = requests_paused(&data.db).await? {
+        ctx.send(
+            CreateReply::default()
+                .embed(
+                    CreateEmbed::new()
+                        .title("Song Requests")
+                        .description(paused),
+                )
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
     let song = Songs::get_by_hash(&song, &data.db).await?;
 
-    let Some(song) = song else {
+    let Some(song) = song.filter(|song| !song.hidden) else {
         ctx.send(
             CreateReply::default()
                 .content("Song not found.")
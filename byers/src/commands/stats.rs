@@ -0,0 +1,54 @@
+use chrono::Utc;
+use poise::serenity_prelude::CreateEmbed;
+use poise::CreateReply;
+
+use crate::event_handlers::message::update_activity;
+use crate::prelude::*;
+use judeharley::{controllers::listener_samples::ListenerTotal, DiscordTimestamp, ListenerSamples};
+
+/// Radio statistics
+#[poise::command(slash_command, subcommands("listeners"), subcommand_required)]
+pub async fn stats(_: ApplicationContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+fn format_total(total: Option<ListenerTotal>) -> String {
+    match total {
+        Some(total) => format!(
+            "{} ({})",
+            total.listeners,
+            total.sampled_at.long_date_short_time()
+        ),
+        None => "No data yet".to_string(),
+    }
+}
+
+/// Displays how many people are listening to the radio
+#[poise::command(slash_command)]
+pub async fn listeners(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data;
+
+    update_activity(data, ctx.author().id, ctx.channel_id()).await?;
+
+    let start_of_today = Utc::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time");
+
+    let current = ListenerSamples::current(&data.db).await?;
+    let peak_today = ListenerSamples::peak(Some(start_of_today), &data.db).await?;
+    let peak_all_time = ListenerSamples::peak(None, &data.db).await?;
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::new()
+                .title("Listeners")
+                .field("Listening now", format_total(current), false)
+                .field("Peak today", format_total(peak_today), false)
+                .field("All-time peak", format_total(peak_all_time), false),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
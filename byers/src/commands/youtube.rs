@@ -4,9 +4,7 @@ use poise::{
     serenity_prelude::{CreateActionRow, CreateButton, CreateEmbed},
     CreateReply,
 };
-use situwaition::{
-    runtime::AsyncWaiter, SituwaitionError, SituwaitionOptsBuilder, TokioAsyncSituwaition,
-};
+use situwaition::{runtime::AsyncWaiter, SituwaitionError, SituwaitionOptsBuilder};
 use tracing::error;
 
 use crate::{event_handlers::message::update_activity, prelude::*};
@@ -86,7 +84,7 @@ If you don't remember your old YouTube name or you no longer have access to your
 
             Ok(connected_channels)
         },
-        SituwaitionOptsBuilder::default()
+        SituwaitionOptsBuilder
             .timeout(Duration::from_secs(120))
             .check_interval(Duration::from_secs(1))
             .check_cooldown(None)
@@ -3,7 +3,11 @@ use poise::{serenity_prelude::CreateEmbed, CreateReply};
 use tracing::error;
 
 use crate::prelude::*;
-use judeharley::{communication::ByersUnixStream, prelude::DiscordTimestamp};
+use judeharley::{
+    alerts::Severity,
+    communication::ByersUnixStream,
+    prelude::{DiscordTimestamp, JudeHarleyError},
+};
 
 type FrameworkError<'a> = poise::FrameworkError<'a, Data<ByersUnixStream>, crate::prelude::Error>;
 
@@ -30,6 +34,12 @@ async fn send_cooldown_embed(
     Ok(())
 }
 
+fn record_failure(ctx: Context<'_>) {
+    judeharley::metrics::COMMANDS_FAILED
+        .with_label_values(&[&ctx.command().qualified_name])
+        .inc();
+}
+
 pub async fn on_error(error: FrameworkError<'_>) -> Result<(), Error> {
     match error {
         FrameworkError::CooldownHit {
@@ -40,13 +50,31 @@ pub async fn on_error(error: FrameworkError<'_>) -> Result<(), Error> {
             send_cooldown_embed(ctx, remaining_cooldown).await?;
         }
         FrameworkError::Command { error, ctx, .. } => {
+            record_failure(ctx);
+            if let Some(error) = error.downcast_ref::<JudeHarleyError>() {
+                judeharley::metrics::record_error(error);
+            }
+
             let err_str = error.to_string();
             error!("Error in command: {}", err_str);
             sentry::add_breadcrumb(BreadcrumbableContext(ctx).as_breadcrumbs().await);
             sentry_anyhow::capture_anyhow(&error);
+            crate::alerts::raise(
+                &ctx.data().redis_pool,
+                Severity::Error,
+                format!("command:{}", ctx.command().qualified_name),
+                format!("/{} failed", ctx.command().qualified_name),
+                &err_str,
+            )
+            .await;
             ctx.say(err_str).await?;
-        },
-        FrameworkError::CommandPanic { ref payload, ref ctx, .. } => {
+        }
+        FrameworkError::CommandPanic {
+            ref payload,
+            ref ctx,
+            ..
+        } => {
+            record_failure(*ctx);
             let payload_clone = payload.clone();
             sentry::add_breadcrumb(BreadcrumbableContext(*ctx).as_breadcrumbs().await);
             if let Some(payload) = payload_clone {
@@ -54,7 +82,17 @@ pub async fn on_error(error: FrameworkError<'_>) -> Result<(), Error> {
             } else {
                 sentry_anyhow::capture_anyhow(&anyhow::anyhow!("Panic in command"));
             }
-            
+            crate::alerts::raise(
+                &ctx.data().redis_pool,
+                Severity::Critical,
+                format!("command_panic:{}", ctx.command().qualified_name),
+                format!("/{} panicked", ctx.command().qualified_name),
+                payload
+                    .clone()
+                    .unwrap_or_else(|| "Panic in command".to_string()),
+            )
+            .await;
+
             let embed = poise::serenity_prelude::CreateEmbed::default()
                 .title("Internal error")
                 .color((255, 0, 0))
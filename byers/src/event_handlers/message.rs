@@ -32,21 +32,9 @@ impl UserMessageHandlerExt for Users {
     }
 
     async fn update_watched_time(self, db: &DatabaseConnection) -> Result<Self, Error> {
-        let user = if self.last_message_sent.is_none() {
-            // first message
-            info!("User {} sent their first message", self.id);
-            let last_message_sent = Some(chrono::Utc::now().naive_utc());
-            self.update(
-                judeharley::entities::users::ActiveModel {
-                    last_message_sent: last_message_sent.map_or(ActiveValue::not_set(), |t| Set(Some(t))),
-                    ..Default::default()
-                },
-                db,
-            )
-            .await?
-        } else {
+        let user = if let Some(previous_message_sent) = self.last_message_sent {
             let now = chrono::Utc::now().naive_utc();
-            let time_diff = now - self.last_message_sent.unwrap();
+            let time_diff = now - previous_message_sent;
             let last_message_sent = Some(now);
 
             let watched_time = if time_diff.num_minutes() <= 15 {
@@ -71,6 +59,18 @@ impl UserMessageHandlerExt for Users {
                 db,
             )
             .await?
+        } else {
+            // first message
+            info!("User {} sent their first message", self.id);
+            let last_message_sent = Some(chrono::Utc::now().naive_utc());
+            self.update(
+                judeharley::entities::users::ActiveModel {
+                    last_message_sent: last_message_sent.map_or(ActiveValue::not_set(), |t| Set(Some(t))),
+                    ..Default::default()
+                },
+                db,
+            )
+            .await?
         };
 
         Ok(user)
@@ -91,7 +91,7 @@ impl UserMessageHandlerExt for Users {
         }
 
         redis_client
-            .set(
+            .set::<(), _, _>(
                 &cooldown_key,
                 (chrono::Utc::now() + chrono::Duration::minutes(5))
                     .naive_utc()
@@ -1,10 +1,10 @@
 use fred::{prelude::PubsubInterface, types::RedisValue};
 use poise::serenity_prelude::*;
-use tracing::{debug, info};
-use tracing_unwrap::ResultExt;
+use tracing::{debug, error, info};
 
 use crate::prelude::*;
 use judeharley::{
+    alerts::{Severity, ALERTS_CHANNEL},
     communication::ByersUnixStream,
     prelude::{ServerChannelConfig, Songs},
 };
@@ -16,6 +16,7 @@ async fn spawn_subscriber_handler(
     info!("Spawning Redis subscriber message handler...");
     let mut message_rx = data.redis_subscriber.on_message();
     let context = ctx.clone();
+    let db = data.db.clone();
     tokio::spawn(async move {
         while let Ok(message) = message_rx.recv().await {
             debug!(
@@ -29,6 +30,15 @@ async fn spawn_subscriber_handler(
                         context.set_activity(Some(ActivityData::listening(song.to_string())));
                     }
                 }
+                ALERTS_CHANNEL => {
+                    if let RedisValue::String(payload) = message.value {
+                        let http = context.http.clone();
+                        let db = db.clone();
+                        tokio::spawn(async move {
+                            crate::alerts::forward_alert(&http, &db, &payload).await;
+                        });
+                    }
+                }
                 "moo" => {}
                 _ => {}
             }
@@ -65,6 +75,7 @@ async fn spawn_hydration_reminder(
     ctx: &poise::serenity_prelude::Context,
 ) -> Result<(), crate::prelude::Error> {
     let db = data.db.clone();
+    let redis_pool = data.redis_pool.clone();
     let inner_ctx = ctx.clone();
 
     tokio::spawn(async move {
@@ -76,13 +87,26 @@ async fn spawn_hydration_reminder(
 
             info!("Sending hydration reminder");
 
-            let hydration_channels = ServerChannelConfig::get_all_hydration_channels(&db)
-                .await
-                .expect_or_log("Failed to fetch hydration channels");
+            let hydration_channels =
+                match ServerChannelConfig::get_all_hydration_channels(&db).await {
+                    Ok(channels) => channels,
+                    Err(e) => {
+                        error!(error = %e, "Failed to fetch hydration channels");
+                        crate::alerts::raise(
+                            &redis_pool,
+                            Severity::Warning,
+                            "hydration_channels",
+                            "Failed to fetch hydration channels",
+                            e.to_string(),
+                        )
+                        .await;
+                        continue;
+                    }
+                };
 
             for channel in hydration_channels {
                 let discord_channel_id = ChannelId::new(channel.id as u64);
-                discord_channel_id
+                if let Err(e) = discord_channel_id
                     .send_message(
                         &ctx.http,
                         CreateMessage::new().embed(
@@ -92,7 +116,17 @@ async fn spawn_hydration_reminder(
                         ),
                     )
                     .await
-                    .expect_or_log("Failed to send hydration reminder");
+                {
+                    error!(channel_id = channel.id, error = %e, "Failed to send hydration reminder");
+                    crate::alerts::raise(
+                        &redis_pool,
+                        Severity::Warning,
+                        format!("hydration_reminder:{}", channel.id),
+                        "Failed to send hydration reminder",
+                        format!("<#{}>: {}", channel.id, e),
+                    )
+                    .await;
+                }
             }
         }
     });
@@ -0,0 +1,477 @@
+//! Atom feeds of the play history, newly indexed albums and users' favourites.
+//!
+//! Favourites feeds are addressed by an opaque per-user token instead of a
+//! login, so they work in any feed reader. Entries link to the cover art of
+//! their album as an enclosure, if the album's folder has one.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use axum::{
+    extract::{FromRef, Path as UrlPath, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use tracing::error;
+
+use judeharley::{
+    sea_orm::DatabaseConnection, FavouriteSongs, FeedTokens, IndexedAlbums, JudeHarleyError,
+    PlayedSongs, Songs,
+};
+
+const ATOM_CONTENT_TYPE: &str = "application/atom+xml; charset=utf-8";
+
+/// How many entries a feed carries.
+const FEED_LENGTH: u64 = 50;
+
+/// File names, without extension, that count as an album's cover art, in
+/// order of preference.
+const COVER_NAMES: [&str; 4] = ["cover", "folder", "front", "album"];
+
+#[derive(Clone)]
+pub struct FeedConfig {
+    /// The URL the web server is reachable at, e.g. `https://discord.lumirad.io`.
+    pub public_url: String,
+}
+
+impl FeedConfig {
+    pub fn new(public_url: &str) -> Self {
+        Self {
+            public_url: public_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.public_url, path)
+    }
+
+    /// A permanent ID for an entry or feed, as a `tag:` URI.
+    fn tag(&self, specific: &str) -> String {
+        let host = reqwest::Url::parse(&self.public_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| "lumirad.io".to_string());
+
+        format!("tag:{},2024:{}", host, specific)
+    }
+}
+
+pub fn feeds_router<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    DatabaseConnection: FromRef<S>,
+    FeedConfig: FromRef<S>,
+{
+    Router::new()
+        .route("/feeds/played", get(played_feed))
+        .route("/feeds/albums", get(albums_feed))
+        .route("/feeds/favourites/:token", get(favourites_feed))
+        .route("/covers/:song_id", get(cover))
+}
+
+struct Enclosure {
+    href: String,
+    mime_type: &'static str,
+    length: u64,
+}
+
+struct Entry {
+    id: String,
+    title: String,
+    author: String,
+    updated: DateTime<Utc>,
+    summary: String,
+    enclosure: Option<Enclosure>,
+}
+
+struct Feed {
+    id: String,
+    title: String,
+    self_link: String,
+    entries: Vec<Entry>,
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // control characters other than tabs and newlines aren't allowed in XML
+            c if c.is_control() && c != '\t' && c != '\n' && c != '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+impl Feed {
+    fn render(&self) -> String {
+        // an empty feed was last updated whenever it is looked at
+        let updated = self
+            .entries
+            .iter()
+            .map(|entry| entry.updated)
+            .max()
+            .unwrap_or_else(Utc::now);
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+        xml.push_str(&format!("  <id>{}</id>\n", escape(&self.id)));
+        xml.push_str(&format!("  <title>{}</title>\n", escape(&self.title)));
+        xml.push_str(&format!("  <updated>{}</updated>\n", timestamp(updated)));
+        xml.push_str(&format!(
+            "  <link rel=\"self\" type=\"application/atom+xml\" href=\"{}\"/>\n",
+            escape(&self.self_link)
+        ));
+        xml.push_str("  <author><name>lumiRadio</name></author>\n");
+        xml.push_str(&format!(
+            "  <generator version=\"{}\">Byers</generator>\n",
+            env!("CARGO_PKG_VERSION")
+        ));
+
+        for entry in &self.entries {
+            xml.push_str("  <entry>\n");
+            xml.push_str(&format!("    <id>{}</id>\n", escape(&entry.id)));
+            xml.push_str(&format!("    <title>{}</title>\n", escape(&entry.title)));
+            xml.push_str(&format!(
+                "    <author><name>{}</name></author>\n",
+                escape(&entry.author)
+            ));
+            xml.push_str(&format!(
+                "    <updated>{}</updated>\n",
+                timestamp(entry.updated)
+            ));
+            xml.push_str(&format!(
+                "    <summary>{}</summary>\n",
+                escape(&entry.summary)
+            ));
+            if let Some(enclosure) = &entry.enclosure {
+                xml.push_str(&format!(
+                    "    <link rel=\"enclosure\" type=\"{}\" length=\"{}\" href=\"{}\"/>\n",
+                    enclosure.mime_type,
+                    enclosure.length,
+                    escape(&enclosure.href)
+                ));
+            }
+            xml.push_str("  </entry>\n");
+        }
+        xml.push_str("</feed>\n");
+
+        xml
+    }
+}
+
+impl IntoResponse for Feed {
+    fn into_response(self) -> Response {
+        ([(header::CONTENT_TYPE, ATOM_CONTENT_TYPE)], self.render()).into_response()
+    }
+}
+
+fn cover_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// A cover art file in `directory`, with its MIME type and size.
+async fn find_cover(directory: &Path) -> Option<(PathBuf, &'static str, u64)> {
+    let mut entries = tokio::fs::read_dir(directory).await.ok()?;
+    let mut best: Option<(usize, PathBuf, &'static str, u64)> = None;
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let Some(mime_type) = cover_mime_type(&path) else {
+            continue;
+        };
+        let Some(stem) = path.file_stem().map(|s| s.to_string_lossy().to_lowercase()) else {
+            continue;
+        };
+        let Some(rank) = COVER_NAMES.iter().position(|name| *name == stem) else {
+            continue;
+        };
+        if best
+            .as_ref()
+            .is_some_and(|(best_rank, ..)| *best_rank <= rank)
+        {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        if metadata.is_file() {
+            best = Some((rank, path, mime_type, metadata.len()));
+        }
+    }
+
+    best.map(|(_, path, mime_type, length)| (path, mime_type, length))
+}
+
+/// Looks up cover art for the songs of one feed, songs of the same album
+/// only cost one directory listing.
+struct Covers<'a> {
+    config: &'a FeedConfig,
+    by_directory: HashMap<PathBuf, Option<(&'static str, u64)>>,
+}
+
+impl<'a> Covers<'a> {
+    fn new(config: &'a FeedConfig) -> Self {
+        Self {
+            config,
+            by_directory: HashMap::new(),
+        }
+    }
+
+    async fn enclosure(&mut self, song: &Songs) -> Option<Enclosure> {
+        let directory = Path::new(&song.file_path).parent()?.to_path_buf();
+        let cover = match self.by_directory.get(&directory) {
+            Some(cover) => *cover,
+            None => {
+                let cover = find_cover(&directory)
+                    .await
+                    .map(|(_, mime_type, length)| (mime_type, length));
+                self.by_directory.insert(directory, cover);
+                cover
+            }
+        };
+
+        cover.map(|(mime_type, length)| Enclosure {
+            href: self.config.url(&format!("/covers/{}", song.file_hash)),
+            mime_type,
+            length,
+        })
+    }
+}
+
+fn internal_error(e: JudeHarleyError) -> StatusCode {
+    error!(error = %e, "failed to build feed");
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+fn utc(time: NaiveDateTime) -> DateTime<Utc> {
+    time.and_utc()
+}
+
+async fn played_feed(
+    State(db): State<DatabaseConnection>,
+    State(config): State<FeedConfig>,
+) -> Result<Feed, StatusCode> {
+    let (plays, _) = PlayedSongs::get_history_page(0, FEED_LENGTH, &db)
+        .await
+        .map_err(internal_error)?;
+
+    let mut covers = Covers::new(&config);
+    let mut entries = vec![];
+    for (play, song) in plays {
+        // songs that have since been removed or hidden
+        let Some(song) = song.filter(|song| !song.hidden) else {
+            continue;
+        };
+
+        entries.push(Entry {
+            id: config.tag(&format!("play/{}", play.id)),
+            title: format!("{} - {}", song.artist, song.title),
+            author: song.artist.clone(),
+            updated: utc(play.played_at),
+            summary: format!(
+                "Played \"{}\" by {} from {}",
+                song.title, song.artist, song.album
+            ),
+            enclosure: covers.enclosure(&song).await,
+        });
+    }
+
+    Ok(Feed {
+        id: config.tag("played"),
+        title: "lumiRadio: Recently played".to_string(),
+        self_link: config.url("/feeds/played"),
+        entries,
+    })
+}
+
+async fn albums_feed(
+    State(db): State<DatabaseConnection>,
+    State(config): State<FeedConfig>,
+) -> Result<Feed, StatusCode> {
+    let albums = IndexedAlbums::get_latest(FEED_LENGTH, &db)
+        .await
+        .map_err(internal_error)?;
+
+    let mut covers = Covers::new(&config);
+    let mut entries = vec![];
+    for album in albums {
+        let songs = Songs::get_by_album(&album.artist, &album.album, &db)
+            .await
+            .map_err(internal_error)?;
+        // albums that have since been removed from the library
+        let Some(first_song) = songs.first() else {
+            continue;
+        };
+
+        let tracks = songs
+            .iter()
+            .enumerate()
+            .map(|(i, song)| format!("{}. {}", i + 1, song.title))
+            .collect::<Vec<_>>()
+            .join("\n");
+        entries.push(Entry {
+            id: config.tag(&format!("album/{}", album.id)),
+            title: format!("{} - {}", album.artist, album.album),
+            author: album.artist.clone(),
+            updated: utc(album.first_indexed_at),
+            summary: format!("{} tracks added to the library:\n{}", songs.len(), tracks),
+            enclosure: covers.enclosure(first_song).await,
+        });
+    }
+
+    Ok(Feed {
+        id: config.tag("albums"),
+        title: "lumiRadio: New albums".to_string(),
+        self_link: config.url("/feeds/albums"),
+        entries,
+    })
+}
+
+async fn favourites_feed(
+    State(db): State<DatabaseConnection>,
+    State(config): State<FeedConfig>,
+    UrlPath(token): UrlPath<String>,
+) -> Result<Feed, StatusCode> {
+    let Some(feed_token) = FeedTokens::get_by_token(&token, &db)
+        .await
+        .map_err(internal_error)?
+    else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let favourites = FavouriteSongs::get_recent_by_user(feed_token.user_id, FEED_LENGTH, &db)
+        .await
+        .map_err(internal_error)?;
+
+    let mut covers = Covers::new(&config);
+    let mut entries = vec![];
+    for (favourite, song) in favourites.into_iter().filter(|(_, song)| !song.hidden) {
+        entries.push(Entry {
+            id: config.tag(&format!("favourite/{}", favourite.id)),
+            title: format!("{} - {}", song.artist, song.title),
+            author: song.artist.clone(),
+            updated: utc(favourite.created_at),
+            summary: format!(
+                "Favourited \"{}\" by {} from {}",
+                song.title, song.artist, song.album
+            ),
+            enclosure: covers.enclosure(&song).await,
+        });
+    }
+
+    Ok(Feed {
+        id: config.tag(&format!("favourites/{}", feed_token.token)),
+        title: "lumiRadio: Favourites".to_string(),
+        self_link: config.url(&format!("/feeds/favourites/{}", feed_token.token)),
+        entries,
+    })
+}
+
+async fn cover(
+    State(db): State<DatabaseConnection>,
+    UrlPath(song_id): UrlPath<String>,
+) -> Result<Response, StatusCode> {
+    let Some(song) = Songs::get_by_hash(&song_id, &db)
+        .await
+        .map_err(internal_error)?
+        .filter(|song| !song.hidden)
+    else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let directory = Path::new(&song.file_path)
+        .parent()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let (path, mime_type, _) = find_cover(directory).await.ok_or(StatusCode::NOT_FOUND)?;
+    let image = tokio::fs::read(&path).await.map_err(|e| {
+        error!(path = %path.display(), error = %e, "failed to read cover art");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, mime_type),
+            (header::CACHE_CONTROL, "public, max-age=86400"),
+        ],
+        image,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_feed() {
+        let config = FeedConfig::new("https://discord.lumirad.io/");
+        let feed = Feed {
+            id: config.tag("played"),
+            title: "lumiRadio: Recently played".to_string(),
+            self_link: config.url("/feeds/played"),
+            entries: vec![Entry {
+                id: config.tag("play/1"),
+                title: "Simon & Garfunkel - <Mrs. Robinson>".to_string(),
+                author: "Simon & Garfunkel".to_string(),
+                updated: DateTime::from_timestamp(1_760_788_800, 0).unwrap(),
+                summary: "Played \"Mrs. Robinson\"\u{0}".to_string(),
+                enclosure: Some(Enclosure {
+                    href: config.url("/covers/abc"),
+                    mime_type: "image/jpeg",
+                    length: 1234,
+                }),
+            }],
+        };
+
+        let xml = feed.render();
+        assert!(xml.contains("<id>tag:discord.lumirad.io,2024:played</id>"));
+        assert!(xml.contains("<updated>2025-10-18T12:00:00Z</updated>"));
+        assert!(xml.contains("href=\"https://discord.lumirad.io/feeds/played\""));
+        assert!(xml.contains("<title>Simon &amp; Garfunkel - &lt;Mrs. Robinson&gt;</title>"));
+        assert!(xml.contains("<summary>Played &quot;Mrs. Robinson&quot;</summary>"));
+        assert!(xml.contains(
+            "<link rel=\"enclosure\" type=\"image/jpeg\" length=\"1234\" href=\"https://discord.lumirad.io/covers/abc\"/>"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_find_cover() {
+        let directory = std::env::temp_dir().join(format!("byers-cover-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("01 Song.mp3"), b"").unwrap();
+        assert!(find_cover(&directory).await.is_none());
+
+        std::fs::write(directory.join("Front.PNG"), b"png").unwrap();
+        std::fs::write(directory.join("notes.jpg"), b"jpg").unwrap();
+        let (path, mime_type, length) = find_cover(&directory).await.unwrap();
+        assert_eq!(path.file_name().unwrap(), "Front.PNG");
+        assert_eq!((mime_type, length), ("image/png", 3));
+
+        std::fs::write(directory.join("cover.jpg"), b"jpeg").unwrap();
+        let (path, mime_type, _) = find_cover(&directory).await.unwrap();
+        assert_eq!(path.file_name().unwrap(), "cover.jpg");
+        assert_eq!(mime_type, "image/jpeg");
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
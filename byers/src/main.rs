@@ -10,30 +10,49 @@ use crate::{
     commands::{
         add_stuff::*,
         admin::{config::config as config_cmd, import::*, user::*, *},
+        album::album,
+        chirp::*,
         context::what_song,
         currency::*,
         help::*,
-        listen, minigames,
+        listen,
+        listenbrainz::listenbrainz,
+        live::live,
+        minigames,
         minigames::pvp::pvp_context,
+        schedule::schedule,
         songs::*,
+        stats::*,
         version::*,
         youtube::*,
-        chirp::*
     },
     oauth2::oauth2_server,
     prelude::*,
 };
-use judeharley::communication::ByersUnixStream;
+use judeharley::{
+    alerts::ALERTS_CHANNEL, communication::ByersUnixStream, maintenance::scrobbling::ListenBrainz,
+    now_playing::NOW_PLAYING_CHANNEL,
+};
 
+mod alerts;
+mod api;
 mod app_config;
 mod commands;
 mod event_handlers;
+mod feeds;
+mod now_playing;
 mod oauth2;
 mod prelude;
+mod queued_albums;
+mod schedule;
+mod song_reports;
+mod watchdog;
 
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
+    judeharley::health::init();
+    judeharley::metrics::init();
 
     info!("Loading config from environment...");
     let config = crate::app_config::AppConfig::from_env();
@@ -42,6 +61,7 @@ async fn main() {
         info!("Initializing Sentry...");
         let guard = sentry::init(sentry::ClientOptions {
             environment: Some(config.environment.clone().into()),
+            debug: config.sentry_debug,
             dsn: Some(
                 sentry::types::Dsn::from_str(sentry_dsn)
                     .expect_or_log("failed to parse Sentry DSN"),
@@ -73,7 +93,12 @@ async fn main() {
         addcan(),
         addbear(),
         what_song(),
-        chirp()
+        chirp(),
+        stats(),
+        listenbrainz(),
+        schedule(),
+        live(),
+        album(),
     ];
 
     info!("Loading {} commands...", commands.len());
@@ -101,6 +126,9 @@ async fn main() {
 
     tokio::spawn(async move {
         while let Ok(error) = redis_error_rx.recv().await {
+            judeharley::metrics::ERRORS
+                .with_label_values(&["redis"])
+                .inc();
             tracing::error!("Redis error: {:?}", error);
         }
     });
@@ -111,6 +139,9 @@ async fn main() {
     });
     tokio::spawn(async move {
         while let Ok(error) = subscriber_error_rx.recv().await {
+            judeharley::metrics::ERRORS
+                .with_label_values(&["redis"])
+                .inc();
             tracing::error!("Redis subscriber error: {:?}", error);
         }
     });
@@ -137,6 +168,14 @@ async fn main() {
         .subscribe::<(), _>("byers:status")
         .await
         .expect_or_log("failed to subscribe");
+    subscriber_client
+        .subscribe::<(), _>(NOW_PLAYING_CHANNEL)
+        .await
+        .expect_or_log("failed to subscribe");
+    subscriber_client
+        .subscribe::<(), _>(ALERTS_CHANNEL)
+        .await
+        .expect_or_log("failed to subscribe");
 
     let context = Data {
         db: db.clone(),
@@ -146,12 +185,31 @@ async fn main() {
         redis_pool: redis_pool.clone(),
         redis_subscriber: subscriber_client.clone(),
         emoji: config.discord.emoji.clone(),
+        listenbrainz: ListenBrainz::new(&config.listenbrainz_url)
+            .expect_or_log("failed to create ListenBrainz client"),
+        public_url: config.public_url.trim_end_matches('/').to_string(),
+        jingles_path: config.jingles_path.clone().into(),
     };
 
+    let comms = context.comms.clone();
+    crate::watchdog::spawn_dead_air_watchdog(
+        db.clone(),
+        redis_pool.clone(),
+        comms.clone(),
+        std::time::Duration::from_secs(config.dead_air_grace_period),
+    );
+    crate::queued_albums::spawn_album_queuer(db.clone(), comms.clone());
+    let now_playing = crate::now_playing::spawn_now_playing_feed(
+        db.clone(),
+        comms.clone(),
+        subscriber_client.clone(),
+    )
+    .await;
+
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
             commands,
-            event_handler: |ctx, event, _framework, data| {
+            event_handler: |ctx, event, framework, data| {
                 Box::pin(async move {
                     debug!("Event received: {}", event.snake_case_name());
 
@@ -165,9 +223,28 @@ async fn main() {
                         crate::event_handlers::ready::on_ready(ctx, data_about_bot, data).await?;
                     }
 
+                    if let serenity::FullEvent::InteractionCreate { interaction } = event {
+                        if let Some(component) = interaction.as_message_component() {
+                            crate::song_reports::handle_review(
+                                ctx,
+                                component,
+                                data,
+                                &framework.options().owners,
+                            )
+                            .await?;
+                        }
+                    }
+
                     Ok(())
                 })
             },
+            pre_command: |ctx| {
+                Box::pin(async move {
+                    judeharley::metrics::COMMANDS_INVOKED
+                        .with_label_values(&[&ctx.command().qualified_name])
+                        .inc();
+                })
+            },
             on_error: |error| {
                 Box::pin(async move {
                     crate::event_handlers::error::on_error(error)
@@ -197,13 +274,16 @@ async fn main() {
         .framework(framework)
         .await
         .expect_or_log("failed to create client");
+    // spawned here rather than on Ready, which fires again on every reconnect
+    crate::schedule::spawn_schedule_announcer(client.http.clone(), db.clone());
 
     let (tx, rx) = tokio::sync::oneshot::channel::<()>();
     let webserver_handle = tokio::spawn(oauth2_server(
-        config.secret.clone(),
+        config,
         db,
         redis_pool.clone(),
-        config.discord,
+        now_playing,
+        comms,
         rx,
     ));
 
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+};
+use fred::{clients::SubscriberClient, prelude::PubsubInterface, types::RedisValue};
+use futures::Stream;
+use tokio::sync::{watch, Mutex};
+use tokio_stream::{wrappers::WatchStream, StreamExt};
+use tracing::{debug, error, warn};
+
+use crate::oauth2::ApiResponse;
+use judeharley::{
+    communication::{ByersUnixStream, LiquidsoapCommunication},
+    now_playing::{NowPlaying, NOW_PLAYING_CHANNEL},
+    sea_orm::DatabaseConnection,
+};
+
+pub type NowPlayingReceiver = watch::Receiver<Option<NowPlaying>>;
+
+async fn with_queue(
+    mut now_playing: NowPlaying,
+    comms: &Arc<Mutex<ByersUnixStream>>,
+) -> NowPlaying {
+    let mut comms = comms.lock().await;
+    match comms.song_requests().await {
+        Ok(queue) => now_playing.queue = queue.into_iter().map(Into::into).collect(),
+        Err(e) => warn!("Failed to fetch song request queue: {}", e),
+    }
+
+    now_playing
+}
+
+/// Keeps track of the current song using langley's now-playing events, with
+/// the upcoming queue filled in from Liquidsoap.
+pub async fn spawn_now_playing_feed(
+    db: DatabaseConnection,
+    comms: Arc<Mutex<ByersUnixStream>>,
+    subscriber: SubscriberClient,
+) -> NowPlayingReceiver {
+    let initial = match NowPlaying::from_history(&db).await {
+        Ok(Some(now_playing)) => Some(with_queue(now_playing, &comms).await),
+        Ok(None) => None,
+        Err(e) => {
+            error!("Failed to fetch the last played song: {}", e);
+            None
+        }
+    };
+
+    let (tx, rx) = watch::channel(initial);
+    let mut message_rx = subscriber.on_message();
+    tokio::spawn(async move {
+        while let Ok(message) = message_rx.recv().await {
+            if message.channel.to_string() != NOW_PLAYING_CHANNEL {
+                continue;
+            }
+
+            let RedisValue::String(payload) = message.value else {
+                continue;
+            };
+            let now_playing = match serde_json::from_str::<NowPlaying>(&payload) {
+                Ok(now_playing) => now_playing,
+                Err(e) => {
+                    warn!("Received malformed now playing event: {}", e);
+                    continue;
+                }
+            };
+
+            debug!("Now playing {}", now_playing.song.id);
+            tx.send_replace(Some(with_queue(now_playing, &comms).await));
+        }
+    });
+
+    rx
+}
+
+/// The song that is currently playing
+pub async fn now_playing(State(now_playing): State<NowPlayingReceiver>) -> ApiResponse<NowPlaying> {
+    match now_playing.borrow().clone() {
+        Some(data) => ApiResponse::Success { data },
+        None => ApiResponse::Error {
+            error: "Nothing is playing".to_string(),
+        },
+    }
+}
+
+/// Server-Sent Events stream with a `now-playing` event for every song
+pub async fn now_playing_stream(
+    State(now_playing): State<NowPlayingReceiver>,
+) -> Sse<impl Stream<Item = Result<Event, serde_json::Error>>> {
+    let events = WatchStream::new(now_playing).filter_map(|now_playing| {
+        now_playing.map(|now_playing| Event::default().event("now-playing").json_data(now_playing))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// WebSocket that receives a JSON message for every song
+pub async fn now_playing_ws(
+    ws: WebSocketUpgrade,
+    State(now_playing): State<NowPlayingReceiver>,
+) -> Response {
+    ws.on_upgrade(move |socket| now_playing_socket(socket, now_playing))
+}
+
+async fn now_playing_socket(mut socket: WebSocket, now_playing: NowPlayingReceiver) {
+    let mut updates = WatchStream::new(now_playing);
+
+    loop {
+        tokio::select! {
+            update = updates.next() => {
+                let Some(update) = update else {
+                    break;
+                };
+                let Some(now_playing) = update else {
+                    continue;
+                };
+                let payload = match serde_json::to_string(&now_playing) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        error!("Failed to serialize now playing: {}", e);
+                        continue;
+                    }
+                };
+
+                if socket.send(Message::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                // clients have nothing to tell us, we only care about them leaving
+                match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
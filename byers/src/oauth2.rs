@@ -1,7 +1,7 @@
 use async_fred_session::RedisSessionStore;
 use axum::{
     extract::{FromRef, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{Html, IntoResponse, Redirect},
     routing::get,
     Json, Router,
@@ -9,7 +9,9 @@ use axum::{
 use axum_sessions::{extractors::WritableSession, SessionLayer};
 use fred::pool::RedisPool;
 use judeharley::{
+    communication::ByersUnixStream,
     discord::{DiscordConnection, MinimalDiscordUser},
+    health::{check_dependencies, HealthReport},
     sea_orm::DatabaseConnection,
 };
 use oauth2::{
@@ -17,20 +19,31 @@ use oauth2::{
     ClientSecret, CsrfToken, Scope, TokenResponse, TokenUrl,
 };
 use serde::{Deserialize, Serialize};
-use tokio::sync::oneshot::Receiver;
-use tracing::error;
+use std::{net::SocketAddr, sync::Arc};
+use tokio::sync::{oneshot::Receiver, Mutex};
+use tracing::{error, info};
 use tracing_unwrap::ResultExt;
 
-use crate::{app_config::DiscordConfig, prelude::Error};
+use crate::{
+    api::v1_router,
+    app_config::{AppConfig, DiscordConfig},
+    feeds::{feeds_router, FeedConfig},
+    now_playing::{now_playing, now_playing_stream, now_playing_ws, NowPlayingReceiver},
+    prelude::Error,
+};
 
 static OAUTH2_SUCCESS_HTML: &str = include_str!("static/oauth2_success.html");
 static OAUTH2_FAILED_CSRF_HTML: &str = include_str!("static/oauth2_csrf.html");
 static OAUTH2_FAILED_DISCORD_HTML: &str = include_str!("static/oauth2_discord.html");
 
-#[derive(FromRef, Debug, Clone)]
+#[derive(FromRef, Clone)]
 struct AppState {
     db: DatabaseConnection,
+    redis: RedisPool,
     discord_config: DiscordConfig,
+    feed_config: FeedConfig,
+    now_playing: NowPlayingReceiver,
+    comms: Arc<Mutex<ByersUnixStream>>,
 }
 
 #[derive(serde::Deserialize)]
@@ -151,7 +164,7 @@ async fn oauth2_callback(
 
 #[derive(Serialize, Debug)]
 #[serde(tag = "type")]
-enum ApiResponse<T> {
+pub(crate) enum ApiResponse<T> {
     Success { data: T },
     Error { error: String },
 }
@@ -202,22 +215,79 @@ async fn song_list(State(app_state): State<AppState>) -> ApiResponse<Vec<Song>>
     }
 }
 
+async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, judeharley::metrics::CONTENT_TYPE)],
+        judeharley::metrics::gather(),
+    )
+}
+
+async fn health(
+    State(db): State<DatabaseConnection>,
+    State(redis): State<RedisPool>,
+) -> impl IntoResponse {
+    let dependencies = check_dependencies(&db, &redis).await;
+    let report = HealthReport::new("byers", env!("CARGO_PKG_VERSION"), dependencies);
+    let status = if report.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(report))
+}
+
+/// Serves `/metrics` on its own listener, so it isn't exposed with the public
+/// web server.
+async fn serve_metrics(address: SocketAddr) -> Result<(), Error> {
+    let app = Router::new().route("/metrics", get(metrics));
+
+    info!("serving metrics on {}", address);
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
 pub async fn oauth2_server(
-    secret: String,
+    config: AppConfig,
     db: DatabaseConnection,
     redis: RedisPool,
-    discord_config: DiscordConfig,
+    now_playing_rx: NowPlayingReceiver,
+    comms: Arc<Mutex<ByersUnixStream>>,
     ctrl_c: Receiver<()>,
 ) -> Result<(), Error> {
-    let cookie_store = RedisSessionStore::from_pool(redis, Some("byers-session/".into()));
-    let session_layer = SessionLayer::new(cookie_store, secret.as_bytes())
+    let cookie_store = RedisSessionStore::from_pool(redis.clone(), Some("byers-session/".into()));
+    let session_layer = SessionLayer::new(cookie_store, config.secret.as_bytes())
         .with_same_site_policy(axum_sessions::SameSite::Lax);
 
+    if let Some(address) = config.metrics_address {
+        tokio::spawn(async move {
+            if let Err(e) = serve_metrics(address).await {
+                error!("Failed to serve metrics: {}", e);
+            }
+        });
+    }
+
     let app = Router::new()
         .route("/oauth2/callback", get(oauth2_callback))
         .route("/oauth2/login", get(oauth2_login))
         .route("/api/songs", get(song_list))
-        .with_state(AppState { db, discord_config })
+        .route("/api/now-playing", get(now_playing))
+        .route("/api/now-playing/stream", get(now_playing_stream))
+        .route("/api/now-playing/ws", get(now_playing_ws))
+        .nest("/api/v1", v1_router())
+        .merge(feeds_router())
+        .route("/health", get(health))
+        .with_state(AppState {
+            db,
+            redis,
+            discord_config: config.discord,
+            feed_config: FeedConfig::new(&config.public_url),
+            now_playing: now_playing_rx,
+            comms,
+        })
         .layer(session_layer);
 
     axum::Server::bind(&"0.0.0.0:8000".parse()?)
@@ -10,6 +10,7 @@ use tokio::sync::Mutex;
 
 use crate::app_config::EmojiConfig;
 use judeharley::communication::{ByersUnixStream, LiquidsoapCommunication};
+use judeharley::maintenance::scrobbling::ListenBrainz;
 
 lazy_static! {
     pub static ref INTENTS: GatewayIntents = GatewayIntents::non_privileged()
@@ -30,6 +31,11 @@ where
     pub redis_pool: fred::pool::RedisPool,
     pub redis_subscriber: fred::clients::SubscriberClient,
     pub emoji: EmojiConfig,
+    pub listenbrainz: ListenBrainz,
+    /// URL the web server is reachable at, for links to it
+    pub public_url: String,
+    /// Directory jingles are uploaded to
+    pub jingles_path: std::path::PathBuf,
 }
 
 pub struct BreadcrumbableContext<'a>(pub Context<'a>);
@@ -0,0 +1,167 @@
+//! Album premieres. `/admin queue_album` pushes an album's songs into the
+//! priority queue right away or stores them to be pushed at a scheduled time.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::prelude::Error;
+use judeharley::{
+    communication::{ByersUnixStream, LiquidsoapCommunication},
+    sea_orm::DatabaseConnection,
+    QueuedAlbums, Songs, Users,
+};
+
+/// How often the queuer checks for albums that are due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Parses when an album should start, either `HH:MM` for the next time the
+/// clock shows it or `YYYY-MM-DD HH:MM`, both in UTC.
+pub fn parse_start(input: &str, now: NaiveDateTime) -> Result<NaiveDateTime, String> {
+    let input = input.trim();
+    if let Ok(time) = NaiveTime::parse_from_str(input, "%H:%M") {
+        let today = now.date().and_time(time);
+        return Ok(if today > now {
+            today
+        } else {
+            today + chrono::Duration::days(1)
+        });
+    }
+
+    let (date, time) = input
+        .split_once(' ')
+        .ok_or_else(|| "expected `HH:MM` or `YYYY-MM-DD HH:MM`".to_string())?;
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("`{}` is not a date like 2026-04-13", date))?;
+    let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| format!("`{}` is not a time like 20:00", time))?;
+    let start = date.and_time(time);
+    if start <= now {
+        return Err("that time has already passed".to_string());
+    }
+
+    Ok(start)
+}
+
+/// Pushes songs into the priority queue in order and records them as
+/// requested by `user`, if any.
+pub async fn queue_songs(
+    comms: &mut ByersUnixStream,
+    songs: &[Songs],
+    user: Option<&Users>,
+    db: &DatabaseConnection,
+) -> Result<(), Error> {
+    for song in songs {
+        comms.priority_request(&song.file_path).await?;
+        if let Some(user) = user {
+            song.request(user, db).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn queue_album(
+    queued: &QueuedAlbums,
+    comms: &Mutex<ByersUnixStream>,
+    db: &DatabaseConnection,
+) -> Result<(), Error> {
+    let ids = queued.song_id_list();
+    let found = Songs::get_by_hashes(ids.iter().map(|id| id.to_string()).collect(), db).await?;
+    // keep the order the album was queued in, songs removed or hidden since
+    // are skipped
+    let songs = ids
+        .iter()
+        .filter_map(|id| found.iter().find(|song| song.file_hash == *id).cloned())
+        .filter(|song| !song.hidden)
+        .collect::<Vec<_>>();
+    if songs.len() < ids.len() {
+        warn!(
+            queued_album_id = queued.id,
+            missing = ids.len() - songs.len(),
+            "some songs of a queued album are gone"
+        );
+    }
+
+    let user = match queued.requested_by {
+        Some(user_id) => Some(Users::get_or_insert(user_id as u64, db).await?),
+        None => None,
+    };
+    {
+        let mut comms = comms.lock().await;
+        queue_songs(&mut comms, &songs, user.as_ref(), db).await?;
+    }
+
+    info!(
+        queued_album_id = queued.id,
+        album = %queued.album,
+        songs = songs.len(),
+        "Queued scheduled album"
+    );
+
+    Ok(())
+}
+
+async fn check(comms: &Mutex<ByersUnixStream>, db: &DatabaseConnection) -> Result<(), Error> {
+    let now = Utc::now().naive_utc();
+    for queued in QueuedAlbums::get_due(now, db).await? {
+        // marked first, a failing push shouldn't queue the album over and over
+        queued.mark_queued(now, db).await?;
+        queue_album(&queued, comms, db).await?;
+    }
+
+    Ok(())
+}
+
+pub fn spawn_album_queuer(db: DatabaseConnection, comms: Arc<Mutex<ByersUnixStream>>) {
+    info!("Spawning album queuer...");
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = check(&comms, &db).await {
+                error!(error = %e, "failed to queue scheduled albums");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_opt(18, 30, 0)
+            .unwrap()
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_start_time_of_day() {
+        assert_eq!(parse_start("20:00", now()), Ok(at(19, 20, 0)));
+        // already passed today, so tomorrow
+        assert_eq!(parse_start(" 04:13 ", now()), Ok(at(20, 4, 13)));
+        assert_eq!(parse_start("18:30", now()), Ok(at(20, 18, 30)));
+    }
+
+    #[test]
+    fn test_parse_start_date_and_time() {
+        assert_eq!(parse_start("2026-10-23 21:00", now()), Ok(at(23, 21, 0)));
+        assert!(parse_start("2026-10-18 21:00", now()).is_err());
+        assert!(parse_start("2026-13-01 21:00", now()).is_err());
+        assert!(parse_start("tomorrow", now()).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Announces scheduled programming blocks in every channel configured with
+//! `announcements` when they start.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, Utc};
+use poise::serenity_prelude::{ChannelId, CreateEmbed, CreateMessage, Http};
+use tracing::{error, info};
+
+use crate::commands::schedule::describe_programme;
+use crate::prelude::Error;
+use judeharley::{
+    schedule, sea_orm::DatabaseConnection, DiscordTimestamp, ScheduleBlocks, ServerChannelConfig,
+};
+
+/// How often the announcer checks for blocks that started.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Blocks that started longer ago than this, e.g. while Byers was down, are
+/// no longer announced.
+const ANNOUNCE_WINDOW: chrono::Duration = chrono::Duration::minutes(10);
+
+async fn announce(
+    http: &Http,
+    block: &ScheduleBlocks,
+    started_at: NaiveDateTime,
+    db: &DatabaseConnection,
+) -> Result<(), Error> {
+    let ends_at = started_at + chrono::Duration::minutes(block.duration.into());
+    let embed = CreateEmbed::new()
+        .title(format!("{} is on air!", block.name))
+        .description(format!(
+            "{}\n\nUntil {}",
+            describe_programme(block),
+            ends_at.short_time()
+        ));
+
+    for channel in ServerChannelConfig::get_all_announcement_channels(db).await? {
+        if let Err(e) = ChannelId::new(channel.id as u64)
+            .send_message(http, CreateMessage::new().embed(embed.clone()))
+            .await
+        {
+            error!(channel_id = channel.id, error = %e, "failed to announce schedule block");
+        }
+    }
+
+    Ok(())
+}
+
+async fn check(http: &Http, db: &DatabaseConnection) -> Result<(), Error> {
+    let now = Utc::now().naive_utc();
+    let blocks = ScheduleBlocks::get_all(db).await?;
+    let Some((block, started_at)) = schedule::active_block(&blocks, now) else {
+        return Ok(());
+    };
+    if block
+        .last_announced_at
+        .is_some_and(|announced| announced >= started_at)
+    {
+        return Ok(());
+    }
+
+    if now - started_at <= ANNOUNCE_WINDOW {
+        info!(block_id = block.id, name = %block.name, "Announcing schedule block");
+        announce(http, block, started_at, db).await?;
+    }
+    block.mark_announced(started_at, db).await?;
+
+    Ok(())
+}
+
+pub fn spawn_schedule_announcer(http: Arc<Http>, db: DatabaseConnection) {
+    info!("Spawning schedule announcer...");
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = check(&http, &db).await {
+                error!(error = %e, "schedule announcement check failed");
+            }
+        }
+    });
+}
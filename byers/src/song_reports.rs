@@ -0,0 +1,335 @@
+//! Song reports. `/song report` proposes corrections to a song's metadata or
+//! flags its file as broken, owners approve or reject them with the buttons
+//! posted in every channel configured with `song_reports`.
+
+use std::collections::HashSet;
+
+use poise::serenity_prelude::{
+    ButtonStyle, ChannelId, ComponentInteraction, Context, CreateActionRow, CreateButton,
+    CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseFollowup,
+    CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse, Http, UserId,
+};
+use tracing::{error, info, warn};
+
+use crate::commands::admin::song::EditTarget;
+use crate::prelude::{Data, Error};
+use judeharley::{
+    communication::ByersUnixStream,
+    controllers::song_reports::{STATUS_APPROVED, STATUS_REJECTED},
+    sea_orm::{DatabaseConnection, Set},
+    ServerChannelConfig, SongMetadataOverrides, SongReports, Songs, Users,
+};
+
+/// Boondollars a reporter can be credited with for an approved report.
+pub const REPORT_REWARD: i32 = 25;
+
+const CUSTOM_ID_PREFIX: &str = "song_report";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    ApproveWithReward,
+    Approve,
+    Reject,
+}
+
+impl Decision {
+    const ALL: [Decision; 3] = [
+        Decision::ApproveWithReward,
+        Decision::Approve,
+        Decision::Reject,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Decision::ApproveWithReward => "reward",
+            Decision::Approve => "approve",
+            Decision::Reject => "reject",
+        }
+    }
+
+    fn custom_id(self, report_id: i32) -> String {
+        format!("{}:{}:{}", CUSTOM_ID_PREFIX, self.as_str(), report_id)
+    }
+
+    /// The decision and report a review button stands for.
+    fn parse(custom_id: &str) -> Option<(Self, i32)> {
+        let mut parts = custom_id.split(':');
+        if parts.next() != Some(CUSTOM_ID_PREFIX) {
+            return None;
+        }
+        let decision = parts.next()?;
+        let decision = Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == decision)?;
+        let report_id = parts.next()?.parse().ok()?;
+
+        parts.next().is_none().then_some((decision, report_id))
+    }
+}
+
+fn song_name(song: Option<&Songs>, song_id: &str) -> String {
+    song.map(|song| format!("{} - {}", song.artist, song.title))
+        .unwrap_or_else(|| format!("a song that's gone ({})", song_id))
+}
+
+/// What the report proposes, next to what the song says now.
+fn report_lines(report: &SongReports, song: Option<&Songs>) -> Vec<String> {
+    let mut lines = [
+        ("Title", &report.title, song.map(|song| song.title.as_str())),
+        (
+            "Artist",
+            &report.artist,
+            song.map(|song| song.artist.as_str()),
+        ),
+        ("Album", &report.album, song.map(|song| song.album.as_str())),
+    ]
+    .into_iter()
+    .filter_map(|(field, proposed, current)| {
+        proposed.as_ref().map(|proposed| {
+            format!(
+                "**{}**: `{}` → `{}`",
+                field,
+                current.unwrap_or("?"),
+                proposed
+            )
+        })
+    })
+    .collect::<Vec<_>>();
+    if report.broken {
+        lines.push("**The file is broken**, approving hides the song".to_string());
+    }
+    if let Some(note) = &report.note {
+        lines.push(format!("> {}", note));
+    }
+
+    lines
+}
+
+fn review_embed(report: &SongReports, song: Option<&Songs>) -> CreateEmbed {
+    CreateEmbed::new()
+        .title(format!("Song Report #{}", report.id))
+        .description(format!(
+            "<@{}> reported {}:\n\n{}",
+            report.user_id,
+            song_name(song, &report.song_id),
+            report_lines(report, song).join("\n")
+        ))
+        .footer(CreateEmbedFooter::new(&report.song_id))
+}
+
+fn review_buttons(report_id: i32) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(Decision::ApproveWithReward.custom_id(report_id))
+            .label(format!("Approve (+{} Boondollars)", REPORT_REWARD))
+            .style(ButtonStyle::Success),
+        CreateButton::new(Decision::Approve.custom_id(report_id))
+            .label("Approve")
+            .style(ButtonStyle::Primary),
+        CreateButton::new(Decision::Reject.custom_id(report_id))
+            .label("Reject")
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+/// Posts a new report in the review channels, returns how many there are.
+pub async fn post_for_review(
+    http: &Http,
+    db: &DatabaseConnection,
+    report: &SongReports,
+    song: &Songs,
+) -> Result<usize, Error> {
+    let channels = ServerChannelConfig::get_all_song_report_channels(db).await?;
+    if channels.is_empty() {
+        warn!(
+            report_id = report.id,
+            "No song report channel is configured"
+        );
+    }
+
+    let message = CreateMessage::new()
+        .embed(review_embed(report, Some(song)))
+        .components(review_buttons(report.id));
+    for channel in &channels {
+        if let Err(e) = ChannelId::new(channel.id as u64)
+            .send_message(http, message.clone())
+            .await
+        {
+            error!(channel_id = channel.id, error = %e, "failed to post song report");
+        }
+    }
+
+    Ok(channels.len())
+}
+
+async fn approve(
+    report: &SongReports,
+    reviewer: i64,
+    data: &Data<ByersUnixStream>,
+) -> Result<String, Error> {
+    let Some(target) = EditTarget::by_id(&report.song_id, &data.db).await? else {
+        return Ok("Approved, but the song is gone.".to_string());
+    };
+
+    let changes = SongMetadataOverrides::edit(
+        &target.song_id,
+        &target.file_path,
+        &report.edit(),
+        Some(reviewer),
+        &data.db,
+    )
+    .await?;
+    // reindexed even without changes, in case an earlier approval stored the
+    // overrides but failed before they took effect
+    target.apply(&changes, data).await?;
+
+    if report.reward > 0 {
+        let user = Users::get_or_insert(report.user_id as u64, &data.db).await?;
+        user.update(
+            judeharley::entities::users::ActiveModel {
+                boonbucks: Set(user.boonbucks + report.reward),
+                ..Default::default()
+            },
+            &data.db,
+        )
+        .await?;
+
+        return Ok(format!(
+            "Approved by <@{}>, <@{}> got {} Boondollars.",
+            reviewer, report.user_id, report.reward
+        ));
+    }
+
+    Ok(format!("Approved by <@{}>.", reviewer))
+}
+
+/// Handles the review buttons. They are told apart by their custom ID, so
+/// reports can still be reviewed after a restart.
+pub async fn handle_review(
+    ctx: &Context,
+    interaction: &ComponentInteraction,
+    data: &Data<ByersUnixStream>,
+    owners: &HashSet<UserId>,
+) -> Result<(), Error> {
+    let Some((decision, report_id)) = Decision::parse(&interaction.data.custom_id) else {
+        return Ok(());
+    };
+
+    let reply = |content: &str| {
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .ephemeral(true),
+        )
+    };
+    if !owners.contains(&interaction.user.id) {
+        interaction
+            .create_response(ctx, reply("Only owners can review song reports."))
+            .await?;
+        return Ok(());
+    }
+
+    let reviewer = interaction.user.id.get() as i64;
+    let (status, reward) = match decision {
+        Decision::ApproveWithReward => (STATUS_APPROVED, REPORT_REWARD),
+        Decision::Approve => (STATUS_APPROVED, 0),
+        Decision::Reject => (STATUS_REJECTED, 0),
+    };
+    let Some(report) = SongReports::review(report_id, status, reviewer, reward, &data.db).await?
+    else {
+        interaction
+            .create_response(ctx, reply("This report was already reviewed."))
+            .await?;
+        return Ok(());
+    };
+
+    // reindexing the song may take a moment
+    interaction
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+    let outcome = if report.status == STATUS_APPROVED {
+        match approve(&report, reviewer, data).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                // leave it for another try instead of claiming it's done
+                SongReports::reopen(report.id, &data.db).await?;
+                interaction
+                    .create_followup(
+                        ctx,
+                        CreateInteractionResponseFollowup::new()
+                            .content(format!(
+                                "Approving the report failed, it's pending again: {}",
+                                e
+                            ))
+                            .ephemeral(true),
+                    )
+                    .await?;
+                return Err(e);
+            }
+        }
+    } else {
+        format!("Rejected by <@{}>.", reviewer)
+    };
+    info!(report_id, status = %report.status, reviewer, "Reviewed song report");
+
+    let song = Songs::get_by_hash(&report.song_id, &data.db).await?;
+    interaction
+        .edit_response(
+            ctx,
+            EditInteractionResponse::new()
+                .embed(review_embed(&report, song.as_ref()).field("Review", outcome, false))
+                .components(vec![]),
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> SongReports {
+        SongReports {
+            id: 4,
+            song_id: "abc".to_string(),
+            user_id: 413,
+            title: Some("Showtime (Original Mix)".to_string()),
+            artist: None,
+            album: Some("Homestuck Vol. 1".to_string()),
+            broken: true,
+            note: Some("the intro is cut off".to_string()),
+            status: "pending".to_string(),
+            reviewed_by: None,
+            reviewed_at: None,
+            reward: 0,
+            created_at: chrono::NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_decision_custom_id() {
+        for decision in Decision::ALL {
+            assert_eq!(
+                Decision::parse(&decision.custom_id(612)),
+                Some((decision, 612))
+            );
+        }
+        assert_eq!(Decision::parse("song_report:approve"), None);
+        assert_eq!(Decision::parse("song_report:approve:x"), None);
+        assert_eq!(Decision::parse("song_report:approve:1:2"), None);
+        assert_eq!(Decision::parse("album_next"), None);
+    }
+
+    #[test]
+    fn test_report_lines() {
+        assert_eq!(
+            report_lines(&report(), None),
+            vec![
+                "**Title**: `?` → `Showtime (Original Mix)`",
+                "**Album**: `?` → `Homestuck Vol. 1`",
+                "**The file is broken**, approving hides the song",
+                "> the intro is cut off",
+            ]
+        );
+    }
+}
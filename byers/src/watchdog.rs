@@ -0,0 +1,285 @@
+//! Dead air detection.
+//!
+//! Liquidsoap reports every song it starts to langley, so when the stream gets
+//! stuck or the playlist runs dry, `played_songs` stops growing. The watchdog
+//! notices the current song running past its duration, raises an alert and
+//! tries to get the stream going again.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, Utc};
+use fred::pool::RedisPool;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::prelude::Error;
+use judeharley::{
+    alerts::Severity,
+    communication::{ByersUnixStream, LiquidsoapCommunication},
+    sea_orm::DatabaseConnection,
+    DiscordTimestamp, LiveSessions, PlayedSongs, Songs,
+};
+
+/// How often the watchdog checks on the stream.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a single recovery step may take.
+const RECOVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Recovery steps to try before leaving the stream to a human: a skip, then a
+/// reconnect followed by another skip.
+const MAX_RECOVERY_ATTEMPTS: u32 = 2;
+
+/// Whether a song that started at `played_at` should have ended more than
+/// `grace_period` before `now`.
+fn is_overdue(
+    played_at: NaiveDateTime,
+    duration: f64,
+    now: NaiveDateTime,
+    grace_period: Duration,
+) -> bool {
+    let expected_end = played_at
+        + chrono::Duration::milliseconds((duration.max(0.0) * 1000.0) as i64)
+        + chrono::Duration::seconds(grace_period.as_secs() as i64);
+
+    now > expected_end
+}
+
+/// When the latest song started as far as the watchdog is concerned: when it
+/// was played, or when the last live session ended if that was later, since
+/// the stream only went back to the playlist then.
+fn watching_since(
+    played_at: NaiveDateTime,
+    session_ended_at: Option<NaiveDateTime>,
+) -> NaiveDateTime {
+    session_ended_at.map_or(played_at, |ended_at| ended_at.max(played_at))
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct DeadAir {
+    /// The play the stream got stuck on, if it is stuck.
+    stuck_on: Option<i32>,
+    recovery_attempts: u32,
+}
+
+impl DeadAir {
+    /// Forgets about the stream being stuck, returns whether it was.
+    fn clear(&mut self) -> bool {
+        self.recovery_attempts = 0;
+
+        self.stuck_on.take().is_some()
+    }
+}
+
+struct Watchdog {
+    db: DatabaseConnection,
+    redis_pool: RedisPool,
+    comms: Arc<Mutex<ByersUnixStream>>,
+    grace_period: Duration,
+    dead_air: DeadAir,
+}
+
+impl Watchdog {
+    async fn check(&mut self) -> Result<(), Error> {
+        // no songs are reported while a DJ is on air, which isn't dead air
+        let last_session = LiveSessions::get_latest(1, &self.db).await?.pop();
+        if last_session
+            .as_ref()
+            .is_some_and(|session| session.ended_at.is_none())
+        {
+            if self.dead_air.clear() {
+                info!("A DJ went live, no longer watching for dead air");
+            }
+
+            return Ok(());
+        }
+
+        let Some(latest) = PlayedSongs::get_latest(&self.db).await? else {
+            return Ok(());
+        };
+        let song = Songs::get_by_hash(&latest.song_id, &self.db).await?;
+        let duration = song.as_ref().map(|s| s.duration).unwrap_or_default();
+
+        let now = Utc::now().naive_utc();
+        let since = watching_since(
+            latest.played_at,
+            last_session.and_then(|session| session.ended_at),
+        );
+        if !is_overdue(since, duration, now, self.grace_period) {
+            if self.dead_air.clear() {
+                info!("Stream recovered from dead air");
+                self.alert(
+                    Severity::Info,
+                    "dead_air_recovered",
+                    "Stream recovered",
+                    "Liquidsoap is reporting played songs again.".to_string(),
+                )
+                .await;
+            }
+
+            return Ok(());
+        }
+
+        if self.dead_air.stuck_on != Some(latest.id) {
+            self.dead_air = DeadAir {
+                stuck_on: Some(latest.id),
+                recovery_attempts: 0,
+            };
+
+            let current = match &song {
+                Some(song) => format!("{} - {}", song.artist, song.title),
+                None => format!("Unknown song `{}`", latest.song_id),
+            };
+            warn!(played_song_id = latest.id, "Dead air detected");
+            self.alert(
+                Severity::Critical,
+                "dead_air",
+                "Dead air detected",
+                format!(
+                    "No song has been reported since {}, the stream may be stuck or the playlist empty.\nLast song: {}",
+                    latest.played_at.relative_time(),
+                    current
+                ),
+            )
+            .await;
+        }
+
+        if self.dead_air.recovery_attempts < MAX_RECOVERY_ATTEMPTS {
+            self.dead_air.recovery_attempts += 1;
+            let step = if self.dead_air.recovery_attempts == 1 {
+                "Skipped the current song"
+            } else {
+                "Reconnected to Liquidsoap and skipped the current song"
+            };
+
+            let description = match self.recover().await {
+                Ok(()) => format!("{}, waiting for the next song to be reported.", step),
+                Err(e) => format!("Recovery step failed: {}", e),
+            };
+            self.alert(
+                Severity::Warning,
+                &format!("dead_air_recovery_{}", self.dead_air.recovery_attempts),
+                &format!(
+                    "Dead air recovery attempt {}/{}",
+                    self.dead_air.recovery_attempts, MAX_RECOVERY_ATTEMPTS
+                ),
+                description,
+            )
+            .await;
+        } else if self.dead_air.recovery_attempts == MAX_RECOVERY_ATTEMPTS {
+            self.dead_air.recovery_attempts += 1;
+            self.alert(
+                Severity::Critical,
+                "dead_air_recovery_failed",
+                "Dead air recovery failed",
+                "The stream is still stuck, someone needs to take a look.".to_string(),
+            )
+            .await;
+        }
+
+        Ok(())
+    }
+
+    async fn recover(&self) -> Result<(), Error> {
+        let reconnect = self.dead_air.recovery_attempts > 1;
+        let mut comms = self.comms.lock().await;
+
+        tokio::time::timeout(RECOVERY_TIMEOUT, async {
+            if reconnect {
+                comms.reconnect().await?;
+            }
+            comms.send_wait("lumiradio.skip").await?;
+
+            Ok::<_, Error>(())
+        })
+        .await?
+    }
+
+    async fn alert(&self, severity: Severity, key: &str, title: &str, message: String) {
+        crate::alerts::raise(&self.redis_pool, severity, key, title, message).await;
+    }
+}
+
+pub fn spawn_dead_air_watchdog(
+    db: DatabaseConnection,
+    redis_pool: RedisPool,
+    comms: Arc<Mutex<ByersUnixStream>>,
+    grace_period: Duration,
+) {
+    info!(
+        grace_period = grace_period.as_secs(),
+        "Spawning dead air watchdog..."
+    );
+
+    tokio::spawn(async move {
+        let mut watchdog = Watchdog {
+            db,
+            redis_pool,
+            comms,
+            grace_period,
+            dead_air: DeadAir::default(),
+        };
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = watchdog.check().await {
+                error!(error = %e, "dead air check failed");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(seconds: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 18)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            + chrono::Duration::seconds(seconds.into())
+    }
+
+    #[test]
+    fn test_is_overdue() {
+        let grace = Duration::from_secs(60);
+
+        assert!(!is_overdue(at(0), 180.0, at(100), grace));
+        assert!(!is_overdue(at(0), 180.0, at(240), grace));
+        assert!(is_overdue(at(0), 180.0, at(241), grace));
+        assert!(is_overdue(at(0), 0.0, at(61), grace));
+    }
+
+    #[test]
+    fn test_session_just_ended() {
+        let grace = Duration::from_secs(60);
+
+        // the last song was played long before the DJ stopped
+        let since = watching_since(at(0), Some(at(3600)));
+        assert_eq!(since, at(3600));
+        assert!(!is_overdue(since, 180.0, at(3601), grace));
+        assert!(is_overdue(since, 180.0, at(3841), grace));
+
+        // songs played after the session count from when they were played
+        assert_eq!(watching_since(at(4000), Some(at(3600))), at(4000));
+        assert_eq!(watching_since(at(4000), None), at(4000));
+    }
+
+    #[test]
+    fn test_dead_air_cleared() {
+        let mut dead_air = DeadAir {
+            stuck_on: Some(413),
+            recovery_attempts: MAX_RECOVERY_ATTEMPTS,
+        };
+
+        // the watchdog clears it while a DJ is live
+        assert!(dead_air.clear());
+        assert_eq!(dead_air, DeadAir::default());
+        assert!(!dead_air.clear());
+    }
+}
@@ -1,11 +1,21 @@
 use std::{
+    net::SocketAddr,
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
 
 use clap::{Parser, Subcommand};
-use judeharley::{sea_orm::DatabaseConnection, SUPPORTED_AUDIO_FORMATS};
+use fred::pool::RedisPool;
+use futures_util::{future::BoxFuture, FutureExt, TryFutureExt};
+use judeharley::{
+    alerts::{self, Alert, Severity},
+    maintenance::lyrics,
+    metrics,
+    sea_orm::DatabaseConnection,
+    webhooks::{self, SongsIndexed, WebhookEvent},
+    JudeHarleyError, SUPPORTED_AUDIO_FORMATS,
+};
 use notify::Watcher;
 use tokio::sync::{mpsc::Receiver, Mutex};
 use tracing::{debug, error, info, warn};
@@ -30,6 +40,41 @@ struct HouseKeeping {
     dry_run: bool,
     #[clap(short = 'D', long)]
     database_url: String,
+    /// URL of Icecast's `status-json.xsl`, listener statistics are only
+    /// collected if this is set
+    #[clap(long)]
+    icecast_status_url: Option<String>,
+    /// Seconds between two listener samples
+    #[clap(long, default_value_t = 60)]
+    listener_interval: u64,
+    /// Address to serve Prometheus metrics on, e.g. `0.0.0.0:9000`
+    #[clap(long)]
+    metrics_address: Option<SocketAddr>,
+    /// Redis to publish operational alerts to, alerts are only logged if
+    /// this is not set
+    #[clap(long)]
+    redis_url: Option<String>,
+    /// Base URL of a ListenBrainz compatible server, queued scrobbles are
+    /// only submitted if this is set
+    #[clap(long)]
+    listenbrainz_url: Option<String>,
+    /// Token of the station's own ListenBrainz account, every play is
+    /// scrobbled to it if set
+    #[clap(long)]
+    listenbrainz_token: Option<String>,
+    /// Seconds between two passes over the scrobble queue
+    #[clap(long, default_value_t = 30)]
+    scrobble_interval: u64,
+    /// Seconds between two passes over the webhook delivery queue
+    #[clap(long, default_value_t = 10)]
+    webhook_interval: u64,
+    /// Directory with the jingles played between songs, they are only
+    /// synced if this is set
+    #[clap(long)]
+    jingles_path: Option<PathBuf>,
+    /// Seconds between two scans of the jingles directory
+    #[clap(long, default_value_t = 60)]
+    jingle_scan_interval: u64,
 
     music_path: PathBuf,
 }
@@ -92,7 +137,87 @@ fn async_watcher(
     Ok((watcher, rx))
 }
 
-async fn async_watch<P: AsRef<Path>>(path: P, db: DatabaseConnection) -> anyhow::Result<()> {
+/// Publishes alerts if house keeping was given a Redis URL.
+#[derive(Clone)]
+struct Alerts(Option<RedisPool>);
+
+impl Alerts {
+    async fn raise(&self, severity: Severity, key: &str, title: &str, message: String) {
+        if let Some(redis_pool) = &self.0 {
+            alerts::raise(
+                redis_pool,
+                Alert::new("frohike", severity, key, title, message),
+            )
+            .await;
+        }
+    }
+}
+
+/// Logs and alerts about a file the watcher failed to (un)index, instead of
+/// stopping the watcher over it.
+async fn report_watch_error(result: Result<(), JudeHarleyError>, path: &Path, alerts: &Alerts) {
+    let Err(e) = result else {
+        return;
+    };
+
+    error!("failed to update index for {}: {}", path.display(), e);
+    metrics::record_error(&e);
+    alerts
+        .raise(
+            Severity::Warning,
+            "watcher",
+            "Failed to update the library index",
+            format!("{}: {}", path.display(), e),
+        )
+        .await;
+}
+
+/// Indexes files that showed up in the library and tells webhooks about the
+/// songs that were added.
+async fn index_new_files(
+    db: &DatabaseConnection,
+    files: impl IntoIterator<Item = PathBuf>,
+    music_path: &Path,
+    alerts: &Alerts,
+) {
+    let mut songs = vec![];
+    for file in files {
+        match judeharley::maintenance::indexing::index_file(db, &file, music_path).await {
+            Ok(Some(song)) => songs.push(song.into()),
+            Ok(None) => {}
+            Err(e) => report_watch_error(Err(e), &file, alerts).await,
+        }
+    }
+
+    if !songs.is_empty() {
+        webhooks::trigger(WebhookEvent::SongsIndexed, &SongsIndexed { songs }, db).await;
+    }
+}
+
+/// The audio file whose lyrics file `event` changed, if any. Lyrics are read
+/// along with the audio file they sit next to, so that one is indexed again.
+fn lyrics_owner(event: &notify::event::Event) -> Option<PathBuf> {
+    let changed = matches!(
+        event.kind,
+        notify::event::EventKind::Create(_)
+            | notify::event::EventKind::Modify(_)
+            | notify::event::EventKind::Remove(_)
+            | notify::event::EventKind::Access(notify::event::AccessKind::Close(
+                notify::event::AccessMode::Write,
+            ))
+    );
+    if !changed {
+        return None;
+    }
+
+    lyrics::sidecar_audio_file(event.paths.first()?)
+}
+
+async fn async_watch<P: AsRef<Path>>(
+    path: P,
+    db: DatabaseConnection,
+    alerts: Alerts,
+) -> anyhow::Result<()> {
     let tokio_rt = tokio::runtime::Handle::current();
     let (mut watcher, mut rx) = async_watcher(tokio_rt)?;
     watcher.watch(path.as_ref(), notify::RecursiveMode::Recursive)?;
@@ -102,18 +227,40 @@ async fn async_watch<P: AsRef<Path>>(path: P, db: DatabaseConnection) -> anyhow:
             Ok(event) => event,
             Err(e) => {
                 error!("watch error: {}", e);
+                metrics::WATCHER_EVENTS.with_label_values(&["error"]).inc();
                 continue;
             }
         };
 
+        let kind = match &event.kind {
+            notify::event::EventKind::Access(_) => "access",
+            notify::event::EventKind::Create(_) => "create",
+            notify::event::EventKind::Modify(_) => "modify",
+            notify::event::EventKind::Remove(_) => "remove",
+            _ => "other",
+        };
+        metrics::WATCHER_EVENTS.with_label_values(&[kind]).inc();
+
+        if let Some(audio_file) = lyrics_owner(&event) {
+            debug!("lyrics changed: {:?}", event.paths);
+            report_watch_error(
+                judeharley::maintenance::indexing::reindex_file(&db, &audio_file, path.as_ref())
+                    .await
+                    .map(|_| ()),
+                &audio_file,
+                &alerts,
+            )
+            .await;
+            continue;
+        }
+
         match &event.kind {
             notify::event::EventKind::Access(notify::event::AccessKind::Close(
                 notify::event::AccessMode::Write,
             )) => {
                 debug!("file written: {:?}", event.paths);
                 let file_path = event.paths.first().unwrap();
-                judeharley::maintenance::indexing::index_file(&db, file_path, path.as_ref())
-                    .await?;
+                index_new_files(&db, [file_path.clone()], path.as_ref(), &alerts).await;
             }
             notify::event::EventKind::Modify(notify::event::ModifyKind::Name(
                 notify::event::RenameMode::From,
@@ -122,17 +269,29 @@ async fn async_watch<P: AsRef<Path>>(path: P, db: DatabaseConnection) -> anyhow:
                 let file_path = event.paths.first().unwrap();
 
                 if file_path.is_file() {
-                    judeharley::maintenance::indexing::drop_index(&db, file_path, path.as_ref())
-                        .await
-                        .unwrap();
+                    report_watch_error(
+                        judeharley::maintenance::indexing::drop_index(
+                            &db,
+                            file_path,
+                            path.as_ref(),
+                        )
+                        .await,
+                        file_path,
+                        &alerts,
+                    )
+                    .await;
                 } else if file_path.is_dir() {
-                    judeharley::maintenance::indexing::drop_index_folder(
-                        &db,
+                    report_watch_error(
+                        judeharley::maintenance::indexing::drop_index_folder(
+                            &db,
+                            file_path,
+                            path.as_ref(),
+                        )
+                        .await,
                         file_path,
-                        path.as_ref(),
+                        &alerts,
                     )
-                    .await
-                    .unwrap();
+                    .await;
                 }
             }
             notify::event::EventKind::Modify(notify::event::ModifyKind::Name(
@@ -142,43 +301,36 @@ async fn async_watch<P: AsRef<Path>>(path: P, db: DatabaseConnection) -> anyhow:
                 let file_path = event.paths.first().unwrap();
 
                 if file_path.is_file() {
-                    judeharley::maintenance::indexing::index_file(&db, file_path, path.as_ref())
-                        .await
-                        .unwrap();
+                    index_new_files(&db, [file_path.clone()], path.as_ref(), &alerts).await;
                 } else if file_path.is_dir() {
-                    for entry in walkdir::WalkDir::new(file_path) {
-                        let entry = entry.unwrap();
-                        if entry.file_type().is_file() {
-                            judeharley::maintenance::indexing::index_file(
-                                &db,
-                                entry.path(),
-                                path.as_ref(),
-                            )
-                            .await
-                            .unwrap();
-                        }
-                    }
+                    let files = walkdir::WalkDir::new(file_path)
+                        .into_iter()
+                        .filter_map(|entry| {
+                            entry
+                                .map_err(|e| warn!("failed to walk directory: {}", e))
+                                .ok()
+                        })
+                        .filter(|entry| entry.file_type().is_file())
+                        .map(|entry| entry.into_path());
+                    index_new_files(&db, files, path.as_ref(), &alerts).await;
                 }
             }
             notify::event::EventKind::Create(notify::event::CreateKind::Any) => {
                 debug!("file or folder created: {:?}", event.paths);
                 let file_path = event.paths.first().unwrap();
                 if file_path.is_file() {
-                    judeharley::maintenance::indexing::index_file(&db, file_path, path.as_ref())
-                        .await?;
+                    index_new_files(&db, [file_path.clone()], path.as_ref(), &alerts).await;
                 } else if file_path.is_dir() {
-                    for entry in walkdir::WalkDir::new(file_path) {
-                        let entry = entry.unwrap();
-                        if entry.file_type().is_file() {
-                            judeharley::maintenance::indexing::index_file(
-                                &db,
-                                entry.path(),
-                                path.as_ref(),
-                            )
-                            .await
-                            .unwrap();
-                        }
-                    }
+                    let files = walkdir::WalkDir::new(file_path)
+                        .into_iter()
+                        .filter_map(|entry| {
+                            entry
+                                .map_err(|e| warn!("failed to walk directory: {}", e))
+                                .ok()
+                        })
+                        .filter(|entry| entry.file_type().is_file())
+                        .map(|entry| entry.into_path());
+                    index_new_files(&db, files, path.as_ref(), &alerts).await;
                 } else {
                     warn!(
                         "file or folder is not actually a file, nor a folder: {:?}",
@@ -191,17 +343,29 @@ async fn async_watch<P: AsRef<Path>>(path: P, db: DatabaseConnection) -> anyhow:
                 let file_path = event.paths.first().unwrap();
 
                 if file_path.is_file() {
-                    judeharley::maintenance::indexing::drop_index(&db, file_path, path.as_ref())
-                        .await
-                        .unwrap();
+                    report_watch_error(
+                        judeharley::maintenance::indexing::drop_index(
+                            &db,
+                            file_path,
+                            path.as_ref(),
+                        )
+                        .await,
+                        file_path,
+                        &alerts,
+                    )
+                    .await;
                 } else if file_path.is_dir() {
-                    judeharley::maintenance::indexing::drop_index_folder(
-                        &db,
+                    report_watch_error(
+                        judeharley::maintenance::indexing::drop_index_folder(
+                            &db,
+                            file_path,
+                            path.as_ref(),
+                        )
+                        .await,
                         file_path,
-                        path.as_ref(),
+                        &alerts,
                     )
-                    .await
-                    .unwrap();
+                    .await;
                 } else {
                     warn!(
                         "file or folder is not actually a file, nor a folder, dropping by extension"
@@ -209,13 +373,17 @@ async fn async_watch<P: AsRef<Path>>(path: P, db: DatabaseConnection) -> anyhow:
                     if let Some(extension) = file_path.extension() {
                         let ext_str = extension.to_string_lossy().to_lowercase();
                         if SUPPORTED_AUDIO_FORMATS.contains(&ext_str.as_str()) {
-                            judeharley::maintenance::indexing::drop_index(
-                                &db,
+                            report_watch_error(
+                                judeharley::maintenance::indexing::drop_index(
+                                    &db,
+                                    file_path,
+                                    path.as_ref(),
+                                )
+                                .await,
                                 file_path,
-                                path.as_ref(),
+                                &alerts,
                             )
-                            .await
-                            .unwrap();
+                            .await;
                         }
                     }
                 }
@@ -223,16 +391,28 @@ async fn async_watch<P: AsRef<Path>>(path: P, db: DatabaseConnection) -> anyhow:
             notify::event::EventKind::Remove(notify::event::RemoveKind::File) => {
                 debug!("file removed: {:?}", event.paths);
                 let file_path = event.paths.first().unwrap();
-                judeharley::maintenance::indexing::drop_index(&db, file_path, path.as_ref())
-                    .await
-                    .unwrap();
+                report_watch_error(
+                    judeharley::maintenance::indexing::drop_index(&db, file_path, path.as_ref())
+                        .await,
+                    file_path,
+                    &alerts,
+                )
+                .await;
             }
             notify::event::EventKind::Remove(notify::event::RemoveKind::Folder) => {
                 debug!("folder removed: {:?}", event.paths);
                 let file_path = event.paths.first().unwrap();
-                judeharley::maintenance::indexing::drop_index_folder(&db, file_path, path.as_ref())
-                    .await
-                    .unwrap();
+                report_watch_error(
+                    judeharley::maintenance::indexing::drop_index_folder(
+                        &db,
+                        file_path,
+                        path.as_ref(),
+                    )
+                    .await,
+                    file_path,
+                    &alerts,
+                )
+                .await;
             }
             _ => (),
         }
@@ -241,6 +421,25 @@ async fn async_watch<P: AsRef<Path>>(path: P, db: DatabaseConnection) -> anyhow:
     Ok(())
 }
 
+async fn serve_metrics(address: SocketAddr) -> anyhow::Result<()> {
+    let app = axum::Router::new().route(
+        "/metrics",
+        axum::routing::get(|| async {
+            (
+                [(axum::http::header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+                metrics::gather(),
+            )
+        }),
+    );
+
+    info!("serving metrics on {}", address);
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
@@ -266,16 +465,111 @@ async fn main() -> anyhow::Result<()> {
             // if they are new, index them into the database
             let db = judeharley::connect_database(&house_keeping.database_url).await?;
 
-            let tasks = vec![async_watch(house_keeping.music_path.clone(), db.clone())];
+            let alerts = match &house_keeping.redis_url {
+                Some(redis_url) => {
+                    let redis_pool = judeharley::redis_pool(redis_url)?;
+                    let _ = redis_pool.connect();
+                    redis_pool.wait_for_connect().await?;
+                    Alerts(Some(redis_pool))
+                }
+                None => Alerts(None),
+            };
+
+            let mut tasks: Vec<(&str, BoxFuture<'static, anyhow::Result<()>>)> = vec![
+                (
+                    "watcher",
+                    async_watch(house_keeping.music_path.clone(), db.clone(), alerts.clone())
+                        .boxed(),
+                ),
+                (
+                    "webhooks",
+                    webhooks::run_dispatcher(
+                        db.clone(),
+                        Duration::from_secs(house_keeping.webhook_interval),
+                    )
+                    .map_err(Into::into)
+                    .boxed(),
+                ),
+            ];
+
+            if let Some(address) = house_keeping.metrics_address {
+                metrics::init();
+                tasks.push(("metrics", serve_metrics(address).boxed()));
+            }
+
+            if let Some(status_url) = house_keeping.icecast_status_url.clone() {
+                info!("collecting listener statistics from {}", status_url);
+                let db = db.clone();
+                let interval = Duration::from_secs(house_keeping.listener_interval);
+                tasks.push((
+                    "listeners",
+                    async move {
+                        judeharley::maintenance::listeners::poll_listeners(db, status_url, interval)
+                            .await
+                            .map_err(Into::into)
+                    }
+                    .boxed(),
+                ));
+            }
+
+            if let Some(base_url) = house_keeping.listenbrainz_url.clone() {
+                let db = db.clone();
+                let listenbrainz =
+                    judeharley::maintenance::scrobbling::ListenBrainz::new(&base_url)?;
+                // an empty token is what compose passes if none is configured
+                let station_token = house_keeping
+                    .listenbrainz_token
+                    .clone()
+                    .filter(|token| !token.is_empty());
+                let interval = Duration::from_secs(house_keeping.scrobble_interval);
+                tasks.push((
+                    "scrobbler",
+                    async move {
+                        judeharley::maintenance::scrobbling::run_scrobbler(
+                            db,
+                            listenbrainz,
+                            station_token,
+                            interval,
+                        )
+                        .await
+                        .map_err(Into::into)
+                    }
+                    .boxed(),
+                ));
+            }
+
+            if let Some(jingles_path) = house_keeping.jingles_path.clone() {
+                info!("syncing jingles from {}", jingles_path.display());
+                let db = db.clone();
+                let interval = Duration::from_secs(house_keeping.jingle_scan_interval);
+                tasks.push((
+                    "jingles",
+                    async move {
+                        judeharley::jingles::run_sync(db, jingles_path, interval)
+                            .await
+                            .map_err(Into::into)
+                    }
+                    .boxed(),
+                ));
+            }
 
             let (tx, mut rx) = tokio::sync::mpsc::channel(100);
-            for task in tasks {
+            for (name, task) in tasks {
                 let tx = tx.clone();
-                debug!("spawning task");
+                let alerts = alerts.clone();
+                debug!("spawning task {}", name);
                 tokio::spawn(async move {
                     let result = task.await;
                     if let Err(e) = result {
-                        error!("task failed: {}", e);
+                        error!("task {} failed: {}", name, e);
+                        alerts
+                            .raise(
+                                Severity::Critical,
+                                &format!("task:{}", name),
+                                &format!("House keeping task {} stopped", name),
+                                e.to_string(),
+                            )
+                            .await;
                     }
                     tx.send(()).await.unwrap();
                 });
@@ -0,0 +1,134 @@
+//! Operational alerts.
+//!
+//! Any service can raise an alert, it is published on [`ALERTS_CHANNEL`] and
+//! Byers posts it in every channel configured for ops alerts. Alerts sharing a
+//! source and key are rate limited through Redis, so a failure that repeats
+//! every few seconds doesn't flood the channel.
+
+use chrono::{DateTime, Utc};
+use fred::{
+    pool::RedisPool,
+    prelude::{KeysInterface, PubsubInterface},
+    types::{Expiration, SetOptions},
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::prelude::*;
+
+pub const ALERTS_CHANNEL: &str = "byers:alerts";
+
+/// Seconds before an alert with the same source and key is posted again.
+pub const DEFAULT_RATE_LIMIT: i64 = 5 * 60;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// The service raising the alert, e.g. `langley`.
+    pub source: String,
+    pub severity: Severity,
+    /// Identifies the kind of failure within the source, for rate limiting.
+    pub key: String,
+    pub title: String,
+    pub message: String,
+    pub raised_at: DateTime<Utc>,
+}
+
+impl Alert {
+    pub fn new(
+        source: impl Into<String>,
+        severity: Severity,
+        key: impl Into<String>,
+        title: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            source: source.into(),
+            severity,
+            key: key.into(),
+            title: title.into(),
+            message: message.into(),
+            raised_at: Utc::now(),
+        }
+    }
+
+    fn rate_limit_key(&self) -> String {
+        format!("alerts:{}:{}", self.source, self.key)
+    }
+}
+
+/// Publishes `alert` unless one with the same source and key went out in the
+/// last `rate_limit` seconds. Returns whether it was published.
+pub async fn publish(pool: &RedisPool, alert: &Alert, rate_limit: i64) -> Result<bool> {
+    let acquired: Option<String> = pool
+        .set(
+            alert.rate_limit_key(),
+            alert.raised_at.timestamp(),
+            Some(Expiration::EX(rate_limit)),
+            Some(SetOptions::NX),
+            false,
+        )
+        .await?;
+    if acquired.is_none() {
+        debug!(source = %alert.source, key = %alert.key, "alert is rate limited");
+        return Ok(false);
+    }
+
+    pool.publish::<i64, _, _>(ALERTS_CHANNEL, serde_json::to_string(alert)?)
+        .await?;
+
+    Ok(true)
+}
+
+/// Publishes `alert` with the [`DEFAULT_RATE_LIMIT`], logging instead of
+/// failing if that doesn't work, as the caller is usually busy handling
+/// another error already.
+pub async fn raise(pool: &RedisPool, alert: Alert) {
+    if let Err(e) = publish(pool, &alert, DEFAULT_RATE_LIMIT).await {
+        warn!(
+            source = %alert.source,
+            key = %alert.key,
+            title = %alert.title,
+            error = %e,
+            "failed to publish alert"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alert_round_trip() {
+        let alert = Alert::new(
+            "langley",
+            Severity::Error,
+            "insert_played_song",
+            "Failed to record a play",
+            "connection refused",
+        );
+
+        let json = serde_json::to_value(&alert).unwrap();
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["source"], "langley");
+
+        let parsed: Alert = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, alert);
+        assert_eq!(parsed.rate_limit_key(), "alerts:langley:insert_played_song");
+    }
+
+    #[test]
+    fn test_severity_order() {
+        assert!(Severity::Critical > Severity::Error);
+        assert!(Severity::Warning > Severity::Info);
+    }
+}
@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use serde::Deserialize;
 use tracing::{debug, warn};
 
-use crate::JudeHarleyError;
+use crate::{metrics, JudeHarleyError};
 
 #[derive(Deserialize, Debug)]
 pub struct QueueItem {
@@ -23,10 +23,26 @@ pub trait LiquidsoapCommunication {
     async fn song_requests(&mut self) -> Result<Vec<QueueItem>, Self::Error>;
 
     async fn request_song(&mut self, song: &str) -> Result<String, Self::Error> {
-        self.send_wait(&format!("srq.push {}", song)).await
+        let result = self.send_wait(&format!("srq.push {}", song)).await?;
+        metrics::SONG_REQUESTS.with_label_values(&["srq"]).inc();
+        Ok(result)
     }
     async fn priority_request(&mut self, song: &str) -> Result<String, Self::Error> {
-        self.send_wait(&format!("prioq.push {}", song)).await
+        let result = self.send_wait(&format!("prioq.push {}", song)).await?;
+        metrics::SONG_REQUESTS.with_label_values(&["prioq"]).inc();
+        Ok(result)
+    }
+    /// Switches between the live harbor input and automation.
+    async fn set_live(&mut self, live: bool) -> Result<String, Self::Error> {
+        self.send_wait(&format!("var.set live = {}", live)).await
+    }
+    /// Describes whether a DJ is connected to the harbor.
+    async fn harbor_status(&mut self) -> Result<String, Self::Error> {
+        self.send_wait("dj.status").await
+    }
+    /// Disconnects the DJ connected to the harbor, if any.
+    async fn kick_dj(&mut self) -> Result<String, Self::Error> {
+        self.send_wait("dj.stop").await
     }
 }
 
@@ -54,6 +70,7 @@ impl ByersUnixStream {
     }
 
     pub async fn reconnect(&mut self) -> Result<(), std::io::Error> {
+        metrics::LIQUIDSOAP_RECONNECTS.inc();
         self.stream = Self::new().await?.stream;
         Ok(())
     }
@@ -137,7 +154,9 @@ impl LiquidsoapCommunication for ByersUnixStream {
     type Error = JudeHarleyError;
 
     async fn send_wait(&mut self, command: &str) -> Result<String, Self::Error> {
+        let timer = metrics::LIQUIDSOAP_LATENCY.start_timer();
         let result = self.write_str_and_wait_for_response(command).await;
+        timer.observe_duration();
 
         if let Err(e) = result {
             if e.kind() == std::io::ErrorKind::BrokenPipe {
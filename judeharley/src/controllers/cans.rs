@@ -96,13 +96,9 @@ impl Model {
         }
 
         if amount <= current {
-            Self::remove_last_n(current - amount, db)
-                .await
-                .map_err(Into::into)
+            Self::remove_last_n(current - amount, db).await
         } else {
-            Self::insert_n(added_by, amount - current, db)
-                .await
-                .map_err(Into::into)
+            Self::insert_n(added_by, amount - current, db).await
         }
     }
 }
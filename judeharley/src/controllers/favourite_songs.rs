@@ -1,4 +1,6 @@
-use sea_orm::{prelude::*, Iterable, QuerySelect, Set};
+use std::collections::HashMap;
+
+use sea_orm::{prelude::*, FromQueryResult, Iterable, QueryOrder, QuerySelect, Set};
 
 use crate::custom_entities::songs::{
     Column as SongColumn, Entity as SongEntity, Model as SongModel,
@@ -9,6 +11,12 @@ use crate::entities::{
 };
 use crate::prelude::JudeHarleyError;
 
+#[derive(FromQueryResult, Debug, Clone)]
+struct SongFavouriteCount {
+    song_id: String,
+    count: i64,
+}
+
 impl Model {
     pub async fn get_by_user_and_song(
         user: &UserModel,
@@ -44,6 +52,35 @@ impl Model {
             .map_err(Into::into)
     }
 
+    /// The user's `limit` most recently favourited songs, newest first.
+    pub async fn get_recent_by_user(
+        user_id: i64,
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<(Self, SongModel)>, JudeHarleyError> {
+        let favourites = Entity::find()
+            .filter(Column::UserId.eq(user_id))
+            .order_by_desc(Column::CreatedAt)
+            .order_by_desc(Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        let mut songs =
+            SongModel::get_by_hashes(favourites.iter().map(|f| f.song_id.clone()).collect(), db)
+                .await?;
+
+        Ok(favourites
+            .into_iter()
+            .filter_map(|favourite| {
+                let index = songs
+                    .iter()
+                    .position(|song| song.file_hash == favourite.song_id)?;
+                Some((favourite, songs.swap_remove(index)))
+            })
+            .collect())
+    }
+
     pub async fn get_by_song(
         song: &SongModel,
         db: &DatabaseConnection,
@@ -59,6 +96,25 @@ impl Model {
             .map_err(Into::into)
     }
 
+    /// How many users favourited each song, songs nobody favourited are left out.
+    pub async fn count_by_song(
+        db: &DatabaseConnection,
+    ) -> Result<HashMap<String, i64>, JudeHarleyError> {
+        let counts = Entity::find()
+            .select_only()
+            .column(Column::SongId)
+            .column_as(Column::Id.count(), "count")
+            .group_by(Column::SongId)
+            .into_model::<SongFavouriteCount>()
+            .all(db)
+            .await?;
+
+        Ok(counts
+            .into_iter()
+            .map(|count| (count.song_id, count.count))
+            .collect())
+    }
+
     pub async fn insert(
         song: &SongModel,
         user: &UserModel,
@@ -0,0 +1,57 @@
+use rand::{distributions::Alphanumeric, Rng};
+use sea_orm::{prelude::*, sea_query::OnConflict, Set};
+
+use crate::entities::feed_tokens::*;
+use crate::prelude::JudeHarleyError;
+
+fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+impl Model {
+    pub async fn get_by_token(
+        token: &str,
+        db: &DatabaseConnection,
+    ) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::Token.eq(token))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_or_create(
+        user_id: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        if let Some(feed_token) = Entity::find_by_id(user_id as i64).one(db).await? {
+            return Ok(feed_token);
+        }
+
+        Self::regenerate(user_id, db).await
+    }
+
+    /// Replaces the user's token, so links shared with the old one stop working.
+    pub async fn regenerate(
+        user_id: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        Entity::insert(ActiveModel {
+            user_id: Set(user_id as i64),
+            token: Set(generate_token()),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+        })
+        .on_conflict(
+            OnConflict::column(Column::UserId)
+                .update_columns([Column::Token, Column::CreatedAt])
+                .to_owned(),
+        )
+        .exec_with_returning(db)
+        .await
+        .map_err(Into::into)
+    }
+}
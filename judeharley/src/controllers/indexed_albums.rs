@@ -0,0 +1,78 @@
+use sea_orm::{
+    prelude::*,
+    sea_query::{extension::postgres::PgExpr, OnConflict},
+    QueryOrder, QuerySelect, Set,
+};
+
+use crate::entities::indexed_albums::*;
+use crate::prelude::JudeHarleyError;
+
+impl Model {
+    /// Remembers when an album was first seen, later calls for the same
+    /// album keep the original timestamp, even across full reindexes.
+    pub async fn record(
+        artist: &str,
+        album: &str,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        if album.is_empty() {
+            return Ok(());
+        }
+
+        let result = Entity::insert(ActiveModel {
+            artist: Set(artist.to_string()),
+            album: Set(album.to_string()),
+            first_indexed_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([Column::Artist, Column::Album])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec(db)
+        .await;
+
+        match result {
+            Ok(_) | Err(DbErr::RecordNotInserted) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The `limit` most recently added albums, newest first.
+    pub async fn get_latest(
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .order_by_desc(Column::FirstIndexedAt)
+            .order_by_desc(Column::Id)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get(id: i32, db: &DatabaseConnection) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find_by_id(id).one(db).await.map_err(Into::into)
+    }
+
+    /// Albums with visible songs whose name contains `query`, ignoring case.
+    pub async fn search(
+        query: &str,
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Expr::col(Column::Album).ilike(format!("%{}%", query.trim())))
+            .filter(Expr::cust(
+                r#"EXISTS (SELECT 1 FROM "songs" WHERE "songs"."album" = "indexed_albums"."album" AND COALESCE("songs"."album_artist", "songs"."artist") = "indexed_albums"."artist" AND NOT "songs"."hidden")"#,
+            ))
+            .order_by_asc(Column::Album)
+            .order_by_asc(Column::Artist)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+}
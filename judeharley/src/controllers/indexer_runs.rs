@@ -0,0 +1,42 @@
+use sea_orm::{prelude::*, IntoActiveModel, QueryOrder, Set};
+
+use crate::entities::indexer_runs::*;
+use crate::prelude::JudeHarleyError;
+
+impl Model {
+    /// Records the start of an indexing run.
+    pub async fn start(db: &DatabaseConnection) -> Result<Self, JudeHarleyError> {
+        ActiveModel {
+            started_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Marks the run as finished, with `error` set if it failed.
+    pub async fn finish(
+        self,
+        indexed_files: usize,
+        failed_files: usize,
+        error: Option<String>,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        let mut active_model = self.into_active_model();
+        active_model.finished_at = Set(Some(chrono::Utc::now().naive_utc()));
+        active_model.indexed_files = Set(Some(indexed_files as i32));
+        active_model.failed_files = Set(Some(failed_files as i32));
+        active_model.error = Set(error);
+
+        active_model.update(db).await.map_err(Into::into)
+    }
+
+    pub async fn get_latest(db: &DatabaseConnection) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find()
+            .order_by_desc(Column::StartedAt)
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+}
@@ -0,0 +1,136 @@
+use chrono::NaiveDateTime;
+use sea_orm::{prelude::*, sea_query::OnConflict, QueryOrder, Set};
+
+use crate::entities::jingles::*;
+use crate::prelude::JudeHarleyError;
+
+pub struct NewJingle {
+    pub file_path: String,
+    pub title: String,
+    pub duration: f64,
+}
+
+impl Model {
+    /// Adds a jingle unless one with the same path exists already.
+    pub async fn insert(jingle: NewJingle, db: &DatabaseConnection) -> Result<(), JudeHarleyError> {
+        let result = Entity::insert(ActiveModel {
+            file_path: Set(jingle.file_path),
+            title: Set(jingle.title),
+            duration: Set(jingle.duration),
+            enabled: Set(true),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        })
+        .on_conflict(OnConflict::column(Column::FilePath).do_nothing().to_owned())
+        .exec(db)
+        .await;
+
+        match result {
+            Ok(_) | Err(DbErr::RecordNotInserted) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn get(id: i32, db: &DatabaseConnection) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find_by_id(id).one(db).await.map_err(Into::into)
+    }
+
+    pub async fn get_by_path(
+        file_path: &str,
+        db: &DatabaseConnection,
+    ) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::FilePath.eq(file_path))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_all(db: &DatabaseConnection) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .order_by_asc(Column::FilePath)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_enabled(db: &DatabaseConnection) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::Enabled.eq(true))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// When any jingle was played last.
+    pub async fn last_played_at(
+        db: &DatabaseConnection,
+    ) -> Result<Option<NaiveDateTime>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::LastPlayedAt.is_not_null())
+            .order_by_desc(Column::LastPlayedAt)
+            .one(db)
+            .await
+            .map_err(Into::into)
+            .map(|jingle| jingle.and_then(|j| j.last_played_at))
+    }
+
+    /// Counts a play of the jingle at `file_path`, returns the jingle or
+    /// `None` if the file isn't a jingle.
+    pub async fn mark_played(
+        file_path: &str,
+        played_at: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<Option<Self>, JudeHarleyError> {
+        let Some(jingle) = Self::get_by_path(file_path, db).await? else {
+            return Ok(None);
+        };
+
+        Entity::update(ActiveModel {
+            id: Set(jingle.id),
+            play_count: Set(jingle.play_count + 1),
+            last_played_at: Set(Some(played_at)),
+            ..Default::default()
+        })
+        .exec(db)
+        .await
+        .map(Some)
+        .map_err(Into::into)
+    }
+
+    /// Returns whether the jingle exists.
+    pub async fn set_enabled(
+        id: i32,
+        enabled: bool,
+        db: &DatabaseConnection,
+    ) -> Result<bool, JudeHarleyError> {
+        let result = Entity::update_many()
+            .col_expr(Column::Enabled, Expr::value(enabled))
+            .filter(Column::Id.eq(id))
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Returns whether the jingle existed.
+    pub async fn delete(id: i32, db: &DatabaseConnection) -> Result<bool, JudeHarleyError> {
+        let result = Entity::delete_by_id(id).exec(db).await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Deletes the jingles whose files aren't in `file_paths` any more,
+    /// returns how many were deleted.
+    pub async fn delete_missing(
+        file_paths: Vec<String>,
+        db: &DatabaseConnection,
+    ) -> Result<u64, JudeHarleyError> {
+        let result = Entity::delete_many()
+            .filter(Column::FilePath.is_not_in(file_paths))
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::NaiveDateTime;
+use sea_orm::{prelude::*, sea_query::OnConflict, Set};
+
+use crate::entities::listenbrainz_accounts::*;
+use crate::entities::users::{Column as UserColumn, Entity as User};
+use crate::prelude::JudeHarleyError;
+
+/// How long after their last message a user still counts as listening, the
+/// same window watched time is counted in.
+const ACTIVITY_WINDOW_MINUTES: i64 = 15;
+
+impl Model {
+    pub async fn get(
+        user_id: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find_by_id(user_id as i64)
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Links a ListenBrainz account to a user, replacing any linked before.
+    pub async fn link(
+        user_id: u64,
+        token: &str,
+        user_name: &str,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        Entity::insert(ActiveModel {
+            user_id: Set(user_id as i64),
+            token: Set(token.to_string()),
+            user_name: Set(user_name.to_string()),
+            linked_at: Set(chrono::Utc::now().naive_utc()),
+        })
+        .on_conflict(
+            OnConflict::column(Column::UserId)
+                .update_columns([Column::Token, Column::UserName, Column::LinkedAt])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Unlinks the user's account, returns whether one was linked.
+    pub async fn unlink(user_id: u64, db: &DatabaseConnection) -> Result<bool, JudeHarleyError> {
+        let result = Entity::delete_by_id(user_id as i64).exec(db).await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Linked accounts of users who sent a message shortly before `at`.
+    pub async fn get_active(
+        at: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        let since = at - chrono::Duration::minutes(ACTIVITY_WINDOW_MINUTES);
+
+        Entity::find()
+            .inner_join(User)
+            .filter(UserColumn::LastMessageSent.gte(since))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_many(
+        user_ids: Vec<i64>,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        if user_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        Entity::find()
+            .filter(Column::UserId.is_in(user_ids))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::NaiveDateTime;
+use sea_orm::{prelude::*, FromQueryResult, QueryOrder, QuerySelect, Set};
+
+use crate::entities::listener_samples::*;
+use crate::prelude::JudeHarleyError;
+
+pub struct NewListenerSample {
+    pub mount: String,
+    pub listeners: i32,
+}
+
+/// The total number of listeners across all mounts at one point in time.
+#[derive(FromQueryResult, Debug, Clone, PartialEq, Eq)]
+pub struct ListenerTotal {
+    pub sampled_at: NaiveDateTime,
+    pub listeners: i64,
+}
+
+impl Model {
+    /// Stores one poll's worth of samples, all sharing the same `sampled_at`.
+    pub async fn insert_many(
+        samples: Vec<NewListenerSample>,
+        sampled_at: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+
+        Entity::insert_many(samples.into_iter().map(|sample| ActiveModel {
+            mount: Set(sample.mount),
+            listeners: Set(sample.listeners),
+            sampled_at: Set(sampled_at),
+            ..Default::default()
+        }))
+        .exec(db)
+        .await?;
+
+        Ok(())
+    }
+
+    fn totals() -> Select<Entity> {
+        Entity::find()
+            .select_only()
+            .column(Column::SampledAt)
+            .column_as(Column::Listeners.sum(), "listeners")
+            .group_by(Column::SampledAt)
+    }
+
+    /// The listener count of the most recent poll.
+    pub async fn current(
+        db: &DatabaseConnection,
+    ) -> Result<Option<ListenerTotal>, JudeHarleyError> {
+        Self::totals()
+            .order_by_desc(Column::SampledAt)
+            .into_model::<ListenerTotal>()
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// The highest listener count recorded since `since`, or ever if `None`.
+    pub async fn peak(
+        since: Option<NaiveDateTime>,
+        db: &DatabaseConnection,
+    ) -> Result<Option<ListenerTotal>, JudeHarleyError> {
+        let mut query = Self::totals();
+        if let Some(since) = since {
+            query = query.filter(Column::SampledAt.gte(since));
+        }
+
+        query
+            .order_by_desc(Expr::cust("listeners"))
+            .order_by_desc(Column::SampledAt)
+            .into_model::<ListenerTotal>()
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+}
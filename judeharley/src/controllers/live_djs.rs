@@ -0,0 +1,73 @@
+use sea_orm::{prelude::*, sea_query::OnConflict, QueryOrder, Set};
+
+use crate::entities::live_djs::*;
+use crate::prelude::JudeHarleyError;
+
+pub struct NewLiveDj {
+    pub user_id: u64,
+    pub name: String,
+    pub username: String,
+    pub password_hash: String,
+}
+
+impl Model {
+    pub async fn get(
+        user_id: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find_by_id(user_id as i64)
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_by_username(
+        username: &str,
+        db: &DatabaseConnection,
+    ) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::Username.eq(username))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_all(db: &DatabaseConnection) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .order_by_asc(Column::Name)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Issues credentials to a DJ, replacing the ones they had before.
+    pub async fn issue(dj: NewLiveDj, db: &DatabaseConnection) -> Result<Self, JudeHarleyError> {
+        Entity::insert(ActiveModel {
+            user_id: Set(dj.user_id as i64),
+            name: Set(dj.name),
+            username: Set(dj.username),
+            password_hash: Set(dj.password_hash),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+        })
+        .on_conflict(
+            OnConflict::column(Column::UserId)
+                .update_columns([
+                    Column::Name,
+                    Column::Username,
+                    Column::PasswordHash,
+                    Column::CreatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec_with_returning(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Returns whether the user had credentials.
+    pub async fn revoke(user_id: u64, db: &DatabaseConnection) -> Result<bool, JudeHarleyError> {
+        let result = Entity::delete_by_id(user_id as i64).exec(db).await?;
+
+        Ok(result.rows_affected > 0)
+    }
+}
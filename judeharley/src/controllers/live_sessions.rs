@@ -0,0 +1,62 @@
+use chrono::NaiveDateTime;
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Set};
+
+use crate::entities::live_sessions::*;
+use crate::prelude::JudeHarleyError;
+
+impl Model {
+    pub async fn start(
+        user_id: u64,
+        dj_name: &str,
+        started_at: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        ActiveModel {
+            user_id: Set(Some(user_id as i64)),
+            dj_name: Set(dj_name.to_string()),
+            started_at: Set(started_at),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// The session that is on air right now, if any.
+    pub async fn current(db: &DatabaseConnection) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::EndedAt.is_null())
+            .order_by_desc(Column::StartedAt)
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn end(
+        &self,
+        ended_at: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        ActiveModel {
+            id: Set(self.id),
+            ended_at: Set(Some(ended_at)),
+            ..Default::default()
+        }
+        .update(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// The latest sessions, newest first.
+    pub async fn get_latest(
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .order_by_desc(Column::StartedAt)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+}
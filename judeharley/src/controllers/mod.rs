@@ -3,16 +3,34 @@ use sea_orm::FromQueryResult;
 pub mod cans;
 pub mod connected_youtube_accounts;
 pub mod favourite_songs;
+pub mod feed_tokens;
+pub mod indexed_albums;
+pub mod indexer_runs;
+pub mod jingles;
+pub mod listenbrainz_accounts;
+pub mod listener_samples;
+pub mod live_djs;
+pub mod live_sessions;
 pub mod played_songs;
+pub mod queued_albums;
+pub mod schedule_blocks;
+pub mod scrobble_queue;
 pub mod server_channel_config;
 pub mod server_config;
 pub mod server_role_config;
 pub mod slcb_currency;
 pub mod slcb_rank;
+pub mod song_lyrics;
+pub mod song_metadata_edits;
+pub mod song_metadata_overrides;
+pub mod song_reports;
 pub mod song_requests;
 pub mod song_tags;
 pub mod songs;
+pub mod unknown_plays;
 pub mod users;
+pub mod webhook_deliveries;
+pub mod webhooks;
 
 #[derive(FromQueryResult)]
 pub struct CountQuery {
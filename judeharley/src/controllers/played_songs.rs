@@ -1,5 +1,9 @@
+use std::collections::HashMap;
+
 use chrono::NaiveDateTime;
-use sea_orm::{prelude::*, Iterable, QueryOrder, QuerySelect, Set};
+use sea_orm::{
+    prelude::*, FromQueryResult, Iterable, PaginatorTrait, QueryOrder, QuerySelect, Set,
+};
 
 use crate::controllers::CountQuery;
 use crate::custom_entities::songs::{
@@ -8,6 +12,49 @@ use crate::custom_entities::songs::{
 use crate::entities::played_songs::*;
 use crate::prelude::JudeHarleyError;
 
+/// How many seconds before its end a song may stop without counting as skipped.
+const SKIP_TOLERANCE: f64 = 5.0;
+
+#[derive(FromQueryResult, Debug, Clone)]
+pub struct SongPlayCount {
+    pub song_id: String,
+    pub count: i64,
+}
+
+#[derive(FromQueryResult, Debug, Clone)]
+pub struct SongSkipCount {
+    pub song_id: String,
+    pub skips: i64,
+    pub plays: i64,
+}
+
+/// How a play ended, given when it started, when the next song started and
+/// how long the song is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayOutcome {
+    pub ended_at: NaiveDateTime,
+    pub played_duration: f64,
+    pub skipped: bool,
+}
+
+impl PlayOutcome {
+    pub fn new(played_at: NaiveDateTime, next_started_at: NaiveDateTime, duration: f64) -> Self {
+        let elapsed = ((next_started_at - played_at).num_milliseconds() as f64 / 1000.0).max(0.0);
+
+        // if more time passed than the song is long, the stream was most likely
+        // idle or down in between, so the song is assumed to have played in full
+        let played_duration = elapsed.min(duration);
+        let ended_at =
+            played_at + chrono::Duration::milliseconds((played_duration * 1000.0) as i64);
+
+        Self {
+            ended_at,
+            played_duration,
+            skipped: elapsed < duration - SKIP_TOLERANCE,
+        }
+    }
+}
+
 impl Model {
     pub async fn insert(song: &SongModel, db: &DatabaseConnection) -> Result<(), JudeHarleyError> {
         ActiveModel {
@@ -20,6 +67,69 @@ impl Model {
         Ok(())
     }
 
+    pub async fn get_latest(db: &DatabaseConnection) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find()
+            .order_by_desc(Column::PlayedAt)
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Records how the latest play ended, called when the next song starts at
+    /// `next_started_at`. Plays that have already ended are left alone.
+    pub async fn finish_latest(
+        next_started_at: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        let Some(latest) = Self::get_latest(db).await? else {
+            return Ok(());
+        };
+        if latest.ended_at.is_some() {
+            return Ok(());
+        }
+
+        let Some(song) = SongModel::get_by_hash(&latest.song_id, db).await? else {
+            return Ok(());
+        };
+
+        let outcome = PlayOutcome::new(latest.played_at, next_started_at, song.duration);
+        ActiveModel {
+            id: Set(latest.id),
+            ended_at: Set(Some(outcome.ended_at)),
+            played_duration: Set(Some(outcome.played_duration)),
+            skipped: Set(Some(outcome.skipped)),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Raises the peak listener count of the song that is currently playing.
+    pub async fn record_listeners(
+        listeners: i32,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        let Some(current) = Self::get_latest(db).await? else {
+            return Ok(());
+        };
+
+        if current.peak_listeners.is_some_and(|peak| peak >= listeners) {
+            return Ok(());
+        }
+
+        ActiveModel {
+            id: Set(current.id),
+            peak_listeners: Set(Some(listeners)),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn get_playing_at(
         timestamp: NaiveDateTime,
         db: &DatabaseConnection,
@@ -97,4 +207,230 @@ impl Model {
             .map_err(Into::into)
             .map(|c| c.map(|c| c.count).unwrap_or(0))
     }
+
+    /// How often each of the songs was played, songs that never played are
+    /// missing.
+    pub async fn count_by_songs(
+        song_ids: Vec<String>,
+        db: &DatabaseConnection,
+    ) -> Result<HashMap<String, i64>, JudeHarleyError> {
+        let counts = Entity::find()
+            .select_only()
+            .column(Column::SongId)
+            .column_as(Column::Id.count(), "count")
+            .filter(Column::SongId.is_in(song_ids))
+            .group_by(Column::SongId)
+            .into_model::<SongPlayCount>()
+            .all(db)
+            .await?;
+
+        Ok(counts.into_iter().map(|c| (c.song_id, c.count)).collect())
+    }
+
+    /// How many songs were played after `since`, or at all if `None`.
+    pub async fn count_since(
+        since: Option<NaiveDateTime>,
+        db: &DatabaseConnection,
+    ) -> Result<u64, JudeHarleyError> {
+        let mut query = Entity::find();
+        if let Some(since) = since {
+            query = query.filter(Column::PlayedAt.gt(since));
+        }
+
+        query.count(db).await.map_err(Into::into)
+    }
+
+    pub async fn get_last_played_at(
+        song: &SongModel,
+        db: &DatabaseConnection,
+    ) -> Result<Option<NaiveDateTime>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::SongId.eq(song.file_hash.clone()))
+            .order_by_desc(Column::PlayedAt)
+            .one(db)
+            .await
+            .map_err(Into::into)
+            .map(|p| p.map(|p| p.played_at))
+    }
+
+    /// The plays since `since`, newest first, but at least the last `at_least`
+    /// plays even if they are older.
+    pub async fn get_recent(
+        since: NaiveDateTime,
+        at_least: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        let plays = Entity::find()
+            .filter(Column::PlayedAt.gte(since))
+            .order_by_desc(Column::PlayedAt)
+            .all(db)
+            .await?;
+        if plays.len() as u64 >= at_least {
+            return Ok(plays);
+        }
+
+        Entity::find()
+            .order_by_desc(Column::PlayedAt)
+            .limit(at_least)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Returns one zero-indexed page of the play history, newest first, with
+    /// the songs that were played. Songs that have since been removed are `None`.
+    pub async fn get_history_page(
+        page: u64,
+        per_page: u64,
+        db: &DatabaseConnection,
+    ) -> Result<(Vec<(Self, Option<SongModel>)>, u64), JudeHarleyError> {
+        let paginator = Entity::find()
+            .order_by_desc(Column::PlayedAt)
+            .paginate(db, per_page);
+        let total = paginator.num_items().await?;
+        let plays = paginator.fetch_page(page).await?;
+
+        let songs =
+            SongModel::get_by_hashes(plays.iter().map(|p| p.song_id.clone()).collect(), db).await?;
+        let plays = plays
+            .into_iter()
+            .map(|play| {
+                let song = songs.iter().find(|s| s.file_hash == play.song_id).cloned();
+                (play, song)
+            })
+            .collect();
+
+        Ok((plays, total))
+    }
+
+    /// The most played songs since `since`, or of all time if `None`.
+    pub async fn get_most_played(
+        since: Option<NaiveDateTime>,
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<(SongModel, i64)>, JudeHarleyError> {
+        let mut query = Entity::find()
+            .select_only()
+            .column(Column::SongId)
+            .column_as(Column::Id.count(), "count")
+            .group_by(Column::SongId);
+        if let Some(since) = since {
+            query = query.filter(Column::PlayedAt.gte(since));
+        }
+
+        let counts = query
+            .order_by_desc(Expr::cust("count"))
+            .order_by_asc(Column::SongId)
+            .limit(limit)
+            .into_model::<SongPlayCount>()
+            .all(db)
+            .await?;
+
+        let songs =
+            SongModel::get_by_hashes(counts.iter().map(|c| c.song_id.clone()).collect(), db)
+                .await?;
+
+        Ok(counts
+            .into_iter()
+            .filter_map(|count| {
+                songs
+                    .iter()
+                    .find(|s| s.file_hash == count.song_id)
+                    .cloned()
+                    .map(|song| (song, count.count))
+            })
+            .collect())
+    }
+
+    /// The songs skipped most often since `since`, or of all time if `None`.
+    pub async fn get_most_skipped(
+        since: Option<NaiveDateTime>,
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<(SongModel, SongSkipCount)>, JudeHarleyError> {
+        let mut query = Entity::find()
+            .select_only()
+            .column(Column::SongId)
+            .column_as(Expr::cust("COUNT(*) FILTER (WHERE skipped)"), "skips")
+            .column_as(Column::Id.count(), "plays")
+            .filter(Column::Skipped.is_not_null())
+            .group_by(Column::SongId)
+            .having(Expr::cust("COUNT(*) FILTER (WHERE skipped) > 0"));
+        if let Some(since) = since {
+            query = query.filter(Column::PlayedAt.gte(since));
+        }
+
+        let counts = query
+            .order_by_desc(Expr::cust("skips"))
+            .order_by_asc(Expr::cust("plays"))
+            .order_by_asc(Column::SongId)
+            .limit(limit)
+            .into_model::<SongSkipCount>()
+            .all(db)
+            .await?;
+
+        let songs =
+            SongModel::get_by_hashes(counts.iter().map(|c| c.song_id.clone()).collect(), db)
+                .await?;
+
+        Ok(counts
+            .into_iter()
+            .filter_map(|count| {
+                songs
+                    .iter()
+                    .find(|s| s.file_hash == count.song_id)
+                    .cloned()
+                    .map(|song| (song, count))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate};
+
+    use super::*;
+
+    fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 18)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_full_play() {
+        let outcome = PlayOutcome::new(start(), start() + Duration::seconds(200), 200.0);
+
+        assert!(!outcome.skipped);
+        assert_eq!(outcome.played_duration, 200.0);
+        assert_eq!(outcome.ended_at, start() + Duration::seconds(200));
+    }
+
+    #[test]
+    fn test_slightly_early_end_is_not_a_skip() {
+        let outcome = PlayOutcome::new(start(), start() + Duration::seconds(197), 200.0);
+
+        assert!(!outcome.skipped);
+        assert_eq!(outcome.played_duration, 197.0);
+    }
+
+    #[test]
+    fn test_skip() {
+        let outcome = PlayOutcome::new(start(), start() + Duration::seconds(42), 200.0);
+
+        assert!(outcome.skipped);
+        assert_eq!(outcome.played_duration, 42.0);
+        assert_eq!(outcome.ended_at, start() + Duration::seconds(42));
+    }
+
+    #[test]
+    fn test_gap_after_song_is_capped() {
+        let outcome = PlayOutcome::new(start(), start() + Duration::hours(3), 200.0);
+
+        assert!(!outcome.skipped);
+        assert_eq!(outcome.played_duration, 200.0);
+        assert_eq!(outcome.ended_at, start() + Duration::seconds(200));
+    }
 }
@@ -0,0 +1,90 @@
+use chrono::NaiveDateTime;
+use sea_orm::{prelude::*, QueryOrder, Set};
+
+use crate::entities::queued_albums::*;
+use crate::prelude::JudeHarleyError;
+
+pub struct NewQueuedAlbum {
+    pub album: String,
+    /// Hashes of the songs, in the order they are queued.
+    pub song_ids: Vec<String>,
+    pub starts_at: NaiveDateTime,
+    pub requested_by: Option<i64>,
+}
+
+impl Model {
+    pub async fn create(
+        queued: NewQueuedAlbum,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        ActiveModel {
+            album: Set(queued.album),
+            song_ids: Set(queued.song_ids.join("\n")),
+            starts_at: Set(queued.starts_at),
+            requested_by: Set(queued.requested_by),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Albums that haven't been queued yet, the next one first.
+    pub async fn get_pending(db: &DatabaseConnection) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::QueuedAt.is_null())
+            .order_by_asc(Column::StartsAt)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Albums that are due to be queued at `now`.
+    pub async fn get_due(
+        now: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::QueuedAt.is_null())
+            .filter(Column::StartsAt.lte(now))
+            .order_by_asc(Column::StartsAt)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn mark_queued(
+        &self,
+        queued_at: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        ActiveModel {
+            id: Set(self.id),
+            queued_at: Set(Some(queued_at)),
+            ..Default::default()
+        }
+        .update(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Returns whether a pending album was cancelled.
+    pub async fn cancel(id: i32, db: &DatabaseConnection) -> Result<bool, JudeHarleyError> {
+        let result = Entity::delete_many()
+            .filter(Column::Id.eq(id))
+            .filter(Column::QueuedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    pub fn song_id_list(&self) -> Vec<&str> {
+        self.song_ids
+            .lines()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .collect()
+    }
+}
@@ -0,0 +1,76 @@
+use chrono::NaiveDateTime;
+use sea_orm::{prelude::*, QueryOrder, Set};
+
+use crate::entities::schedule_blocks::*;
+use crate::prelude::JudeHarleyError;
+
+pub struct NewScheduleBlock {
+    pub name: String,
+    /// Cron-like `minute hour day-of-month month day-of-week`, in UTC.
+    pub recurrence: String,
+    /// Minutes the block lasts once it started.
+    pub duration: i32,
+    pub albums: Vec<String>,
+    pub filter: Option<String>,
+    pub created_by: Option<i64>,
+}
+
+impl Model {
+    pub async fn create(
+        block: NewScheduleBlock,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        ActiveModel {
+            name: Set(block.name),
+            recurrence: Set(block.recurrence),
+            duration: Set(block.duration),
+            albums: Set(block.albums.join("\n")),
+            filter: Set(block.filter),
+            created_by: Set(block.created_by),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_all(db: &DatabaseConnection) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Returns whether the block existed.
+    pub async fn delete(id: i32, db: &DatabaseConnection) -> Result<bool, JudeHarleyError> {
+        let result = Entity::delete_by_id(id).exec(db).await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Remembers that the block's run starting at `started_at` was announced.
+    pub async fn mark_announced(
+        &self,
+        started_at: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        Entity::update_many()
+            .col_expr(Column::LastAnnouncedAt, Expr::value(started_at))
+            .filter(Column::Id.eq(self.id))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The albums the block plays, if it plays a list of albums.
+    pub fn album_list(&self) -> Vec<&str> {
+        self.albums
+            .lines()
+            .map(str::trim)
+            .filter(|album| !album.is_empty())
+            .collect()
+    }
+}
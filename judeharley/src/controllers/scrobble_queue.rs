@@ -0,0 +1,110 @@
+use chrono::NaiveDateTime;
+use sea_orm::{prelude::*, PaginatorTrait, QueryOrder, QuerySelect, Set};
+
+use crate::entities::scrobble_queue::*;
+use crate::prelude::JudeHarleyError;
+
+/// A listen waiting to be submitted, `user_id` is `None` for the station's
+/// own account.
+pub struct NewScrobble {
+    pub user_id: Option<i64>,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration: f64,
+    pub listened_at: NaiveDateTime,
+}
+
+impl Model {
+    pub async fn insert_many(
+        scrobbles: Vec<NewScrobble>,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        if scrobbles.is_empty() {
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        Entity::insert_many(scrobbles.into_iter().map(|scrobble| ActiveModel {
+            user_id: Set(scrobble.user_id),
+            title: Set(scrobble.title),
+            artist: Set(scrobble.artist),
+            album: Set(scrobble.album),
+            duration: Set(scrobble.duration),
+            listened_at: Set(scrobble.listened_at),
+            next_attempt_at: Set(now),
+            ..Default::default()
+        }))
+        .exec(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Up to `limit` scrobbles whose next attempt is due, oldest listens first.
+    pub async fn get_due(
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::NextAttemptAt.lte(chrono::Utc::now().naive_utc()))
+            .order_by_asc(Column::ListenedAt)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn delete_many(
+        ids: Vec<i32>,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        Entity::delete_many()
+            .filter(Column::Id.is_in(ids))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Drops everything still queued for a user, e.g. after they unlinked.
+    pub async fn delete_for_user(
+        user_id: u64,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        Entity::delete_many()
+            .filter(Column::UserId.eq(user_id as i64))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Records a failed attempt and schedules the next one.
+    pub async fn postpone(
+        self,
+        next_attempt_at: NaiveDateTime,
+        error: String,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        ActiveModel {
+            id: Set(self.id),
+            attempts: Set(self.attempts + 1),
+            next_attempt_at: Set(next_attempt_at),
+            last_error: Set(Some(error)),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn count(db: &DatabaseConnection) -> Result<u64, JudeHarleyError> {
+        Entity::find().count(db).await.map_err(Into::into)
+    }
+}
@@ -46,4 +46,34 @@ impl Model {
             .await
             .map_err(Into::into)
     }
+
+    pub async fn get_all_ops_alert_channels(
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::OpsAlerts.eq(true))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_all_announcement_channels(
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::Announcements.eq(true))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_all_song_report_channels(
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::SongReports.eq(true))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
 }
@@ -0,0 +1,80 @@
+use sea_orm::{prelude::*, Set};
+
+use crate::custom_entities::songs::Model as SongModel;
+use crate::entities::song_lyrics::*;
+use crate::maintenance::lyrics::{self, LyricLine};
+use crate::prelude::JudeHarleyError;
+
+pub const SOURCE_TAG: &str = "tag";
+pub const SOURCE_LRC: &str = "lrc";
+pub const SOURCE_TXT: &str = "txt";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewLyrics {
+    pub source: String,
+    pub synced: bool,
+    pub lyrics: String,
+}
+
+impl Model {
+    pub async fn insert(
+        song: &SongModel,
+        lyrics: NewLyrics,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        Self::delete_by_song(song, db).await?;
+
+        ActiveModel {
+            song_id: Set(song.file_hash.clone()),
+            source: Set(lyrics.source),
+            synced: Set(lyrics.synced),
+            lyrics: Set(lyrics.lyrics),
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_by_song(
+        song: &SongModel,
+        db: &DatabaseConnection,
+    ) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find_by_id(song.file_hash.clone())
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn delete_by_song(
+        song: &SongModel,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        Entity::delete_by_id(song.file_hash.clone())
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_many(
+        songs: &[SongModel],
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        Entity::delete_many()
+            .filter(Column::SongId.is_in(songs.iter().map(|s| s.file_hash.clone())))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn prune(db: &DatabaseConnection) -> Result<(), JudeHarleyError> {
+        Entity::delete_many().exec(db).await?;
+
+        Ok(())
+    }
+
+    pub fn lines(&self) -> Vec<LyricLine> {
+        lyrics::lines(&self.lyrics, self.synced)
+    }
+}
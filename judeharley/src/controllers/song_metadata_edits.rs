@@ -0,0 +1,44 @@
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Set};
+
+use crate::entities::song_metadata_edits::*;
+use crate::prelude::JudeHarleyError;
+
+impl Model {
+    pub async fn record(
+        song_id: &str,
+        user_id: Option<i64>,
+        field: &str,
+        old_value: Option<String>,
+        new_value: Option<String>,
+        db: &impl ConnectionTrait,
+    ) -> Result<Self, JudeHarleyError> {
+        ActiveModel {
+            song_id: Set(song_id.to_string()),
+            user_id: Set(user_id),
+            field: Set(field.to_string()),
+            old_value: Set(old_value),
+            new_value: Set(new_value),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// The latest edits of a song's metadata, newest first.
+    pub async fn for_song(
+        song_id: &str,
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::SongId.eq(song_id))
+            .order_by_desc(Column::CreatedAt)
+            .order_by_desc(Column::Id)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+}
@@ -0,0 +1,324 @@
+use sea_orm::{
+    prelude::*, sea_query::extension::postgres::PgExpr, QueryOrder, QuerySelect, Set,
+    TransactionTrait,
+};
+
+use crate::controllers::songs::NewSong;
+use crate::entities::song_metadata_overrides::*;
+use crate::prelude::{JudeHarleyError, SongMetadataEdits};
+
+/// Changes to a song's overrides. Fields that are `None` are left as they
+/// are, empty ones go back to what the file's tags say.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataEdit {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub hidden: Option<bool>,
+}
+
+/// One field an edit changed, as recorded in the audit trail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+fn text_change(
+    field: &'static str,
+    current: Option<&String>,
+    edit: Option<&String>,
+) -> Option<FieldChange> {
+    let new_value = edit?.trim();
+    let new_value = (!new_value.is_empty()).then(|| new_value.to_string());
+    if current == new_value.as_ref() {
+        return None;
+    }
+
+    Some(FieldChange {
+        field,
+        old_value: current.cloned(),
+        new_value,
+    })
+}
+
+/// What `edit` changes about the `current` overrides.
+pub fn changes(current: Option<&Model>, edit: &MetadataEdit) -> Vec<FieldChange> {
+    let hidden = current.is_some_and(|current| current.hidden);
+    [
+        text_change(
+            "title",
+            current.and_then(|c| c.title.as_ref()),
+            edit.title.as_ref(),
+        ),
+        text_change(
+            "artist",
+            current.and_then(|c| c.artist.as_ref()),
+            edit.artist.as_ref(),
+        ),
+        text_change(
+            "album",
+            current.and_then(|c| c.album.as_ref()),
+            edit.album.as_ref(),
+        ),
+        edit.hidden
+            .filter(|new| *new != hidden)
+            .map(|new| FieldChange {
+                field: "hidden",
+                old_value: Some(hidden.to_string()),
+                new_value: Some(new.to_string()),
+            }),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+impl Model {
+    pub async fn get(
+        song_id: &str,
+        db: &DatabaseConnection,
+    ) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find_by_id(song_id)
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_by_path(
+        file_path: &str,
+        db: &DatabaseConnection,
+    ) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::FilePath.eq(file_path))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Hidden songs whose path contains `query`, ignoring case.
+    pub async fn search_hidden(
+        query: &str,
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::Hidden.eq(true))
+            .filter(Expr::col(Column::FilePath).ilike(format!("%{}%", query)))
+            .order_by_asc(Column::FilePath)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Applies `edit` to a song's overrides and records what changed in the
+    /// audit trail. Returns the changes, which are empty if there were none.
+    pub async fn edit(
+        song_id: &str,
+        file_path: &str,
+        edit: &MetadataEdit,
+        user_id: Option<i64>,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<FieldChange>, JudeHarleyError> {
+        let current = Self::get(song_id, db).await?;
+        let changes = changes(current.as_ref(), edit);
+        if changes.is_empty() {
+            return Ok(changes);
+        }
+
+        let mut model = current.clone().unwrap_or_else(|| Model {
+            song_id: song_id.to_string(),
+            file_path: file_path.to_string(),
+            title: None,
+            artist: None,
+            album: None,
+            hidden: false,
+            updated_at: chrono::Utc::now().naive_utc(),
+        });
+        for change in &changes {
+            match change.field {
+                "title" => model.title = change.new_value.clone(),
+                "artist" => model.artist = change.new_value.clone(),
+                "album" => model.album = change.new_value.clone(),
+                "hidden" => model.hidden = change.new_value.as_deref() == Some("true"),
+                _ => {}
+            }
+        }
+
+        let txn = db.begin().await?;
+        let active = ActiveModel {
+            song_id: Set(model.song_id),
+            file_path: Set(file_path.to_string()),
+            title: Set(model.title),
+            artist: Set(model.artist),
+            album: Set(model.album),
+            hidden: Set(model.hidden),
+            updated_at: Set(chrono::Utc::now().naive_utc()),
+        };
+        if current.is_some() {
+            active.update(&txn).await?;
+        } else {
+            active.insert(&txn).await?;
+        }
+        for change in &changes {
+            SongMetadataEdits::record(
+                song_id,
+                user_id,
+                change.field,
+                change.old_value.clone(),
+                change.new_value.clone(),
+                &txn,
+            )
+            .await?;
+        }
+        txn.commit().await?;
+
+        Ok(changes)
+    }
+
+    /// Removes all of a song's overrides, recording each one in the audit
+    /// trail. Returns what changed, which is empty if there were none.
+    pub async fn reset(
+        song_id: &str,
+        user_id: Option<i64>,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<FieldChange>, JudeHarleyError> {
+        let Some(current) = Self::get(song_id, db).await? else {
+            return Ok(vec![]);
+        };
+        let changes = changes(
+            Some(&current),
+            &MetadataEdit {
+                title: Some(String::new()),
+                artist: Some(String::new()),
+                album: Some(String::new()),
+                hidden: Some(false),
+            },
+        );
+
+        let txn = db.begin().await?;
+        Entity::delete_by_id(song_id).exec(&txn).await?;
+        for change in &changes {
+            SongMetadataEdits::record(
+                song_id,
+                user_id,
+                change.field,
+                change.old_value.clone(),
+                change.new_value.clone(),
+                &txn,
+            )
+            .await?;
+        }
+        txn.commit().await?;
+
+        Ok(changes)
+    }
+
+    /// Puts the overridden fields over what the file's tags say.
+    pub fn apply(&self, song: &mut NewSong) {
+        song.hidden = self.hidden;
+        if let Some(title) = &self.title {
+            song.title.clone_from(title);
+        }
+        if let Some(artist) = &self.artist {
+            song.artist.clone_from(artist);
+        }
+        if let Some(album) = &self.album {
+            song.album.clone_from(album);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn current() -> Model {
+        Model {
+            song_id: "abc".to_string(),
+            file_path: "/music/Homestuck/Showtime.mp3".to_string(),
+            title: Some("Showtime (Original Mix)".to_string()),
+            artist: None,
+            album: None,
+            hidden: false,
+            updated_at: chrono::NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_changes() {
+        let edit = MetadataEdit {
+            title: Some("Showtime (Original Mix)".to_string()),
+            artist: Some(" Homestuck ".to_string()),
+            hidden: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            changes(Some(&current()), &edit),
+            vec![
+                FieldChange {
+                    field: "artist",
+                    old_value: None,
+                    new_value: Some("Homestuck".to_string()),
+                },
+                FieldChange {
+                    field: "hidden",
+                    old_value: Some("false".to_string()),
+                    new_value: Some("true".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changes_resets_empty_fields() {
+        let edit = MetadataEdit {
+            title: Some(String::new()),
+            album: Some(String::new()),
+            hidden: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(
+            changes(Some(&current()), &edit),
+            vec![FieldChange {
+                field: "title",
+                old_value: Some("Showtime (Original Mix)".to_string()),
+                new_value: None,
+            }]
+        );
+        assert!(changes(None, &edit).is_empty());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut song = NewSong {
+            title: "Showtime".to_string(),
+            artist: "Toby Fox".to_string(),
+            album: "Homestuck Vol. 1".to_string(),
+            file_path: "/music/Homestuck/Showtime.mp3".to_string(),
+            file_hash: "abc".to_string(),
+            duration: 87.0,
+            bitrate: 320,
+            track_number: Some(2),
+            disc_number: None,
+            year: Some(2009),
+            genre: None,
+            album_artist: None,
+            hidden: false,
+        };
+        current().apply(&mut song);
+        assert_eq!(song.title, "Showtime (Original Mix)");
+        assert_eq!(song.artist, "Toby Fox");
+        assert_eq!(song.album, "Homestuck Vol. 1");
+        assert!(!song.hidden);
+
+        let hidden = Model {
+            hidden: true,
+            ..current()
+        };
+        hidden.apply(&mut song);
+        assert!(song.hidden);
+    }
+}
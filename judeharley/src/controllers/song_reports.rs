@@ -0,0 +1,113 @@
+use sea_orm::{prelude::*, Set};
+
+use crate::controllers::song_metadata_overrides::MetadataEdit;
+use crate::entities::song_reports::*;
+use crate::prelude::JudeHarleyError;
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_APPROVED: &str = "approved";
+pub const STATUS_REJECTED: &str = "rejected";
+
+pub struct NewSongReport {
+    pub song_id: String,
+    pub user_id: i64,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub broken: bool,
+    pub note: Option<String>,
+}
+
+impl Model {
+    pub async fn create(
+        report: NewSongReport,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        ActiveModel {
+            song_id: Set(report.song_id),
+            user_id: Set(report.user_id),
+            title: Set(report.title),
+            artist: Set(report.artist),
+            album: Set(report.album),
+            broken: Set(report.broken),
+            note: Set(report.note),
+            status: Set(STATUS_PENDING.to_string()),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get(id: i32, db: &DatabaseConnection) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find_by_id(id).one(db).await.map_err(Into::into)
+    }
+
+    pub async fn count_pending_by_user(
+        user_id: i64,
+        db: &DatabaseConnection,
+    ) -> Result<u64, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::Status.eq(STATUS_PENDING))
+            .count(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Marks a pending report as approved or rejected. Returns the reviewed
+    /// report, or `None` if it doesn't exist or was already reviewed, so two
+    /// reviewers can't both act on it.
+    pub async fn review(
+        id: i32,
+        status: &str,
+        reviewed_by: i64,
+        reward: i32,
+        db: &DatabaseConnection,
+    ) -> Result<Option<Self>, JudeHarleyError> {
+        let result = Entity::update_many()
+            .col_expr(Column::Status, Expr::value(status))
+            .col_expr(Column::ReviewedBy, Expr::value(reviewed_by))
+            .col_expr(
+                Column::ReviewedAt,
+                Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .col_expr(Column::Reward, Expr::value(reward))
+            .filter(Column::Id.eq(id))
+            .filter(Column::Status.eq(STATUS_PENDING))
+            .exec(db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        Self::get(id, db).await
+    }
+
+    /// Puts a reviewed report back up for review, e.g. when approving it
+    /// failed half way.
+    pub async fn reopen(id: i32, db: &DatabaseConnection) -> Result<(), JudeHarleyError> {
+        Entity::update_many()
+            .col_expr(Column::Status, Expr::value(STATUS_PENDING))
+            .col_expr(Column::ReviewedBy, Expr::value(Option::<i64>::None))
+            .col_expr(Column::ReviewedAt, Expr::value(Option::<DateTime>::None))
+            .col_expr(Column::Reward, Expr::value(0))
+            .filter(Column::Id.eq(id))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The overrides approving the report results in, a broken file is
+    /// hidden until it's replaced.
+    pub fn edit(&self) -> MetadataEdit {
+        MetadataEdit {
+            title: self.title.clone(),
+            artist: self.artist.clone(),
+            album: self.album.clone(),
+            hidden: self.broken.then_some(true),
+        }
+    }
+}
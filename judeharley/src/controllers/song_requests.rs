@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use chrono::NaiveDateTime;
 use sea_orm::{prelude::*, FromQueryResult, QueryOrder, QuerySelect, Set};
 
@@ -11,6 +13,12 @@ struct CreatedAtQuery {
     created_at: NaiveDateTime,
 }
 
+#[derive(FromQueryResult)]
+struct SongRequestCount {
+    song_id: String,
+    count: i64,
+}
+
 impl Model {
     pub async fn insert(
         song: &SongModel,
@@ -56,4 +64,23 @@ impl Model {
             .map_err(Into::into)
             .map(|c| c.map(|c| c.count).unwrap_or(0))
     }
+
+    /// How often each song was requested, songs nobody requested are left out.
+    pub async fn count_by_song(
+        db: &DatabaseConnection,
+    ) -> Result<HashMap<String, i64>, JudeHarleyError> {
+        let counts = Entity::find()
+            .select_only()
+            .column(Column::SongId)
+            .column_as(Column::Id.count(), "count")
+            .group_by(Column::SongId)
+            .into_model::<SongRequestCount>()
+            .all(db)
+            .await?;
+
+        Ok(counts
+            .into_iter()
+            .map(|count| (count.song_id, count.count))
+            .collect())
+    }
 }
@@ -1,11 +1,22 @@
 use std::path::Path;
 
-use sea_orm::{prelude::*, FromQueryResult, QuerySelect, Set, Statement};
+use sea_orm::{
+    prelude::*,
+    sea_query::{OnConflict, Query},
+    Condition, DatabaseTransaction, FromQueryResult, Order, PaginatorTrait, QueryOrder,
+    QuerySelect, Select, Set, TransactionTrait,
+};
 
 use crate::entities::{
-    favourite_songs::Model as FavouriteSongModel, played_songs::Model as PlayedModel,
-    song_requests::Model as RequestModel, song_tags::Model as TagsModel, users::Model as UserModel,
+    favourite_songs::{self, Model as FavouriteSongModel},
+    played_songs::Model as PlayedModel,
+    song_lyrics::Model as LyricsModel,
+    song_requests::Model as RequestModel,
+    song_tags::Model as TagsModel,
+    users::Model as UserModel,
 };
+use crate::search::SearchQuery;
+use crate::webhooks::{self, RequestQueued, WebhookEvent};
 use crate::{custom_entities::songs::*, JudeHarleyError};
 
 pub struct NewSong {
@@ -16,6 +27,12 @@ pub struct NewSong {
     pub file_hash: String,
     pub duration: f64,
     pub bitrate: i32,
+    pub track_number: Option<i32>,
+    pub disc_number: Option<i32>,
+    pub year: Option<i32>,
+    pub genre: Option<String>,
+    pub album_artist: Option<String>,
+    pub hidden: bool,
 }
 
 #[derive(FromQueryResult)]
@@ -23,11 +40,129 @@ struct PathQuery {
     file_path: String,
 }
 
+/// How similar a song has to be to a query that matched nothing else, typos
+/// like "shotime" score about 0.5, unrelated songs below 0.2. `<%` compares
+/// against `pg_trgm.word_similarity_threshold`, see [`begin_search`].
+const FUZZY_THRESHOLD: f64 = 0.4;
+
+/// The words of `$1` as a prefix query, accents removed.
+const TS_QUERY: &str = "(SELECT to_tsquery('simple', string_agg(lexeme || ':*', ' & ' ORDER BY positions)) FROM unnest(to_tsvector('simple', immutable_unaccent($1))))";
+
+const SIMILARITY: &str = r#"word_similarity(lower(immutable_unaccent($1)), song_search_text("songs"."title", "songs"."artist", "songs"."album"))"#;
+
+/// Whether [`SIMILARITY`] reaches the threshold, using the trigram index.
+const SIMILAR: &str = r#"lower(immutable_unaccent($1)) <% song_search_text("songs"."title", "songs"."artist", "songs"."album")"#;
+
+/// Weighs a song's relevance by how often it was played and requested.
+const POPULARITY: &str = r#"(1 + ln(
+    1
+    + (SELECT COUNT(*) FROM played_songs WHERE played_songs.song_id = "songs"."file_hash")
+    + (SELECT COUNT(*) FROM song_requests WHERE song_requests.song_id = "songs"."file_hash")
+))"#;
+
+/// Songs that aren't hidden by their metadata overrides. Hidden songs keep
+/// their row so favourites, history and reports still find them.
+fn visible() -> Select<Entity> {
+    Entity::find().filter(Column::Hidden.eq(false))
+}
+
+/// Starts the transaction a search runs in, so the [`FUZZY_THRESHOLD`] is
+/// only set for it.
+async fn begin_search(db: &DatabaseConnection) -> Result<DatabaseTransaction, JudeHarleyError> {
+    let txn = db.begin().await?;
+    txn.execute_unprepared(&format!(
+        "SET LOCAL pg_trgm.word_similarity_threshold = {FUZZY_THRESHOLD}"
+    ))
+    .await?;
+
+    Ok(txn)
+}
+
+/// Finds the songs in `scope` matching `query`. Its words are matched by
+/// prefix and ranked by `ts_rank`, if nothing matches that way, songs
+/// similar to them by trigrams are found instead, so typos still find
+/// something. Accents are ignored either way. Without any words, songs are
+/// ordered by artist, album and title.
+async fn search_select(
+    query: &SearchQuery,
+    scope: Condition,
+    db: &DatabaseTransaction,
+) -> Result<Select<Entity>, JudeHarleyError> {
+    let select = visible().filter(scope).filter(query.condition());
+    let Some(text) = query.text() else {
+        return Ok(select
+            .order_by_asc(Column::Artist)
+            .order_by_asc(Column::Album)
+            .order_by_asc(Column::Title));
+    };
+
+    let matches = select.clone().filter(Expr::cust_with_values(
+        format!(r#""songs"."tsvector" @@ {TS_QUERY}"#),
+        [text.clone()],
+    ));
+    let (select, relevance) = if matches.clone().count(db).await? > 0 {
+        (
+            matches,
+            format!(r#"ts_rank("songs"."tsvector", {TS_QUERY})"#),
+        )
+    } else {
+        (
+            select.filter(Expr::cust_with_values(SIMILAR, [text.clone()])),
+            SIMILARITY.to_string(),
+        )
+    };
+
+    Ok(select
+        .order_by(
+            Expr::cust_with_values(format!("{relevance} * {POPULARITY}"), [text]),
+            Order::Desc,
+        )
+        .order_by_asc(Column::Artist)
+        .order_by_asc(Column::Album)
+        .order_by_asc(Column::Title))
+}
+
 impl Model {
-    pub async fn insert(
-        params: NewSong,
-        db: &DatabaseConnection,
-    ) -> Result<Self, JudeHarleyError> {
+    /// The artist the song's album is filed under.
+    pub fn album_artist(&self) -> &str {
+        self.album_artist.as_deref().unwrap_or(&self.artist)
+    }
+
+    pub async fn insert(params: NewSong, db: &DatabaseConnection) -> Result<Self, JudeHarleyError> {
+        Self::active_model(params)
+            .insert(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Inserts the song, or updates it in place if its path is indexed
+    /// already, so its plays, favourites and requests stay with it.
+    pub async fn upsert(params: NewSong, db: &DatabaseConnection) -> Result<Self, JudeHarleyError> {
+        Entity::insert(Self::active_model(params))
+            .on_conflict(
+                OnConflict::column(Column::FilePath)
+                    .update_columns([
+                        Column::FileHash,
+                        Column::Title,
+                        Column::Artist,
+                        Column::Album,
+                        Column::Duration,
+                        Column::Bitrate,
+                        Column::TrackNumber,
+                        Column::DiscNumber,
+                        Column::Year,
+                        Column::Genre,
+                        Column::AlbumArtist,
+                        Column::Hidden,
+                    ])
+                    .to_owned(),
+            )
+            .exec_with_returning(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    fn active_model(params: NewSong) -> ActiveModel {
         ActiveModel {
             file_path: Set(params.file_path),
             file_hash: Set(params.file_hash),
@@ -36,15 +171,19 @@ impl Model {
             album: Set(params.album),
             duration: Set(params.duration),
             bitrate: Set(params.bitrate),
+            track_number: Set(params.track_number),
+            disc_number: Set(params.disc_number),
+            year: Set(params.year),
+            genre: Set(params.genre),
+            album_artist: Set(params.album_artist),
+            hidden: Set(params.hidden),
             ..Default::default()
         }
-        .insert(db)
-        .await
-        .map_err(Into::into)
     }
 
     pub async fn delete(&self, db: &DatabaseConnection) -> Result<(), JudeHarleyError> {
         TagsModel::delete_by_song(self, db).await?;
+        LyricsModel::delete_by_song(self, db).await?;
         Entity::delete_by_id(&self.file_path).exec(db).await?;
 
         Ok(())
@@ -55,6 +194,7 @@ impl Model {
         db: &DatabaseConnection,
     ) -> Result<(), JudeHarleyError> {
         TagsModel::delete_many(songs, db).await?;
+        LyricsModel::delete_many(songs, db).await?;
 
         Entity::delete_many()
             .filter(Column::FileHash.is_in(songs.iter().map(|s| s.file_hash.clone())))
@@ -82,12 +222,29 @@ impl Model {
 
     pub async fn prune(db: &DatabaseConnection) -> Result<(), JudeHarleyError> {
         TagsModel::prune(db).await?;
+        LyricsModel::prune(db).await?;
         Entity::delete_many().exec(db).await?;
         Ok(())
     }
 
+    /// Every song that isn't hidden.
     pub async fn get_all(db: &DatabaseConnection) -> Result<Vec<Self>, JudeHarleyError> {
-        Entity::find().all(db).await.map_err(Into::into)
+        visible().all(db).await.map_err(Into::into)
+    }
+
+    /// The visible songs the rotation may pick from: all but the `played` ones
+    /// and, if `max_duration` is given, none longer than that many seconds.
+    pub async fn get_rotation_candidates(
+        played: Vec<String>,
+        max_duration: Option<f64>,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        let mut query = visible().filter(Column::FileHash.is_not_in(played));
+        if let Some(max_duration) = max_duration {
+            query = query.filter(Column::Duration.lte(max_duration));
+        }
+
+        query.all(db).await.map_err(Into::into)
     }
 
     pub async fn get(
@@ -111,6 +268,74 @@ impl Model {
             .map_err(Into::into)
     }
 
+    pub async fn get_by_hashes(
+        file_hashes: Vec<String>,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::FileHash.is_in(file_hashes))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// The songs of an artist's album in track order, songs without numbers
+    /// last in file order. Songs tagged with an album artist are matched on
+    /// that, so compilations stay together.
+    pub async fn get_by_album(
+        artist: &str,
+        album: &str,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        visible()
+            .filter(
+                Condition::any().add(Column::AlbumArtist.eq(artist)).add(
+                    Condition::all()
+                        .add(Column::AlbumArtist.is_null())
+                        .add(Column::Artist.eq(artist)),
+                ),
+            )
+            .filter(Column::Album.eq(album))
+            .order_by_asc(Column::DiscNumber)
+            .order_by_asc(Column::TrackNumber)
+            .order_by_asc(Column::FilePath)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Returns one zero-indexed page of songs together with the total number
+    /// of songs, optionally filtered by a search query.
+    pub async fn get_page(
+        query: Option<&str>,
+        page: u64,
+        per_page: u64,
+        db: &DatabaseConnection,
+    ) -> Result<(Vec<Self>, u64), JudeHarleyError> {
+        let Some(query) = query.filter(|q| !q.trim().is_empty()) else {
+            let paginator = visible()
+                .order_by_asc(Column::Artist)
+                .order_by_asc(Column::Album)
+                .order_by_asc(Column::Title)
+                .paginate(db, per_page);
+            let total = paginator.num_items().await?;
+            let songs = paginator.fetch_page(page).await?;
+
+            return Ok((songs, total));
+        };
+
+        let query = SearchQuery::parse(query)?;
+        let txn = begin_search(db).await?;
+        let paginator = search_select(&query, Condition::all(), &txn)
+            .await?
+            .paginate(&txn, per_page);
+        let total = paginator.num_items().await?;
+        let songs = paginator.fetch_page(page).await?;
+        txn.commit().await?;
+
+        Ok((songs, total))
+    }
+
     pub async fn get_by_directory(
         directory: &Path,
         db: &DatabaseConnection,
@@ -127,8 +352,9 @@ impl Model {
             .map_err(Into::into)
     }
 
+    /// The paths of every song that isn't hidden.
     pub async fn get_all_paths(db: &DatabaseConnection) -> Result<Vec<String>, JudeHarleyError> {
-        Entity::find()
+        visible()
             .select_only()
             .column(Column::FilePath)
             .into_model::<PathQuery>()
@@ -138,56 +364,73 @@ impl Model {
             .map(|p| p.into_iter().map(|q| q.file_path).collect())
     }
 
+    /// Up to `limit` songs matching `query`, the best matches first. See
+    /// [`crate::search`] for what queries may contain.
     pub async fn search(
         query: &str,
+        limit: u64,
         db: &DatabaseConnection,
     ) -> Result<Vec<Self>, JudeHarleyError> {
-        Model::find_by_statement(Statement::from_sql_and_values(
-            sea_orm::DatabaseBackend::Postgres,
-            r#"
-            WITH search AS (
-                SELECT to_tsquery(string_agg(lexeme || ':*', ' & ' ORDER BY positions)) AS query
-                FROM unnest(to_tsvector($1))
-            )
-            SELECT songs.*
-            FROM songs, search
-            WHERE tsvector @@ query
-            "#,
-            [query.into()],
-        ))
-        .all(db)
-        .await
-        .map_err(Into::into)
+        let query = SearchQuery::parse(query)?;
+        if query.terms.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let txn = begin_search(db).await?;
+        let songs = search_select(&query, Condition::all(), &txn)
+            .await?
+            .limit(limit)
+            .all(&txn)
+            .await?;
+        txn.commit().await?;
+
+        Ok(songs)
     }
 
+    /// Up to `limit` of the user's favourite songs matching `query`, the best
+    /// matches first.
     pub async fn search_favourited_songs(
         query: &str,
         user: &UserModel,
+        limit: u64,
         db: &DatabaseConnection,
     ) -> Result<Vec<Self>, JudeHarleyError> {
-        Model::find_by_statement(Statement::from_sql_and_values(
-            sea_orm::DatabaseBackend::Postgres,
-            r#"
-            WITH search AS (
-                SELECT to_tsquery(string_agg(lexeme || ':*', ' & ' ORDER BY positions)) AS query
-                FROM unnest(to_tsvector($1))
-            )
-            SELECT songs.*
-            FROM search, favourite_songs
-            JOIN songs ON favourite_songs.song_id = songs.file_hash
-            WHERE favourite_songs.user_id = $2 AND tsvector @@ query
-            "#,
-            [query.into(), user.id.into()],
-        ))
-        .all(db)
-        .await
-        .map_err(Into::into)
+        let query = SearchQuery::parse(query)?;
+        if query.terms.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let favourites = Query::select()
+            .column(favourite_songs::Column::SongId)
+            .from(favourite_songs::Entity)
+            .and_where(favourite_songs::Column::UserId.eq(user.id))
+            .to_owned();
+        let txn = begin_search(db).await?;
+        let songs = search_select(
+            &query,
+            Condition::all().add(Column::FileHash.in_subquery(favourites)),
+            &txn,
+        )
+        .await?
+        .limit(limit)
+        .all(&txn)
+        .await?;
+        txn.commit().await?;
+
+        Ok(songs)
     }
 
     pub async fn tags(&self, db: &DatabaseConnection) -> Result<Vec<TagsModel>, JudeHarleyError> {
         TagsModel::get_by_song(self, db).await
     }
 
+    pub async fn lyrics(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<Option<LyricsModel>, JudeHarleyError> {
+        LyricsModel::get_by_song(self, db).await
+    }
+
     pub async fn tag(
         &self,
         tag: &str,
@@ -211,13 +454,32 @@ impl Model {
         user: &UserModel,
         db: &DatabaseConnection,
     ) -> Result<(), JudeHarleyError> {
-        RequestModel::insert(self, user, db).await
+        RequestModel::insert(self, user, db).await?;
+
+        webhooks::trigger(
+            WebhookEvent::RequestQueued,
+            &RequestQueued {
+                song: self.clone().into(),
+                requested_by: user.id.to_string(),
+            },
+            db,
+        )
+        .await;
+
+        Ok(())
     }
 
     pub async fn last_10_songs(db: &DatabaseConnection) -> Result<[Self; 10], JudeHarleyError> {
         PlayedModel::get_last_10_played(db).await
     }
 
+    pub async fn last_played_at(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<Option<chrono::NaiveDateTime>, JudeHarleyError> {
+        PlayedModel::get_last_played_at(self, db).await
+    }
+
     pub async fn last_played(db: &DatabaseConnection) -> Result<Option<Self>, JudeHarleyError> {
         PlayedModel::get_last_played(db).await
     }
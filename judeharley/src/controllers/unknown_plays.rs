@@ -0,0 +1,43 @@
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Set};
+
+use crate::entities::unknown_plays::*;
+use crate::prelude::JudeHarleyError;
+
+pub struct NewUnknownPlay {
+    pub filename: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub reason: String,
+}
+
+impl Model {
+    pub async fn insert(
+        params: NewUnknownPlay,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        ActiveModel {
+            filename: Set(params.filename),
+            title: Set(params.title),
+            artist: Set(params.artist),
+            album: Set(params.album),
+            reason: Set(params.reason),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_latest(
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .order_by_desc(Column::PlayedAt)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+}
@@ -88,9 +88,7 @@ impl Model {
     }
 
     pub async fn place_can(&self, db: &DatabaseConnection) -> Result<(), JudeHarleyError> {
-        crate::entities::cans::Model::insert(self, true, db)
-            .await
-            .map_err(Into::into)
+        crate::entities::cans::Model::insert(self, true, db).await
     }
 
     pub async fn insert_channels(
@@ -0,0 +1,145 @@
+use chrono::NaiveDateTime;
+use sea_orm::{prelude::*, sea_query::Query, QueryOrder, QuerySelect, Set};
+
+use crate::entities::{webhook_deliveries::*, webhooks};
+use crate::prelude::JudeHarleyError;
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_DELIVERED: &str = "delivered";
+pub const STATUS_FAILED: &str = "failed";
+
+impl Model {
+    /// Queues `payload` for delivery to every webhook in `webhook_ids`.
+    pub async fn insert_many(
+        webhook_ids: Vec<i32>,
+        event: &str,
+        payload: &str,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        if webhook_ids.is_empty() {
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        Entity::insert_many(webhook_ids.into_iter().map(|webhook_id| ActiveModel {
+            webhook_id: Set(webhook_id),
+            event: Set(event.to_string()),
+            payload: Set(payload.to_string()),
+            status: Set(STATUS_PENDING.to_string()),
+            next_attempt_at: Set(Some(now)),
+            created_at: Set(now),
+            ..Default::default()
+        }))
+        .exec(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Up to `limit` pending deliveries to enabled webhooks that are due,
+    /// oldest first.
+    pub async fn get_due(
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::Status.eq(STATUS_PENDING))
+            .filter(
+                Column::WebhookId.in_subquery(
+                    Query::select()
+                        .column(webhooks::Column::Id)
+                        .from(webhooks::Entity)
+                        .and_where(webhooks::Column::Enabled.eq(true))
+                        .to_owned(),
+                ),
+            )
+            .filter(Column::NextAttemptAt.lte(chrono::Utc::now().naive_utc()))
+            .order_by_asc(Column::Id)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// The `limit` most recent deliveries to a webhook, newest first.
+    pub async fn get_latest_for_webhook(
+        webhook_id: i32,
+        limit: u64,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::WebhookId.eq(webhook_id))
+            .order_by_desc(Column::CreatedAt)
+            .order_by_desc(Column::Id)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn mark_delivered(
+        self,
+        response_status: u16,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        let now = chrono::Utc::now().naive_utc();
+        ActiveModel {
+            id: Set(self.id),
+            status: Set(STATUS_DELIVERED.to_string()),
+            attempts: Set(self.attempts + 1),
+            next_attempt_at: Set(None),
+            response_status: Set(Some(response_status.into())),
+            last_error: Set(None),
+            delivered_at: Set(Some(now)),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records a failed attempt, the delivery is retried at `next_attempt_at`
+    /// or given up on if that is `None`.
+    pub async fn record_failure(
+        self,
+        response_status: Option<u16>,
+        error: String,
+        next_attempt_at: Option<NaiveDateTime>,
+        db: &DatabaseConnection,
+    ) -> Result<(), JudeHarleyError> {
+        let status = if next_attempt_at.is_some() {
+            STATUS_PENDING
+        } else {
+            STATUS_FAILED
+        };
+
+        ActiveModel {
+            id: Set(self.id),
+            status: Set(status.to_string()),
+            attempts: Set(self.attempts + 1),
+            next_attempt_at: Set(next_attempt_at),
+            response_status: Set(response_status.map(Into::into)),
+            last_error: Set(Some(error)),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deletes finished deliveries created before `before`, returns how many.
+    pub async fn prune(
+        before: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<u64, JudeHarleyError> {
+        let result = Entity::delete_many()
+            .filter(Column::Status.ne(STATUS_PENDING))
+            .filter(Column::CreatedAt.lt(before))
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
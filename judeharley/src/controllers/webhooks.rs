@@ -0,0 +1,76 @@
+use sea_orm::{prelude::*, QueryOrder, Set};
+
+use crate::entities::webhooks::*;
+use crate::prelude::JudeHarleyError;
+
+impl Model {
+    /// Registers a webhook for `events`, a comma separated list of event names.
+    pub async fn create(
+        url: &str,
+        events: &str,
+        secret: &str,
+        db: &DatabaseConnection,
+    ) -> Result<Self, JudeHarleyError> {
+        ActiveModel {
+            url: Set(url.to_string()),
+            events: Set(events.to_string()),
+            secret: Set(secret.to_string()),
+            enabled: Set(true),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get(id: i32, db: &DatabaseConnection) -> Result<Option<Self>, JudeHarleyError> {
+        Entity::find_by_id(id).one(db).await.map_err(Into::into)
+    }
+
+    pub async fn get_all(db: &DatabaseConnection) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_enabled(db: &DatabaseConnection) -> Result<Vec<Self>, JudeHarleyError> {
+        Entity::find()
+            .filter(Column::Enabled.eq(true))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Deletes the webhook along with its delivery log, returns whether it existed.
+    pub async fn delete(id: i32, db: &DatabaseConnection) -> Result<bool, JudeHarleyError> {
+        let result = Entity::delete_by_id(id).exec(db).await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Returns whether the webhook exists.
+    pub async fn set_enabled(
+        id: i32,
+        enabled: bool,
+        db: &DatabaseConnection,
+    ) -> Result<bool, JudeHarleyError> {
+        let result = Entity::update_many()
+            .col_expr(Column::Enabled, Expr::value(enabled))
+            .filter(Column::Id.eq(id))
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// The event names the webhook is subscribed to.
+    pub fn event_names(&self) -> impl Iterator<Item = &str> {
+        self.events
+            .split(',')
+            .map(str::trim)
+            .filter(|event| !event.is_empty())
+    }
+}
@@ -18,6 +18,12 @@ pub mod songs {
         #[sea_orm(unique)]
         pub file_hash: String,
         pub bitrate: i32,
+        pub track_number: Option<i32>,
+        pub disc_number: Option<i32>,
+        pub year: Option<i32>,
+        pub genre: Option<String>,
+        pub album_artist: Option<String>,
+        pub hidden: bool,
     }
 
     #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -9,6 +9,7 @@ pub struct Model {
     pub id: i32,
     pub user_id: i64,
     pub song_id: String,
+    pub created_at: DateTime,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -2,13 +2,18 @@
 
 use sea_orm::entity::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "played_songs")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub song_id: String,
     pub played_at: DateTime,
+    pub peak_listeners: Option<i32>,
+    pub ended_at: Option<DateTime>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub played_duration: Option<f64>,
+    pub skipped: Option<bool>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -3,13 +3,31 @@
 pub use super::cans::Entity as Cans;
 pub use super::connected_youtube_accounts::Entity as ConnectedYoutubeAccounts;
 pub use super::favourite_songs::Entity as FavouriteSongs;
+pub use super::feed_tokens::Entity as FeedTokens;
+pub use super::indexed_albums::Entity as IndexedAlbums;
+pub use super::indexer_runs::Entity as IndexerRuns;
+pub use super::jingles::Entity as Jingles;
+pub use super::listenbrainz_accounts::Entity as ListenbrainzAccounts;
+pub use super::listener_samples::Entity as ListenerSamples;
+pub use super::live_djs::Entity as LiveDjs;
+pub use super::live_sessions::Entity as LiveSessions;
 pub use super::played_songs::Entity as PlayedSongs;
+pub use super::queued_albums::Entity as QueuedAlbums;
+pub use super::schedule_blocks::Entity as ScheduleBlocks;
+pub use super::scrobble_queue::Entity as ScrobbleQueue;
 pub use super::server_channel_config::Entity as ServerChannelConfig;
 pub use super::server_config::Entity as ServerConfig;
 pub use super::server_role_config::Entity as ServerRoleConfig;
 pub use super::slcb_currency::Entity as SlcbCurrency;
 pub use super::slcb_rank::Entity as SlcbRank;
+pub use super::song_lyrics::Entity as SongLyrics;
+pub use super::song_metadata_edits::Entity as SongMetadataEdits;
+pub use super::song_metadata_overrides::Entity as SongMetadataOverrides;
+pub use super::song_reports::Entity as SongReports;
 pub use super::song_requests::Entity as SongRequests;
 pub use super::song_tags::Entity as SongTags;
 pub use super::songs::Entity as Songs;
+pub use super::unknown_plays::Entity as UnknownPlays;
 pub use super::users::Entity as Users;
+pub use super::webhook_deliveries::Entity as WebhookDeliveries;
+pub use super::webhooks::Entity as Webhooks;
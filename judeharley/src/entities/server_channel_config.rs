@@ -11,6 +11,9 @@ pub struct Model {
     pub allow_watch_time_accumulation: bool,
     pub allow_point_accumulation: bool,
     pub hydration_reminder: bool,
+    pub ops_alerts: bool,
+    pub announcements: bool,
+    pub song_reports: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,249 @@
+//! Dependency checks backing the `/health` endpoints and `/admin status`.
+//!
+//! Every check reports the dependency's version and uptime when it can find
+//! out about them, and gives up after [`CHECK_TIMEOUT`].
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use fred::interfaces::ServerInterface;
+use fred::pool::RedisPool;
+use fred::types::InfoKind;
+use lazy_static::lazy_static;
+use sea_orm::{DatabaseConnection, DbBackend, FromQueryResult, Statement};
+use serde::Serialize;
+
+use crate::communication::{ByersUnixStream, LiquidsoapCommunication};
+use crate::prelude::*;
+
+/// How long a single dependency check may take before it counts as failed.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+lazy_static! {
+    static ref STARTED_AT: Instant = Instant::now();
+}
+
+/// Marks the service as started, call it first thing in `main`.
+pub fn init() {
+    lazy_static::initialize(&STARTED_AT);
+}
+
+/// How long the service has been running for.
+pub fn uptime() -> Duration {
+    STARTED_AT.elapsed()
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DependencyStatus {
+    pub name: &'static str,
+    pub healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime_seconds: Option<u64>,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The body of a service's `/health` response.
+#[derive(Serialize, Debug, Clone)]
+pub struct HealthReport {
+    pub service: &'static str,
+    pub version: &'static str,
+    pub healthy: bool,
+    pub uptime_seconds: u64,
+    pub dependencies: Vec<DependencyStatus>,
+}
+
+impl HealthReport {
+    pub fn new(
+        service: &'static str,
+        version: &'static str,
+        dependencies: Vec<DependencyStatus>,
+    ) -> Self {
+        Self {
+            service,
+            version,
+            healthy: dependencies.iter().all(|d| d.healthy),
+            uptime_seconds: uptime().as_secs(),
+            dependencies,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct DependencyInfo {
+    version: Option<String>,
+    uptime_seconds: Option<u64>,
+}
+
+async fn check<F>(name: &'static str, probe: F) -> DependencyStatus
+where
+    F: Future<Output = Result<DependencyInfo>>,
+{
+    let start = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, probe).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let (info, error) = match result {
+        Ok(Ok(info)) => (info, None),
+        Ok(Err(e)) => (DependencyInfo::default(), Some(e.to_string())),
+        Err(_) => (
+            DependencyInfo::default(),
+            Some(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+        ),
+    };
+
+    DependencyStatus {
+        name,
+        healthy: error.is_none(),
+        version: info.version,
+        uptime_seconds: info.uptime_seconds,
+        latency_ms,
+        error,
+    }
+}
+
+#[derive(FromQueryResult)]
+struct PostgresInfo {
+    version: String,
+    uptime: i64,
+}
+
+pub async fn check_database(db: &DatabaseConnection) -> DependencyStatus {
+    check("database", async {
+        let info = PostgresInfo::find_by_statement(Statement::from_string(
+            DbBackend::Postgres,
+            "SELECT current_setting('server_version') AS version, \
+             EXTRACT(EPOCH FROM now() - pg_postmaster_start_time())::bigint AS uptime",
+        ))
+        .one(db)
+        .await?;
+
+        Ok(DependencyInfo {
+            version: info.as_ref().map(|i| i.version.clone()),
+            uptime_seconds: info.map(|i| i.uptime.max(0) as u64),
+        })
+    })
+    .await
+}
+
+pub async fn check_redis(pool: &RedisPool) -> DependencyStatus {
+    check("redis", async {
+        let info: String = pool.info(Some(InfoKind::Server)).await?;
+
+        Ok(parse_redis_info(&info))
+    })
+    .await
+}
+
+/// Checks the Liquidsoap socket over a connection of its own, so a stuck
+/// check can't leave a shared connection halfway through a response.
+pub async fn check_liquidsoap() -> DependencyStatus {
+    check("liquidsoap", async {
+        let mut stream = ByersUnixStream::new().await?;
+        let version = stream.send_wait("version").await?;
+        let uptime = stream.send_wait("uptime").await?;
+
+        Ok(DependencyInfo {
+            version: Some(version.trim().to_string()),
+            uptime_seconds: parse_liquidsoap_uptime(&uptime),
+        })
+    })
+    .await
+}
+
+/// Checks the database, Redis and the Liquidsoap socket at the same time.
+pub async fn check_dependencies(
+    db: &DatabaseConnection,
+    redis_pool: &RedisPool,
+) -> Vec<DependencyStatus> {
+    let (database, redis, liquidsoap) = tokio::join!(
+        check_database(db),
+        check_redis(redis_pool),
+        check_liquidsoap()
+    );
+
+    vec![database, redis, liquidsoap]
+}
+
+fn parse_redis_info(info: &str) -> DependencyInfo {
+    let mut result = DependencyInfo::default();
+    for line in info.lines() {
+        match line.trim().split_once(':') {
+            Some(("redis_version", version)) => result.version = Some(version.to_string()),
+            Some(("uptime_in_seconds", uptime)) => result.uptime_seconds = uptime.parse().ok(),
+            _ => {}
+        }
+    }
+
+    result
+}
+
+/// Parses Liquidsoap's `uptime` response, e.g. `0d 02h 13m 07s`.
+fn parse_liquidsoap_uptime(uptime: &str) -> Option<u64> {
+    if uptime.trim().is_empty() {
+        return None;
+    }
+
+    uptime
+        .split_whitespace()
+        .map(|part| {
+            let (value, unit) = part.split_at(part.len().checked_sub(1)?);
+            let value = value.parse::<u64>().ok()?;
+            match unit {
+                "d" => Some(value * 86_400),
+                "h" => Some(value * 3_600),
+                "m" => Some(value * 60),
+                "s" => Some(value),
+                _ => None,
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_redis_info() {
+        let info = "# Server\r\nredis_version:7.2.4\r\nredis_mode:standalone\r\nuptime_in_seconds:5025\r\nuptime_in_days:0\r\n";
+
+        assert_eq!(
+            parse_redis_info(info),
+            DependencyInfo {
+                version: Some("7.2.4".to_string()),
+                uptime_seconds: Some(5025),
+            }
+        );
+        assert_eq!(parse_redis_info(""), DependencyInfo::default());
+    }
+
+    #[test]
+    fn test_parse_liquidsoap_uptime() {
+        assert_eq!(parse_liquidsoap_uptime("0d 02h 13m 07s\r\n"), Some(7987));
+        assert_eq!(parse_liquidsoap_uptime("3d 00h 00m 00s"), Some(259_200));
+        assert_eq!(parse_liquidsoap_uptime("garbage"), None);
+        assert_eq!(parse_liquidsoap_uptime(""), None);
+    }
+
+    #[test]
+    fn test_report_is_unhealthy_if_any_dependency_is() {
+        let status = |healthy| DependencyStatus {
+            name: "test",
+            healthy,
+            version: None,
+            uptime_seconds: None,
+            latency_ms: 0,
+            error: None,
+        };
+
+        let report = HealthReport::new("byers", "1.0.0", vec![status(true)]);
+        assert!(report.healthy);
+
+        let report = HealthReport::new("byers", "1.0.0", vec![status(true), status(false)]);
+        assert!(!report.healthy);
+    }
+}
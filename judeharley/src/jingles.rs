@@ -0,0 +1,261 @@
+//! Jingles and station IDs are played between songs every few songs or
+//! minutes.
+//!
+//! They live in their own directory and table, so they never show up in
+//! searches or requests, and their plays aren't recorded as song plays.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{Duration, NaiveDateTime};
+use rand::{seq::SliceRandom, Rng};
+use sea_orm::DatabaseConnection;
+use tracing::{error, info, warn};
+
+use crate::{
+    controllers::jingles::NewJingle,
+    maintenance::{metadata::MusicMetadata, rewrite_jingle_path},
+    prelude::*,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct JingleRules {
+    /// Plays a jingle after this many songs, if set.
+    pub every_songs: Option<u64>,
+    /// Plays a jingle when the last one is this old, if set.
+    pub every_minutes: Option<i64>,
+}
+
+impl JingleRules {
+    pub fn enabled(&self) -> bool {
+        self.every_songs.is_some_and(|songs| songs > 0)
+            || self.every_minutes.is_some_and(|minutes| minutes > 0)
+    }
+
+    /// Whether a jingle should play next, given when the last one played and
+    /// how many songs played since then.
+    pub fn is_due(
+        &self,
+        last_jingle_at: Option<NaiveDateTime>,
+        songs_since: u64,
+        now: NaiveDateTime,
+    ) -> bool {
+        if self
+            .every_songs
+            .is_some_and(|songs| songs > 0 && songs_since >= songs)
+        {
+            return true;
+        }
+
+        match (
+            self.every_minutes.filter(|minutes| *minutes > 0),
+            last_jingle_at,
+        ) {
+            // never two jingles in a row
+            (Some(_), None) => songs_since > 0,
+            (Some(minutes), Some(last)) => {
+                songs_since > 0 && now - last >= Duration::minutes(minutes)
+            }
+            (None, _) => false,
+        }
+    }
+}
+
+/// Picks a jingle, avoiding the one that played last if there is a choice.
+pub fn pick<'a, R: Rng + ?Sized>(jingles: &'a [Jingles], rng: &mut R) -> Option<&'a Jingles> {
+    let last = jingles
+        .iter()
+        .filter(|jingle| jingle.last_played_at.is_some())
+        .max_by_key(|jingle| jingle.last_played_at)
+        .map(|jingle| jingle.id);
+    let eligible = jingles
+        .iter()
+        .filter(|jingle| jingles.len() == 1 || Some(jingle.id) != last)
+        .collect::<Vec<_>>();
+
+    eligible.choose(rng).copied()
+}
+
+/// The jingle to play next, or `None` if none is due or there are none.
+pub async fn next_jingle(
+    rules: &JingleRules,
+    now: NaiveDateTime,
+    db: &DatabaseConnection,
+) -> Result<Option<Jingles>> {
+    if !rules.enabled() {
+        return Ok(None);
+    }
+
+    let last_jingle_at = Jingles::last_played_at(db).await?;
+    let songs_since = PlayedSongs::count_since(last_jingle_at, db).await?;
+    if !rules.is_due(last_jingle_at, songs_since, now) {
+        return Ok(None);
+    }
+
+    let jingles = Jingles::get_enabled(db).await?;
+    let jingle = pick(&jingles, &mut rand::thread_rng()).cloned();
+
+    Ok(jingle)
+}
+
+/// What a jingle sync changed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SyncSummary {
+    pub added: usize,
+    pub removed: u64,
+}
+
+/// Adds the audio files in `jingles_path` that aren't jingles yet and removes
+/// the jingles whose files are gone.
+#[tracing::instrument(skip(db))]
+pub async fn sync(db: &DatabaseConnection, jingles_path: &Path) -> Result<SyncSummary> {
+    let files = walkdir::WalkDir::new(jingles_path)
+        .into_iter()
+        .filter_map(|e| match e {
+            Ok(e) => Some(e),
+            Err(e) => {
+                error!("Failed to walk directory: {}", e);
+                None
+            }
+        })
+        .filter(|e| {
+            e.file_type().is_file()
+                && e.path().extension().is_some_and(|extension| {
+                    SUPPORTED_AUDIO_FORMATS
+                        .contains(&extension.to_string_lossy().to_lowercase().as_str())
+                })
+        })
+        .map(|e| e.path().to_owned())
+        .collect::<Vec<_>>();
+
+    let mut summary = SyncSummary::default();
+    let mut paths = vec![];
+    for file in files {
+        let db_path = rewrite_jingle_path(&file, jingles_path)?
+            .to_string_lossy()
+            .to_string();
+        paths.push(db_path.clone());
+        if Jingles::get_by_path(&db_path, db).await?.is_some() {
+            continue;
+        }
+
+        let metadata = match MusicMetadata::new(&file) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                warn!("Failed to read jingle {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let title = file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| db_path.clone());
+
+        Jingles::insert(
+            NewJingle {
+                file_path: db_path,
+                title,
+                duration: metadata.duration,
+            },
+            db,
+        )
+        .await?;
+        summary.added += 1;
+    }
+    summary.removed = Jingles::delete_missing(paths, db).await?;
+
+    if summary.added > 0 || summary.removed > 0 {
+        info!(
+            "Synced jingles: {} added, {} removed",
+            summary.added, summary.removed
+        );
+    }
+
+    Ok(summary)
+}
+
+/// Syncs the jingles directory every `interval`, forever.
+pub async fn run_sync(
+    db: DatabaseConnection,
+    jingles_path: PathBuf,
+    interval: std::time::Duration,
+) -> Result<()> {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+
+        if let Err(e) = sync(&db, &jingles_path).await {
+            error!(jingles_path = %jingles_path.display(), error = %e, "failed to sync jingles");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    fn jingle(id: i32, minutes_ago: Option<i64>) -> Jingles {
+        Jingles {
+            id,
+            file_path: format!("/jingles/{id}.mp3"),
+            title: id.to_string(),
+            duration: 5.0,
+            enabled: true,
+            play_count: 0,
+            last_played_at: minutes_ago.map(|minutes| now() - Duration::minutes(minutes)),
+            created_at: now(),
+        }
+    }
+
+    #[test]
+    fn test_due_every_few_songs() {
+        let rules = JingleRules {
+            every_songs: Some(4),
+            every_minutes: None,
+        };
+        let last = Some(now() - Duration::minutes(5));
+
+        assert!(!rules.is_due(last, 3, now()));
+        assert!(rules.is_due(last, 4, now()));
+        assert!(rules.is_due(None, 4, now()));
+        assert!(!JingleRules::default().is_due(None, 100, now()));
+    }
+
+    #[test]
+    fn test_due_every_few_minutes() {
+        let rules = JingleRules {
+            every_songs: None,
+            every_minutes: Some(30),
+        };
+
+        assert!(!rules.is_due(Some(now() - Duration::minutes(29)), 5, now()));
+        assert!(rules.is_due(Some(now() - Duration::minutes(30)), 5, now()));
+        // never two jingles in a row
+        assert!(!rules.is_due(Some(now() - Duration::minutes(90)), 0, now()));
+        assert!(rules.is_due(None, 1, now()));
+    }
+
+    #[test]
+    fn test_pick_avoids_last_jingle() {
+        let jingles = vec![jingle(1, Some(10)), jingle(2, Some(3)), jingle(3, None)];
+        let mut rng = StdRng::seed_from_u64(413);
+        for _ in 0..50 {
+            assert_ne!(pick(&jingles, &mut rng).unwrap().id, 2);
+        }
+
+        let only = vec![jingle(1, Some(3))];
+        assert_eq!(pick(&only, &mut rng).unwrap().id, 1);
+        assert!(pick(&[], &mut rng).is_none());
+    }
+}
@@ -9,15 +9,28 @@ pub use crate::prelude::*;
 pub use sea_orm;
 pub use sea_orm::entity::prelude::Decimal;
 
+pub mod alerts;
 pub mod communication;
 pub mod controllers;
 pub mod cooldowns;
 pub mod custom_entities;
 pub mod discord;
 pub mod entities;
+pub mod health;
+pub mod jingles;
+pub mod live;
+pub mod metrics;
 pub mod prelude;
+pub mod rotation;
+pub mod schedule;
+pub mod search;
+pub mod webhooks;
 
 pub mod maintenance;
+pub mod now_playing;
+
+#[cfg(test)]
+mod test_util;
 
 pub async fn migrate(db: &sea_orm::DatabaseConnection) -> Result<()> {
     migration::Migrator::up(db, None).await?;
@@ -0,0 +1,108 @@
+//! Live DJ sessions. DJs stream to Liquidsoap's harbor with their own
+//! credentials while a session of theirs is on air, and the request queues
+//! are paused until it ends.
+
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use rand::{distributions::Alphanumeric, Rng, RngCore};
+use sea_orm::DatabaseConnection;
+
+use crate::prelude::*;
+
+/// Generates a harbor password, it is only shown to the DJ once.
+pub fn generate_password() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(24)
+        .map(char::from)
+        .collect()
+}
+
+pub fn hash_password(password: &str) -> Result<String> {
+    let mut salt = [0; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let salt = SaltString::encode_b64(&salt)?;
+
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// A harbor login derived from the DJ's name, e.g. `dj-vantas` for
+/// "DJ Vantas".
+pub fn username_for(name: &str) -> String {
+    let mut username = String::new();
+    for c in name.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            username.push(c);
+        } else if !username.is_empty() && !username.ends_with('-') {
+            username.push('-');
+        }
+    }
+    let username = username.trim_end_matches('-');
+
+    if username.is_empty() {
+        "dj".to_string()
+    } else {
+        username.to_string()
+    }
+}
+
+/// Checks a harbor login, returns the DJ if the credentials are theirs and
+/// their session is on air.
+pub async fn authenticate(
+    username: &str,
+    password: &str,
+    db: &DatabaseConnection,
+) -> Result<Option<LiveDjs>> {
+    let Some(dj) = LiveDjs::get_by_username(username, db).await? else {
+        return Ok(None);
+    };
+    if !verify_password(password, &dj.password_hash) {
+        return Ok(None);
+    }
+
+    let on_air = LiveSessions::current(db)
+        .await?
+        .is_some_and(|session| session.user_id == Some(dj.user_id));
+
+    Ok(on_air.then_some(dj))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashes_and_verifies_passwords() {
+        let password = generate_password();
+        assert_eq!(password.len(), 24);
+
+        let hash = hash_password(&password).unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(!hash.contains(&password));
+        assert!(verify_password(&password, &hash));
+        assert!(!verify_password("hackme", &hash));
+        assert!(!verify_password(&password, "not a hash"));
+
+        // every hash gets its own salt
+        assert_ne!(hash, hash_password(&password).unwrap());
+    }
+
+    #[test]
+    fn test_username_for() {
+        assert_eq!(username_for("DJ Vantas"), "dj-vantas");
+        assert_eq!(username_for("  Sollux (Captor)  "), "sollux-captor");
+        assert_eq!(username_for("ΣΣΣ"), "dj");
+    }
+}
@@ -9,6 +9,7 @@ use crate::{
     controllers::song_tags::NewTag,
     controllers::songs::NewSong,
     maintenance::rewrite_music_path,
+    metrics,
     prelude::{Songs, *},
 };
 
@@ -29,8 +30,34 @@ impl WavTag for Id3v2Tag {
     }
 }
 
+/// How many files an indexing run went through.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IndexSummary {
+    pub indexed: usize,
+    pub failed: usize,
+}
+
 #[tracing::instrument(skip(db))]
 pub async fn index(db: &DatabaseConnection, directory: PathBuf) -> Result<()> {
+    metrics::INDEXER_RUNS.inc();
+    let run = IndexerRuns::start(db).await?;
+
+    let result = index_library(db, directory).await;
+    let (summary, error) = match &result {
+        Ok(summary) => (*summary, None),
+        Err(e) => {
+            metrics::INDEXER_RUN_FAILURES.inc();
+            (IndexSummary::default(), Some(e.to_string()))
+        }
+    };
+    if let Err(e) = run.finish(summary.indexed, summary.failed, error, db).await {
+        warn!("Failed to record indexer run: {}", e);
+    }
+
+    result.map(|_| ())
+}
+
+async fn index_library(db: &DatabaseConnection, directory: PathBuf) -> Result<IndexSummary> {
     info!("Pruning indexing database");
     Songs::prune(db).await?;
 
@@ -66,6 +93,7 @@ pub async fn index(db: &DatabaseConnection, directory: PathBuf) -> Result<()> {
         let result = index_file(db, &file, &directory).await;
         if let Err(e) = result {
             error!("failed to index file: {}", e);
+            metrics::INDEXER_FILE_FAILURES.inc();
             failed_files.push(file);
         }
     }
@@ -75,24 +103,60 @@ pub async fn index(db: &DatabaseConnection, directory: PathBuf) -> Result<()> {
         warn!("Failed files: {:#?}", failed_files);
     }
 
-    Ok(())
+    Ok(IndexSummary {
+        indexed: len - failed_files.len(),
+        failed: failed_files.len(),
+    })
 }
 
+/// The lowercase extension of `path` if it's a supported audio file.
+fn audio_format(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .filter(|extension| SUPPORTED_AUDIO_FORMATS.contains(&extension.as_str()))
+}
+
+/// Indexes a single file with its metadata overrides applied, returns the new
+/// song or `None` if the file isn't a supported audio file.
 #[tracing::instrument(skip(db))]
-pub async fn index_file(db: &DatabaseConnection, path: &Path, music_path: &Path) -> Result<()> {
-    if !SUPPORTED_AUDIO_FORMATS.contains(
-        &path
-            .extension()
-            .unwrap()
-            .to_string_lossy()
-            .to_lowercase()
-            .as_str(),
-    ) {
-        return Ok(());
-    }
+pub async fn index_file(
+    db: &DatabaseConnection,
+    path: &Path,
+    music_path: &Path,
+) -> Result<Option<Songs>> {
+    index_song(db, path, music_path, false).await
+}
+
+/// Indexes a file again, e.g. after its metadata overrides changed. The song
+/// is updated in place, nothing is touched if the file can't be read.
+#[tracing::instrument(skip(db))]
+pub async fn reindex_file(
+    db: &DatabaseConnection,
+    path: &Path,
+    music_path: &Path,
+) -> Result<Option<Songs>> {
+    index_song(db, path, music_path, true).await
+}
+
+async fn index_song(
+    db: &DatabaseConnection,
+    path: &Path,
+    music_path: &Path,
+    replace: bool,
+) -> Result<Option<Songs>> {
+    let Some(format) = audio_format(path) else {
+        return Ok(None);
+    };
+
+    let mut hasher: Sha256 = Digest::new();
+    hasher.update(path.canonicalize()?.to_string_lossy().as_bytes());
+    let hash = hasher.finalize();
+    let hash_str = format!("{:x}", hash);
+
+    let overrides = SongMetadataOverrides::get(&hash_str, db).await?;
 
     let (title, artist, album) = {
-        if path.extension().unwrap().to_ascii_lowercase() == "wav" {
+        if format == "wav" {
             let tag = Id3v2Tag::read_from_wav_path(path)?;
 
             (
@@ -111,45 +175,63 @@ pub async fn index_file(db: &DatabaseConnection, path: &Path, music_path: &Path)
     };
     // let meta = metadata::media_file::MediaFileMetadata::new(&path)?;
     let meta = super::metadata::MusicMetadata::new(&path)?;
-
-    let mut hasher: Sha256 = Digest::new();
-    hasher.update(path.canonicalize()?.to_string_lossy().as_bytes());
-    let hash = hasher.finalize();
-    let hash_str = format!("{:x}", hash);
+    let typed = super::metadata::TypedTags::from_tags(&meta.tags);
+    let lyrics = super::lyrics::find_lyrics(path, &meta.tags);
 
     let path = rewrite_music_path(path, music_path)?;
 
+    let mut new_song = NewSong {
+        title: title.replace(char::from(0), ""),
+        artist: artist.replace(char::from(0), ""),
+        album: album.replace(char::from(0), ""),
+        file_path: path.display().to_string(),
+        file_hash: hash_str,
+        duration: meta.duration,
+        bitrate: meta.bitrate as i32,
+        track_number: typed.track_number,
+        disc_number: typed.disc_number,
+        year: typed.year,
+        genre: typed.genre,
+        album_artist: typed.album_artist,
+        hidden: false,
+    };
+    if let Some(overrides) = &overrides {
+        overrides.apply(&mut new_song);
+    }
+
     info!(
-        "Indexing {title} by {artist} on {album} at path {}",
+        "Indexing {} by {} on {} at path {}",
+        new_song.title,
+        new_song.artist,
+        new_song.album,
         path.display()
     );
 
-    let song = Songs::insert(
-        NewSong {
-            title: title.replace(char::from(0), ""),
-            artist: artist.replace(char::from(0), ""),
-            album: album.replace(char::from(0), ""),
-            file_path: path.display().to_string(),
-            file_hash: hash_str.clone(),
-            duration: meta.duration,
-            bitrate: meta.bitrate as i32,
-        },
-        db,
-    )
-    .await?;
+    let song = if replace {
+        Songs::upsert(new_song, db).await?
+    } else {
+        Songs::insert(new_song, db).await?
+    };
 
     Tags::insert_many(
         &song,
-        &meta
-            .tags
+        &super::lyrics::without_lyrics(meta.tags)
             .into_iter()
             .map(|(k, v)| NewTag(k, v))
             .collect::<Vec<_>>(),
         db,
     )
     .await?;
+    match lyrics {
+        Some(lyrics) => {
+            SongLyrics::insert(&song, lyrics, db).await?;
+        }
+        None if replace => SongLyrics::delete_by_song(&song, db).await?,
+        None => {}
+    }
+    IndexedAlbums::record(song.album_artist(), &song.album, db).await?;
 
-    Ok(())
+    Ok(Some(song))
 }
 
 pub async fn drop_index(db: &DatabaseConnection, path: &Path, music_path: &Path) -> Result<()> {
@@ -192,3 +274,32 @@ pub async fn create_playlist(db: &DatabaseConnection, playlist_path: &Path) -> R
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_format() {
+        assert_eq!(
+            audio_format(Path::new("/music/Showtime.MP3")),
+            Some("mp3".to_string())
+        );
+        assert_eq!(audio_format(Path::new("/music/Showtime.lrc")), None);
+        assert_eq!(audio_format(Path::new("/music/Showtime")), None);
+    }
+
+    #[tokio::test]
+    async fn test_index_file_without_extension() {
+        // returns before touching the database
+        let song = index_file(
+            &DatabaseConnection::Disconnected,
+            Path::new("/music/Showtime"),
+            Path::new("/music"),
+        )
+        .await
+        .unwrap();
+
+        assert!(song.is_none());
+    }
+}
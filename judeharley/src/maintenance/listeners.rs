@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use tracing::{debug, error};
+
+use crate::controllers::listener_samples::NewListenerSample;
+use crate::prelude::*;
+
+#[derive(Deserialize)]
+struct IcecastStatus {
+    icestats: IceStats,
+}
+
+#[derive(Deserialize)]
+struct IceStats {
+    /// Icecast returns a single object for one mount, an array for several
+    /// mounts and omits the field entirely if nothing is streaming.
+    #[serde(default)]
+    source: Option<Sources>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Sources {
+    One(Source),
+    Many(Vec<Source>),
+}
+
+#[derive(Deserialize)]
+struct Source {
+    listenurl: String,
+    #[serde(default)]
+    listeners: i32,
+}
+
+/// Parses the listener count of every mount out of Icecast's `status-json.xsl`.
+pub fn parse_status(body: &str) -> Result<Vec<NewListenerSample>> {
+    let status: IcecastStatus = serde_json::from_str(body)?;
+
+    let sources = match status.icestats.source {
+        None => vec![],
+        Some(Sources::One(source)) => vec![source],
+        Some(Sources::Many(sources)) => sources,
+    };
+
+    Ok(sources
+        .into_iter()
+        .map(|source| NewListenerSample {
+            mount: reqwest::Url::parse(&source.listenurl)
+                .map(|url| url.path().to_string())
+                .unwrap_or(source.listenurl),
+            listeners: source.listeners,
+        })
+        .collect())
+}
+
+pub async fn fetch_listeners(
+    client: &reqwest::Client,
+    status_url: &str,
+) -> Result<Vec<NewListenerSample>> {
+    let body = client
+        .get(status_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    parse_status(&body)
+}
+
+/// Takes a single listener sample, stores it and raises the peak listener
+/// count of the current song if needed. Returns the total listener count.
+pub async fn sample_listeners(
+    client: &reqwest::Client,
+    status_url: &str,
+    db: &sea_orm::DatabaseConnection,
+) -> Result<i32> {
+    let samples = fetch_listeners(client, status_url).await?;
+    let total = samples.iter().map(|s| s.listeners).sum();
+    let sampled_at = chrono::Utc::now().naive_utc();
+
+    ListenerSamples::insert_many(samples, sampled_at, db).await?;
+    PlayedSongs::record_listeners(total, db).await?;
+
+    Ok(total)
+}
+
+/// Polls Icecast forever, failed polls are logged and retried on the next tick.
+pub async fn poll_listeners(
+    db: sea_orm::DatabaseConnection,
+    status_url: String,
+    interval: Duration,
+) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+
+        match sample_listeners(&client, &status_url, &db).await {
+            Ok(total) => debug!(listeners = total, "sampled listeners"),
+            Err(e) => error!(status_url, error = %e, "failed to sample listeners"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    const ONE_MOUNT: &str = r#"{"icestats":{"admin":"icemaster@localhost","host":"ice","server_id":"Icecast 2.4.4","source":{"listeners":3,"listenurl":"http://ice:8000/lumiradio","server_name":"lumiRadio"}}}"#;
+    const TWO_MOUNTS: &str = r#"{"icestats":{"host":"ice","source":[{"listeners":3,"listenurl":"http://ice:8000/lumiradio"},{"listeners":2,"listenurl":"http://ice:8000/lumiradio.ogg"}]}}"#;
+    const NO_MOUNTS: &str = r#"{"icestats":{"host":"ice","server_id":"Icecast 2.4.4"}}"#;
+
+    /// Serves a single HTTP response on a random local port and returns its URL.
+    async fn stand_in(status: &'static str, body: &'static str) -> String {
+        let (base_url, _request) = test_util::stand_in(status, body).await;
+
+        format!("{base_url}/status-json.xsl")
+    }
+
+    fn counts(samples: Vec<NewListenerSample>) -> Vec<(String, i32)> {
+        samples
+            .into_iter()
+            .map(|s| (s.mount, s.listeners))
+            .collect()
+    }
+
+    #[test]
+    fn parses_single_mount() {
+        let samples = parse_status(ONE_MOUNT).unwrap();
+        assert_eq!(counts(samples), vec![("/lumiradio".to_string(), 3)]);
+    }
+
+    #[test]
+    fn parses_multiple_mounts() {
+        let samples = parse_status(TWO_MOUNTS).unwrap();
+        assert_eq!(
+            counts(samples),
+            vec![
+                ("/lumiradio".to_string(), 3),
+                ("/lumiradio.ogg".to_string(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_no_mounts() {
+        assert!(parse_status(NO_MOUNTS).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn fetches_from_icecast() {
+        let url = stand_in("200 OK", TWO_MOUNTS).await;
+        let samples = fetch_listeners(&reqwest::Client::new(), &url)
+            .await
+            .unwrap();
+
+        assert_eq!(samples.iter().map(|s| s.listeners).sum::<i32>(), 5);
+    }
+
+    #[tokio::test]
+    async fn fails_on_server_error() {
+        let url = stand_in("500 Internal Server Error", "").await;
+
+        assert!(fetch_listeners(&reqwest::Client::new(), &url)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn fails_on_garbage() {
+        let url = stand_in("200 OK", "<html>not json</html>").await;
+
+        assert!(fetch_listeners(&reqwest::Client::new(), &url)
+            .await
+            .is_err());
+    }
+}
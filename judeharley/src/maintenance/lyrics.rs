@@ -0,0 +1,320 @@
+//! Lyrics found next to or inside audio files.
+//!
+//! A song's lyrics come from the first of these that has any:
+//!
+//! 1. an `.lrc` file with the same name as the audio file,
+//! 2. an embedded USLT frame or `LYRICS`/`UNSYNCEDLYRICS` tag,
+//! 3. a `.txt` file with the same name as the audio file.
+//!
+//! Lyrics with LRC timestamps like `[01:23.45]` are synced, which works for
+//! embedded lyrics as well.
+
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use tracing::warn;
+
+use crate::controllers::song_lyrics::{NewLyrics, SOURCE_LRC, SOURCE_TAG, SOURCE_TXT};
+use crate::maintenance::metadata::Tags;
+use crate::prelude::SUPPORTED_AUDIO_FORMATS;
+
+/// Tag keys lyrics are read from, ffmpeg names USLT frames `lyrics-<language>`.
+const LYRICS_KEYS: &[&str] = &["lyrics", "unsyncedlyrics", "uslt"];
+
+/// Extensions of the files lyrics are read from next to audio files.
+const SIDECAR_EXTENSIONS: &[&str] = &["lrc", "txt"];
+
+fn is_lyrics_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+
+    LYRICS_KEYS.contains(&key.as_str()) || key.starts_with("lyrics-")
+}
+
+/// A line of lyrics, and when it's sung in seconds if the lyrics are synced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricLine {
+    pub time: Option<f64>,
+    pub text: String,
+}
+
+impl LyricLine {
+    fn unsynced(text: &str) -> Self {
+        Self {
+            time: None,
+            text: text.trim().to_string(),
+        }
+    }
+}
+
+/// Parses a `[mm:ss]`, `[mm:ss.xx]` or `[mm:ss:xx]` timestamp into seconds.
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let (minutes, seconds) = value.split_once(':')?;
+    let minutes = minutes.trim().parse::<u32>().ok()?;
+    let seconds = seconds.trim().replacen(':', ".", 1).parse::<f64>().ok()?;
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+
+    Some(minutes as f64 * 60.0 + seconds)
+}
+
+/// Parses LRC lyrics. Lines can have several timestamps, `[offset:]` shifts
+/// all of them and word timestamps like `<00:12.34>` are left out. If any
+/// line has a timestamp the lines are sorted by it and lines without one are
+/// dropped, otherwise every line is kept as it is.
+pub fn parse_lrc(contents: &str) -> Vec<LyricLine> {
+    lazy_static! {
+        static ref WORD_TIMESTAMP: regex::Regex =
+            regex::Regex::new(r"<\d+:\d+(?:[.:]\d+)?>").unwrap();
+    }
+
+    let mut offset = 0.0;
+    let mut timed = vec![];
+    let mut untimed = vec![];
+    for line in contents.lines() {
+        let mut rest = line.trim();
+        let mut times = vec![];
+        let mut metadata = false;
+        while let Some((tag, after)) = rest.strip_prefix('[').and_then(|rest| rest.split_once(']'))
+        {
+            if let Some(time) = parse_timestamp(tag) {
+                times.push(time);
+            } else if let Some((key, value)) = tag.split_once(':') {
+                // [offset:+500] shows every line half a second earlier
+                if key.trim().eq_ignore_ascii_case("offset") {
+                    offset = value.trim().parse::<f64>().unwrap_or(0.0) / 1000.0;
+                }
+                metadata = true;
+            } else {
+                break;
+            }
+            rest = after;
+        }
+
+        let text = WORD_TIMESTAMP.replace_all(rest, "");
+        if !times.is_empty() {
+            timed.extend(times.into_iter().map(|time| LyricLine {
+                time: Some(time),
+                text: text.trim().to_string(),
+            }));
+        } else if !metadata {
+            untimed.push(LyricLine::unsynced(&text));
+        }
+    }
+
+    if timed.is_empty() {
+        return untimed;
+    }
+    for line in &mut timed {
+        line.time = line.time.map(|time| (time - offset).max(0.0));
+    }
+    timed.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+
+    timed
+}
+
+/// Splits lyrics into lines, parsing them as LRC if they're synced.
+pub fn lines(lyrics: &str, synced: bool) -> Vec<LyricLine> {
+    if synced {
+        parse_lrc(lyrics)
+    } else {
+        lyrics.lines().map(LyricLine::unsynced).collect()
+    }
+}
+
+/// The line being sung `elapsed` seconds into the song.
+pub fn current_line(lines: &[LyricLine], elapsed: f64) -> Option<usize> {
+    lines
+        .iter()
+        .rposition(|line| line.time.is_some_and(|time| time <= elapsed))
+}
+
+fn is_synced(lyrics: &str) -> bool {
+    parse_lrc(lyrics).iter().any(|line| line.time.is_some())
+}
+
+fn normalize(lyrics: &str) -> Option<String> {
+    let lyrics = lyrics.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+
+    Some(lyrics.trim().to_string()).filter(|lyrics| !lyrics.is_empty())
+}
+
+/// Reads the file next to `path` with the `extension`, if there is one.
+fn read_sidecar(path: &Path, extension: &str) -> Option<String> {
+    [extension.to_lowercase(), extension.to_uppercase()]
+        .into_iter()
+        .map(|extension| path.with_extension(extension))
+        .find(|sidecar| sidecar.is_file())
+        .and_then(|sidecar| match std::fs::read(&sidecar) {
+            Ok(contents) => normalize(&String::from_utf8_lossy(&contents)),
+            Err(e) => {
+                warn!("Failed to read lyrics from {}: {}", sidecar.display(), e);
+                None
+            }
+        })
+}
+
+/// The `tags` without the lyrics, which are stored on their own.
+pub fn without_lyrics(tags: Tags) -> Tags {
+    tags.into_iter()
+        .filter(|(key, _)| !is_lyrics_key(key))
+        .collect()
+}
+
+/// The audio file a lyrics file at `path` sits next to, if it is one.
+pub fn sidecar_audio_file(path: &Path) -> Option<PathBuf> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    if !SIDECAR_EXTENSIONS.contains(&extension.as_str()) {
+        return None;
+    }
+
+    let stem = path.file_stem()?;
+    std::fs::read_dir(path.parent()?)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|sibling| {
+            sibling.file_stem() == Some(stem)
+                && sibling.is_file()
+                && sibling.extension().is_some_and(|extension| {
+                    SUPPORTED_AUDIO_FORMATS
+                        .contains(&extension.to_string_lossy().to_lowercase().as_str())
+                })
+        })
+}
+
+fn from_tags(tags: &Tags) -> Option<String> {
+    tags.iter()
+        .filter(|(key, _)| is_lyrics_key(key))
+        .find_map(|(_, value)| normalize(value))
+}
+
+/// The lyrics of the audio file at `path` with the `tags` read from it.
+pub fn find_lyrics(path: &Path, tags: &Tags) -> Option<NewLyrics> {
+    let (source, lyrics) = read_sidecar(path, "lrc")
+        .map(|lyrics| (SOURCE_LRC, lyrics))
+        .or_else(|| from_tags(tags).map(|lyrics| (SOURCE_TAG, lyrics)))
+        .or_else(|| read_sidecar(path, "txt").map(|lyrics| (SOURCE_TXT, lyrics)))?;
+
+    Some(NewLyrics {
+        source: source.to_string(),
+        synced: source != SOURCE_TXT && is_synced(&lyrics),
+        lyrics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(time: Option<f64>, text: &str) -> LyricLine {
+        LyricLine {
+            time,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_is_lyrics_key() {
+        assert!(is_lyrics_key("LYRICS"));
+        assert!(is_lyrics_key("UNSYNCEDLYRICS"));
+        assert!(is_lyrics_key("lyrics-eng"));
+        assert!(!is_lyrics_key("lyricist"));
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("01:23.45"), Some(83.45));
+        assert_eq!(parse_timestamp("00:07"), Some(7.0));
+        assert_eq!(parse_timestamp("00:07:50"), Some(7.5));
+        assert_eq!(parse_timestamp("ar:Toby Fox"), None);
+        assert_eq!(parse_timestamp("00:75"), None);
+    }
+
+    #[test]
+    fn test_parse_lrc() {
+        let lrc = "[ar:Toby Fox]\n\
+                   [offset:+500]\n\
+                   [00:12.50]Hello <00:13.00>world\n\
+                   [00:05.00][00:30.00]Chorus\n\
+                   \n\
+                   [00:20.00]\n\
+                   stray line";
+
+        assert_eq!(
+            parse_lrc(lrc),
+            vec![
+                line(Some(4.5), "Chorus"),
+                line(Some(12.0), "Hello world"),
+                line(Some(19.5), ""),
+                line(Some(29.5), "Chorus"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsynced_lines() {
+        let lyrics = "[Verse 1]\nSburb\n\nSkaia";
+
+        assert_eq!(
+            lines(lyrics, false),
+            vec![
+                line(None, "[Verse 1]"),
+                line(None, "Sburb"),
+                line(None, ""),
+                line(None, "Skaia"),
+            ]
+        );
+        assert!(!is_synced(lyrics));
+        assert!(is_synced("[00:01.00]Sburb"));
+    }
+
+    #[test]
+    fn test_current_line() {
+        let synced = parse_lrc("[00:05.00]one\n[00:10.00]two\n[00:15.00]three");
+
+        assert_eq!(current_line(&synced, 2.0), None);
+        assert_eq!(current_line(&synced, 10.0), Some(1));
+        assert_eq!(current_line(&synced, 99.0), Some(2));
+        assert_eq!(current_line(&lines("one\ntwo", false), 99.0), None);
+    }
+
+    #[test]
+    fn test_lyrics_from_tags() {
+        let tags = vec![
+            ("title".to_string(), "Showtime".to_string()),
+            ("lyrics-eng".to_string(), "\u{feff}  \r\n".to_string()),
+            ("LYRICS".to_string(), "[00:01.00]Showtime\r\n".to_string()),
+        ];
+
+        assert_eq!(from_tags(&tags), Some("[00:01.00]Showtime".to_string()));
+        assert_eq!(from_tags(&vec![]), None);
+        assert_eq!(
+            without_lyrics(tags),
+            vec![("title".to_string(), "Showtime".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_sidecar_audio_file() {
+        let directory =
+            std::env::temp_dir().join(format!("judeharley-lyrics-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("Showtime.MP3"), b"").unwrap();
+        std::fs::write(directory.join("Showtime.lrc"), b"").unwrap();
+        std::fs::write(directory.join("Harlequin.txt"), b"").unwrap();
+
+        assert_eq!(
+            sidecar_audio_file(&directory.join("Showtime.lrc")),
+            Some(directory.join("Showtime.MP3"))
+        );
+        // deleted lyrics still belong to their song
+        assert_eq!(
+            sidecar_audio_file(&directory.join("Showtime.txt")),
+            Some(directory.join("Showtime.MP3"))
+        );
+        assert_eq!(sidecar_audio_file(&directory.join("Harlequin.txt")), None);
+        assert_eq!(sidecar_audio_file(&directory.join("Showtime.MP3")), None);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
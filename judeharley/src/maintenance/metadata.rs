@@ -40,6 +40,77 @@ impl MusicMetadata {
 }
 
 pub type Tags = Vec<(String, String)>;
+
+/// The raw tag keys each typed field is read from, ffmpeg passes them on as
+/// the container names them, e.g. `track` for mp3, `TRACKNUMBER` for flac and
+/// ogg, or the ID3 frame itself.
+pub const TRACK_KEYS: &[&str] = &["track", "tracknumber", "trck"];
+pub const DISC_KEYS: &[&str] = &["disc", "discnumber", "disk", "tpos"];
+pub const YEAR_KEYS: &[&str] = &["date", "year", "tdrc", "tyer", "originaldate", "tdor"];
+pub const GENRE_KEYS: &[&str] = &["genre", "tcon"];
+pub const ALBUM_ARTIST_KEYS: &[&str] = &["album_artist", "albumartist", "album artist", "tpe2"];
+
+/// Parses a track or disc position like `3`, `03` or `3/12`.
+pub fn parse_position(value: &str) -> Option<i32> {
+    let digits = value
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+
+    digits.parse().ok().filter(|position| *position > 0)
+}
+
+/// Parses the year out of a date like `2014`, `2014-05-02` or `May 2014`.
+pub fn parse_year(value: &str) -> Option<i32> {
+    value
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|digits| digits.len() == 4)
+        .and_then(|digits| digits.parse().ok())
+}
+
+fn parse_text(value: &str) -> Option<String> {
+    Some(value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// The first value for any of `keys`, ignoring case, that `parse` accepts.
+/// Earlier keys win.
+fn find<T>(tags: &Tags, keys: &[&str], parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+    keys.iter().find_map(|key| {
+        tags.iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(key))
+            .find_map(|(_, v)| parse(v))
+    })
+}
+
+/// The position in the first of `keys` that has one.
+pub fn position(tags: &Tags, keys: &[&str]) -> Option<i32> {
+    find(tags, keys, parse_position)
+}
+
+/// The fields that are stored in their own columns, normalized across
+/// formats.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypedTags {
+    pub track_number: Option<i32>,
+    pub disc_number: Option<i32>,
+    pub year: Option<i32>,
+    pub genre: Option<String>,
+    pub album_artist: Option<String>,
+}
+
+impl TypedTags {
+    pub fn from_tags(tags: &Tags) -> Self {
+        Self {
+            track_number: position(tags, TRACK_KEYS),
+            disc_number: position(tags, DISC_KEYS),
+            year: find(tags, YEAR_KEYS, parse_year),
+            genre: find(tags, GENRE_KEYS, parse_text),
+            album_artist: find(tags, ALBUM_ARTIST_KEYS, parse_text),
+        }
+    }
+}
+
 pub trait ToTags {
     fn to_tags(&self) -> Tags;
 }
@@ -65,3 +136,81 @@ impl<'a> ToTags for DictionaryRef<'a> {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_position() {
+        assert_eq!(parse_position("3"), Some(3));
+        assert_eq!(parse_position(" 03/12"), Some(3));
+        assert_eq!(parse_position("0"), None);
+        assert_eq!(parse_position("A1"), None);
+        assert_eq!(parse_position(""), None);
+    }
+
+    #[test]
+    fn test_position_ignores_case() {
+        let tags = vec![
+            ("TRACK".to_string(), "side A".to_string()),
+            ("track".to_string(), "7/13".to_string()),
+            ("disc".to_string(), "2".to_string()),
+        ];
+
+        assert_eq!(position(&tags, TRACK_KEYS), Some(7));
+        assert_eq!(position(&tags, DISC_KEYS), Some(2));
+        assert_eq!(position(&tags, &["year"]), None);
+    }
+
+    #[test]
+    fn test_parse_year() {
+        assert_eq!(parse_year("2014"), Some(2014));
+        assert_eq!(parse_year("2014-05-02T00:00:00"), Some(2014));
+        assert_eq!(parse_year("May 2014"), Some(2014));
+        assert_eq!(parse_year("14"), None);
+        assert_eq!(parse_year("20145"), None);
+    }
+
+    #[test]
+    fn test_typed_tags_across_formats() {
+        let tag = |k: &str, v: &str| (k.to_string(), v.to_string());
+        let expected = TypedTags {
+            track_number: Some(4),
+            disc_number: Some(1),
+            year: Some(2013),
+            genre: Some("Electronic".to_string()),
+            album_artist: Some("Toby Fox".to_string()),
+        };
+
+        let mp3 = vec![
+            tag("track", "4/12"),
+            tag("disc", "1/2"),
+            tag("date", "2013"),
+            tag("genre", "Electronic"),
+            tag("album_artist", "Toby Fox"),
+        ];
+        let flac = vec![
+            tag("TRACKNUMBER", "04"),
+            tag("DISCNUMBER", "1"),
+            tag("DATE", "2013-04-13"),
+            tag("GENRE", " Electronic "),
+            tag("ALBUMARTIST", "Toby Fox"),
+        ];
+        let id3_frames = vec![
+            tag("TRCK", "4"),
+            tag("TPOS", "1"),
+            tag("TYER", "2013"),
+            tag("TCON", "Electronic"),
+            tag("TPE2", "Toby Fox"),
+        ];
+
+        assert_eq!(TypedTags::from_tags(&mp3), expected);
+        assert_eq!(TypedTags::from_tags(&flac), expected);
+        assert_eq!(TypedTags::from_tags(&id3_frames), expected);
+        assert_eq!(
+            TypedTags::from_tags(&vec![tag("genre", "  ")]),
+            TypedTags::default()
+        );
+    }
+}
@@ -2,8 +2,16 @@ use crate::prelude::*;
 use std::path::{Path, PathBuf};
 
 pub mod indexing;
+pub mod listeners;
+pub mod lyrics;
 pub mod metadata;
+pub mod overrides;
+pub mod scrobbling;
 
 pub fn rewrite_music_path(path: &Path, music_path: &Path) -> Result<PathBuf> {
     Ok(Path::new("/music").join(path.strip_prefix(music_path)?))
 }
+
+pub fn rewrite_jingle_path(path: &Path, jingles_path: &Path) -> Result<PathBuf> {
+    Ok(Path::new("/jingles").join(path.strip_prefix(jingles_path)?))
+}
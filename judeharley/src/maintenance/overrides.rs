@@ -0,0 +1,201 @@
+//! Sidecar files to set many songs' metadata overrides at once.
+//!
+//! A TOML sidecar lists the songs as an array of tables:
+//!
+//! ```toml
+//! [[songs]]
+//! path = "Homestuck/Vol. 1/02 Showtime.mp3"
+//! title = "Showtime (Original Mix)"
+//!
+//! [[songs]]
+//! id = "3f5a..."
+//! hidden = true
+//! ```
+//!
+//! A CSV sidecar has a header with the same columns, empty cells are left as
+//! they are. Songs are found by their `id` (the file hash) or their `path`,
+//! relative to the music directory or starting with `/music/`.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::controllers::song_metadata_overrides::MetadataEdit;
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SidecarEntry {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub album: Option<String>,
+    #[serde(default)]
+    pub hidden: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlSidecar {
+    #[serde(default)]
+    songs: Vec<SidecarEntry>,
+}
+
+impl SidecarEntry {
+    pub fn edit(&self) -> MetadataEdit {
+        MetadataEdit {
+            title: self.title.clone(),
+            artist: self.artist.clone(),
+            album: self.album.clone(),
+            hidden: self.hidden,
+        }
+    }
+}
+
+/// Where a song in a sidecar is, as it's stored in the songs table.
+fn library_path(path: &str) -> String {
+    let path = path.trim();
+    if path.starts_with("/music/") {
+        path.to_string()
+    } else {
+        Path::new("/music")
+            .join(path.trim_start_matches('/'))
+            .display()
+            .to_string()
+    }
+}
+
+/// Parses a sidecar file, telling TOML and CSV apart by `file_name`'s
+/// extension.
+pub fn parse_sidecar(file_name: &str, contents: &str) -> Result<Vec<SidecarEntry>> {
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    let entries = match extension.as_deref() {
+        Some("toml") => {
+            toml::from_str::<TomlSidecar>(contents)
+                .map_err(|e| Error::InvalidSidecar(e.to_string()))?
+                .songs
+        }
+        Some("csv") => csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(contents.as_bytes())
+            .deserialize::<SidecarEntry>()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::InvalidSidecar(e.to_string()))?,
+        _ => {
+            return Err(Error::InvalidSidecar(
+                "sidecar files have to be .toml or .csv".to_string(),
+            ))
+        }
+    };
+
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut entry)| {
+            entry.id = entry.id.filter(|id| !id.trim().is_empty());
+            entry.path = entry
+                .path
+                .filter(|path| !path.trim().is_empty())
+                .map(|path| library_path(&path));
+            if entry.id.is_none() && entry.path.is_none() {
+                return Err(Error::InvalidSidecar(format!(
+                    "song #{} needs an `id` or a `path`",
+                    i + 1
+                )));
+            }
+
+            Ok(entry)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_toml() {
+        let entries = parse_sidecar(
+            "overrides.toml",
+            r#"
+            [[songs]]
+            path = "Homestuck/Vol. 1/02 Showtime.mp3"
+            title = "Showtime (Original Mix)"
+
+            [[songs]]
+            id = "3f5a"
+            hidden = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                SidecarEntry {
+                    path: Some("/music/Homestuck/Vol. 1/02 Showtime.mp3".to_string()),
+                    title: Some("Showtime (Original Mix)".to_string()),
+                    ..Default::default()
+                },
+                SidecarEntry {
+                    id: Some("3f5a".to_string()),
+                    hidden: Some(true),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let entries = parse_sidecar(
+            "Overrides.CSV",
+            "path,title,artist,album,hidden\n\
+             /music/Homestuck/Descend.mp3,,\"Toby Fox, Homestuck\",,\n\
+             Homestuck/Remix.mp3,,,,true\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                SidecarEntry {
+                    path: Some("/music/Homestuck/Descend.mp3".to_string()),
+                    artist: Some("Toby Fox, Homestuck".to_string()),
+                    ..Default::default()
+                },
+                SidecarEntry {
+                    path: Some("/music/Homestuck/Remix.mp3".to_string()),
+                    hidden: Some(true),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |file_name: &str, contents: &str| {
+            parse_sidecar(file_name, contents).unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            error("overrides.json", "{}"),
+            "invalid sidecar file: sidecar files have to be .toml or .csv"
+        );
+        assert_eq!(
+            error("overrides.toml", "[[songs]]\ntitle = \"Descend\""),
+            "invalid sidecar file: song #1 needs an `id` or a `path`"
+        );
+        assert!(error("overrides.csv", "path,tittle\nDescend.mp3,Descend\n").contains("tittle"));
+        assert!(error("overrides.toml", "[[songs]\n").starts_with("invalid sidecar file: "));
+    }
+}
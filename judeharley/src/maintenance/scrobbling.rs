@@ -0,0 +1,405 @@
+//! Scrobbling of radio plays to a ListenBrainz compatible server.
+//!
+//! Langley queues a listen for the station account and for every user with a
+//! linked account who was active when a song started. The queue lives in
+//! Postgres, so listens survive restarts and outages of the server, and is
+//! worked off by [`run_scrobbler`] with an exponential backoff.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, error, info, warn};
+
+use crate::controllers::scrobble_queue::NewScrobble;
+use crate::prelude::*;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.listenbrainz.org";
+
+/// How many queued listens are submitted per pass.
+const BATCH_SIZE: u64 = 100;
+
+/// Listens failing this often are dropped, with the backoff capped at six
+/// hours that is a bit more than four days.
+const MAX_ATTEMPTS: i32 = 24;
+
+const MAX_RETRY_DELAY_MINUTES: i64 = 6 * 60;
+
+#[derive(Deserialize)]
+struct ValidateToken {
+    valid: bool,
+    user_name: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct ListenBrainz {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl ListenBrainz {
+    pub fn new(base_url: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent(concat!("lumiRadio/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Returns the user name the token belongs to, or `None` if it is invalid.
+    pub async fn validate_token(&self, token: &str) -> Result<Option<String>> {
+        let response: ValidateToken = self
+            .client
+            .get(format!("{}/1/validate-token", self.base_url))
+            .header("Authorization", format!("Token {}", token))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(response.user_name.filter(|_| response.valid))
+    }
+
+    pub async fn submit(&self, token: &str, listens: &[ScrobbleQueue]) -> Result<()> {
+        self.client
+            .post(format!("{}/1/submit-listens", self.base_url))
+            .header("Authorization", format!("Token {}", token))
+            .json(&submission(listens))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+/// Builds the body of a `submit-listens` request, a single listen has to be
+/// submitted as `single`, several as `import`.
+fn submission(listens: &[ScrobbleQueue]) -> serde_json::Value {
+    let payload = listens
+        .iter()
+        .map(|listen| {
+            json!({
+                "listened_at": listen.listened_at.and_utc().timestamp(),
+                "track_metadata": {
+                    "artist_name": listen.artist,
+                    "track_name": listen.title,
+                    "release_name": listen.album,
+                    "additional_info": {
+                        "duration_ms": (listen.duration * 1000.0) as i64,
+                        "media_player": "lumiRadio",
+                        "submission_client": "lumiRadio",
+                        "submission_client_version": env!("CARGO_PKG_VERSION"),
+                    },
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "listen_type": if listens.len() == 1 { "single" } else { "import" },
+        "payload": payload,
+    })
+}
+
+/// Whether retrying a failed submission can't help, i.e. the server rejected
+/// the listens or the token. Rate limits and server errors are retried.
+fn is_permanent(e: &JudeHarleyError) -> bool {
+    match e {
+        JudeHarleyError::Reqwest(e) => e.status().is_some_and(|status| {
+            status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+        }),
+        _ => false,
+    }
+}
+
+/// How long to wait before the next attempt after `attempts` failed ones.
+fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.clamp(1, 10) - 1;
+
+    chrono::Duration::minutes((1i64 << exponent).min(MAX_RETRY_DELAY_MINUTES))
+}
+
+/// Queues a listen of `song` for every linked user who was active at
+/// `listened_at`, and for the station account if `station` is set. Returns
+/// the number of listens queued.
+pub async fn enqueue_play(
+    song: &Songs,
+    listened_at: NaiveDateTime,
+    station: bool,
+    db: &DatabaseConnection,
+) -> Result<usize> {
+    let mut user_ids = ListenbrainzAccounts::get_active(listened_at, db)
+        .await?
+        .into_iter()
+        .map(|account| Some(account.user_id))
+        .collect::<Vec<_>>();
+    if station {
+        user_ids.push(None);
+    }
+
+    let queued = user_ids.len();
+    ScrobbleQueue::insert_many(
+        user_ids
+            .into_iter()
+            .map(|user_id| NewScrobble {
+                user_id,
+                title: song.title.clone(),
+                artist: song.artist.clone(),
+                album: song.album.clone(),
+                duration: song.duration,
+                listened_at,
+            })
+            .collect(),
+        db,
+    )
+    .await?;
+
+    Ok(queued)
+}
+
+/// Submits the listens that are due, returns how many were submitted.
+pub async fn process_queue(
+    listenbrainz: &ListenBrainz,
+    station_token: Option<&str>,
+    db: &DatabaseConnection,
+) -> Result<usize> {
+    let due = ScrobbleQueue::get_due(BATCH_SIZE, db).await?;
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    let mut by_user: BTreeMap<Option<i64>, Vec<ScrobbleQueue>> = BTreeMap::new();
+    for listen in due {
+        by_user.entry(listen.user_id).or_default().push(listen);
+    }
+
+    let tokens = ListenbrainzAccounts::get_many(by_user.keys().flatten().copied().collect(), db)
+        .await?
+        .into_iter()
+        .map(|account| (account.user_id, account.token))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut submitted = 0;
+    for (user_id, listens) in by_user {
+        let ids = listens.iter().map(|l| l.id).collect::<Vec<_>>();
+        let token = match user_id {
+            Some(user_id) => tokens.get(&user_id).map(String::as_str),
+            None => station_token,
+        };
+        let Some(token) = token else {
+            debug!(
+                ?user_id,
+                listens = ids.len(),
+                "no token to scrobble with, dropping listens"
+            );
+            ScrobbleQueue::delete_many(ids, db).await?;
+            continue;
+        };
+
+        match listenbrainz.submit(token, &listens).await {
+            Ok(()) => {
+                submitted += ids.len();
+                ScrobbleQueue::delete_many(ids, db).await?;
+            }
+            Err(e) if is_permanent(&e) => {
+                warn!(?user_id, listens = ids.len(), error = %e, "scrobbles were rejected, dropping them");
+                ScrobbleQueue::delete_many(ids, db).await?;
+            }
+            Err(e) => {
+                warn!(?user_id, listens = ids.len(), error = %e, "failed to scrobble, will retry");
+                let now = chrono::Utc::now().naive_utc();
+                for listen in listens {
+                    if listen.attempts + 1 >= MAX_ATTEMPTS {
+                        warn!(scrobble_id = listen.id, "giving up on scrobble");
+                        ScrobbleQueue::delete_many(vec![listen.id], db).await?;
+                        continue;
+                    }
+
+                    let next_attempt_at = now + retry_delay(listen.attempts + 1);
+                    listen.postpone(next_attempt_at, e.to_string(), db).await?;
+                }
+            }
+        }
+    }
+
+    Ok(submitted)
+}
+
+/// Works off the scrobble queue forever, failed passes are logged and
+/// retried on the next tick.
+pub async fn run_scrobbler(
+    db: DatabaseConnection,
+    listenbrainz: ListenBrainz,
+    station_token: Option<String>,
+    interval: Duration,
+) -> Result<()> {
+    info!(
+        base_url = %listenbrainz.base_url,
+        station = station_token.is_some(),
+        "scrobbling plays"
+    );
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+
+        match process_queue(&listenbrainz, station_token.as_deref(), &db).await {
+            Ok(0) => {}
+            Ok(submitted) => debug!(submitted, "scrobbled listens"),
+            Err(e) => error!(error = %e, "failed to process scrobble queue"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::stand_in;
+
+    fn listen(id: i32, title: &str) -> ScrobbleQueue {
+        ScrobbleQueue {
+            id,
+            user_id: Some(1),
+            title: title.to_string(),
+            artist: "Artist".to_string(),
+            album: "Album".to_string(),
+            duration: 180.5,
+            listened_at: chrono::DateTime::from_timestamp(1_760_788_800, 0)
+                .unwrap()
+                .naive_utc(),
+            attempts: 0,
+            next_attempt_at: chrono::DateTime::from_timestamp(1_760_788_800, 0)
+                .unwrap()
+                .naive_utc(),
+            last_error: None,
+        }
+    }
+
+    fn request_body(request: &str) -> serde_json::Value {
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn submits_a_single_listen() {
+        let (base_url, request) = stand_in("200 OK", r#"{"status":"ok"}"#).await;
+        let listenbrainz = ListenBrainz::new(&format!("{base_url}/")).unwrap();
+
+        listenbrainz
+            .submit("secret-token", &[listen(1, "Song")])
+            .await
+            .unwrap();
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("POST /1/submit-listens HTTP/1.1"));
+        assert!(request
+            .to_lowercase()
+            .contains("authorization: token secret-token"));
+
+        let body = request_body(&request);
+        assert_eq!(body["listen_type"], "single");
+        assert_eq!(body["payload"][0]["listened_at"], 1_760_788_800);
+        assert_eq!(body["payload"][0]["track_metadata"]["track_name"], "Song");
+        assert_eq!(
+            body["payload"][0]["track_metadata"]["artist_name"],
+            "Artist"
+        );
+        assert_eq!(
+            body["payload"][0]["track_metadata"]["release_name"],
+            "Album"
+        );
+        assert_eq!(
+            body["payload"][0]["track_metadata"]["additional_info"]["duration_ms"],
+            180_500
+        );
+    }
+
+    #[tokio::test]
+    async fn imports_several_listens() {
+        let (base_url, request) = stand_in("200 OK", r#"{"status":"ok"}"#).await;
+        let listenbrainz = ListenBrainz::new(&base_url).unwrap();
+
+        listenbrainz
+            .submit("secret-token", &[listen(1, "First"), listen(2, "Second")])
+            .await
+            .unwrap();
+
+        let body = request_body(&request.await.unwrap());
+        assert_eq!(body["listen_type"], "import");
+        assert_eq!(body["payload"].as_array().unwrap().len(), 2);
+        assert_eq!(body["payload"][1]["track_metadata"]["track_name"], "Second");
+    }
+
+    #[tokio::test]
+    async fn classifies_failed_submissions() {
+        for (status, permanent) in [
+            ("400 Bad Request", true),
+            ("401 Unauthorized", true),
+            ("429 Too Many Requests", false),
+            ("503 Service Unavailable", false),
+        ] {
+            let (base_url, _request) = stand_in(status, r#"{"code":0,"error":"nope"}"#).await;
+            let listenbrainz = ListenBrainz::new(&base_url).unwrap();
+
+            let e = listenbrainz
+                .submit("secret-token", &[listen(1, "Song")])
+                .await
+                .unwrap_err();
+            assert_eq!(is_permanent(&e), permanent, "{status}");
+        }
+
+        // nothing listening at all
+        let listenbrainz = ListenBrainz::new("http://127.0.0.1:1").unwrap();
+        let e = listenbrainz
+            .submit("secret-token", &[listen(1, "Song")])
+            .await
+            .unwrap_err();
+        assert!(!is_permanent(&e));
+    }
+
+    #[tokio::test]
+    async fn validates_tokens() {
+        let (base_url, request) = stand_in(
+            "200 OK",
+            r#"{"code":200,"message":"Token valid.","valid":true,"user_name":"lumi"}"#,
+        )
+        .await;
+        let listenbrainz = ListenBrainz::new(&base_url).unwrap();
+        assert_eq!(
+            listenbrainz.validate_token("good").await.unwrap(),
+            Some("lumi".to_string())
+        );
+        assert!(request
+            .await
+            .unwrap()
+            .starts_with("GET /1/validate-token HTTP/1.1"));
+
+        let (base_url, _request) = stand_in(
+            "200 OK",
+            r#"{"code":200,"message":"Token invalid.","valid":false}"#,
+        )
+        .await;
+        let listenbrainz = ListenBrainz::new(&base_url).unwrap();
+        assert_eq!(listenbrainz.validate_token("bad").await.unwrap(), None);
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        assert_eq!(retry_delay(1), chrono::Duration::minutes(1));
+        assert_eq!(retry_delay(2), chrono::Duration::minutes(2));
+        assert_eq!(retry_delay(5), chrono::Duration::minutes(16));
+        assert_eq!(retry_delay(9), chrono::Duration::minutes(256));
+        assert_eq!(retry_delay(10), chrono::Duration::hours(6));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), chrono::Duration::hours(6));
+    }
+}
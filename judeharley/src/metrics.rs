@@ -0,0 +1,146 @@
+//! Prometheus metrics shared by all lumiRadio services.
+//!
+//! Every service registers the same metrics, each one only moves the ones that
+//! apply to it. [`gather`] renders them for a `/metrics` endpoint.
+
+use lazy_static::lazy_static;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+
+use crate::prelude::JudeHarleyError;
+
+/// `Content-Type` of the response produced by [`gather`].
+pub const CONTENT_TYPE: &str = prometheus::TEXT_FORMAT;
+
+lazy_static! {
+    pub static ref REGISTRY: Registry =
+        Registry::new_custom(Some("lumiradio".to_string()), None).expect("metrics prefix is valid");
+    pub static ref COMMANDS_INVOKED: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("commands_invoked_total", "Discord commands invoked"),
+        &["command"],
+    ));
+    pub static ref COMMANDS_FAILED: IntCounterVec = register(IntCounterVec::new(
+        Opts::new(
+            "commands_failed_total",
+            "Discord commands that failed or panicked"
+        ),
+        &["command"],
+    ));
+    pub static ref LIQUIDSOAP_LATENCY: Histogram = register(Histogram::with_opts(
+        HistogramOpts::new(
+            "liquidsoap_request_duration_seconds",
+            "Round-trip time of Liquidsoap socket commands",
+        )
+        .buckets(vec![
+            0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0
+        ]),
+    ));
+    pub static ref LIQUIDSOAP_RECONNECTS: IntCounter = register(IntCounter::new(
+        "liquidsoap_reconnects_total",
+        "Reconnects to the Liquidsoap socket",
+    ));
+    pub static ref ERRORS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("errors_total", "Errors by the system they came from"),
+        &["kind"],
+    ));
+    pub static ref SONGS_PLAYED: IntCounter = register(IntCounter::new(
+        "songs_played_total",
+        "Songs reported as played by Liquidsoap",
+    ));
+    pub static ref SONG_REQUESTS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new(
+            "song_requests_total",
+            "Songs pushed onto a Liquidsoap queue"
+        ),
+        &["queue"],
+    ));
+    pub static ref INDEXER_RUNS: IntCounter = register(IntCounter::new(
+        "indexer_runs_total",
+        "Full indexing runs of the music library",
+    ));
+    pub static ref INDEXER_RUN_FAILURES: IntCounter = register(IntCounter::new(
+        "indexer_run_failures_total",
+        "Full indexing runs that failed",
+    ));
+    pub static ref INDEXER_FILE_FAILURES: IntCounter = register(IntCounter::new(
+        "indexer_file_failures_total",
+        "Files that failed to be indexed",
+    ));
+    pub static ref WATCHER_EVENTS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new(
+            "watcher_events_total",
+            "File system events handled by the watcher"
+        ),
+        &["kind"],
+    ));
+}
+
+fn register<M>(metric: prometheus::Result<M>) -> M
+where
+    M: prometheus::core::Collector + Clone + 'static,
+{
+    let metric = metric.expect("metric options are valid");
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("metric is registered only once");
+
+    metric
+}
+
+/// Registers all metrics up front so they show up before they are first used.
+pub fn init() {
+    lazy_static::initialize(&COMMANDS_INVOKED);
+    lazy_static::initialize(&COMMANDS_FAILED);
+    lazy_static::initialize(&LIQUIDSOAP_LATENCY);
+    lazy_static::initialize(&LIQUIDSOAP_RECONNECTS);
+    lazy_static::initialize(&ERRORS);
+    lazy_static::initialize(&SONGS_PLAYED);
+    lazy_static::initialize(&SONG_REQUESTS);
+    lazy_static::initialize(&INDEXER_RUNS);
+    lazy_static::initialize(&INDEXER_RUN_FAILURES);
+    lazy_static::initialize(&INDEXER_FILE_FAILURES);
+    lazy_static::initialize(&WATCHER_EVENTS);
+}
+
+/// Counts an error by the system it came from.
+pub fn record_error(error: &JudeHarleyError) {
+    let kind = match error {
+        JudeHarleyError::SeaOrmDb(_)
+        | JudeHarleyError::SeaOrmSql(_)
+        | JudeHarleyError::SeaOrmRuntime(_)
+        | JudeHarleyError::SeaOrmConn(_) => "database",
+        JudeHarleyError::Redis(_) => "redis",
+        _ => "other",
+    };
+
+    ERRORS.with_label_values(&[kind]).inc();
+}
+
+/// Renders all metrics in the Prometheus text format.
+pub fn gather() -> String {
+    let mut buffer = vec![];
+    TextEncoder::new()
+        .encode(&REGISTRY.gather(), &mut buffer)
+        .expect("metrics can be encoded");
+
+    String::from_utf8(buffer).expect("metrics are valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gather() {
+        init();
+        assert!(gather().contains("lumiradio_indexer_runs_total 0"));
+
+        SONGS_PLAYED.inc();
+        COMMANDS_INVOKED.with_label_values(&["song request"]).inc();
+
+        let metrics = gather();
+        assert!(metrics.contains("lumiradio_songs_played_total"));
+        assert!(metrics.contains(r#"lumiradio_commands_invoked_total{command="song request"}"#));
+    }
+}
@@ -0,0 +1,87 @@
+//! The now-playing event published by langley whenever a song starts.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::communication::QueueItem;
+use crate::prelude::*;
+
+/// Redis channel langley publishes a [`NowPlaying`] JSON payload to.
+pub const NOW_PLAYING_CHANNEL: &str = "byers:now_playing";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NowPlayingSong {
+    pub id: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration: f64,
+    pub bitrate: i32,
+}
+
+impl From<Songs> for NowPlayingSong {
+    fn from(value: Songs) -> Self {
+        Self {
+            id: value.file_hash,
+            title: value.title,
+            artist: value.artist,
+            album: value.album,
+            duration: value.duration,
+            bitrate: value.bitrate,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QueuedSong {
+    pub title: String,
+    pub artist: String,
+    pub album: Option<String>,
+    pub queue: String,
+}
+
+impl From<QueueItem> for QueuedSong {
+    fn from(value: QueueItem) -> Self {
+        Self {
+            title: value.title,
+            artist: value.artist,
+            album: value.album,
+            queue: value.queue,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NowPlaying {
+    pub song: NowPlayingSong,
+    pub started_at: DateTime<Utc>,
+    pub duration: f64,
+    /// Songs queued up after this one, langley leaves this empty since it
+    /// can't talk to Liquidsoap.
+    #[serde(default)]
+    pub queue: Vec<QueuedSong>,
+}
+
+impl NowPlaying {
+    pub fn new(song: Songs, started_at: DateTime<Utc>) -> Self {
+        Self {
+            duration: song.duration,
+            song: song.into(),
+            started_at,
+            queue: vec![],
+        }
+    }
+
+    /// Finds out what's playing from the play history, for when no event
+    /// has been received yet.
+    pub async fn from_history(db: &sea_orm::DatabaseConnection) -> Result<Option<Self>> {
+        let Some(played) = PlayedSongs::get_latest(db).await? else {
+            return Ok(None);
+        };
+        let Some(song) = Songs::get_by_hash(&played.song_id, db).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self::new(song, played.played_at.and_utc())))
+    }
+}
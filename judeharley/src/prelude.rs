@@ -10,11 +10,20 @@ pub(crate) type Result<T> = std::result::Result<T, Error>;
 pub use crate::custom_entities::songs::Model as Songs;
 pub use crate::entities::{
     cans::Model as Cans, connected_youtube_accounts::Model as ConnectedYoutubeAccounts,
-    favourite_songs::Model as FavouriteSongs, played_songs::Model as PlayedSongs,
-    server_channel_config::Model as ServerChannelConfig, server_config::Model as ServerConfig,
-    server_role_config::Model as ServerRoleConfig, slcb_currency::Model as SlcbCurrency,
-    slcb_rank::Model as SlcbRank, song_requests::Model as SongRequests, song_tags::Model as Tags,
-    users::Model as Users,
+    favourite_songs::Model as FavouriteSongs, feed_tokens::Model as FeedTokens,
+    indexed_albums::Model as IndexedAlbums, indexer_runs::Model as IndexerRuns,
+    jingles::Model as Jingles, listenbrainz_accounts::Model as ListenbrainzAccounts,
+    listener_samples::Model as ListenerSamples, live_djs::Model as LiveDjs,
+    live_sessions::Model as LiveSessions, played_songs::Model as PlayedSongs,
+    queued_albums::Model as QueuedAlbums, schedule_blocks::Model as ScheduleBlocks,
+    scrobble_queue::Model as ScrobbleQueue, server_channel_config::Model as ServerChannelConfig,
+    server_config::Model as ServerConfig, server_role_config::Model as ServerRoleConfig,
+    slcb_currency::Model as SlcbCurrency, slcb_rank::Model as SlcbRank,
+    song_lyrics::Model as SongLyrics, song_metadata_edits::Model as SongMetadataEdits,
+    song_metadata_overrides::Model as SongMetadataOverrides, song_reports::Model as SongReports,
+    song_requests::Model as SongRequests, song_tags::Model as Tags,
+    unknown_plays::Model as UnknownPlays, users::Model as Users,
+    webhook_deliveries::Model as WebhookDeliveries, webhooks::Model as Webhooks,
 };
 
 pub static SUPPORTED_AUDIO_FORMATS: [&str; 4] = ["mp3", "flac", "ogg", "wav"];
@@ -48,6 +57,12 @@ pub enum JudeHarleyError {
     Id3(#[from] id3::Error),
     #[error(transparent)]
     AudioTags(#[from] audiotags::Error),
+    #[error(transparent)]
+    PasswordHash(#[from] argon2::password_hash::Error),
+    #[error("invalid search: {0}")]
+    InvalidSearch(#[from] crate::search::ParseError),
+    #[error("invalid sidecar file: {0}")]
+    InvalidSidecar(String),
 }
 
 pub trait DiscordTimestamp {
@@ -153,6 +168,20 @@ where
     }
 }
 
+/// Formats a number of seconds as e.g. `3d 4h 12m`.
+pub fn format_duration(seconds: u64) -> String {
+    let days = seconds / 86_400;
+    let hours = seconds % 86_400 / 3_600;
+    let minutes = seconds % 3_600 / 60;
+
+    match (days, hours) {
+        (0, 0) if minutes == 0 => format!("{}s", seconds),
+        (0, 0) => format!("{}m {}s", minutes, seconds % 60),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h {}m", days, hours, minutes),
+    }
+}
+
 pub struct W<T>(pub T);
 
 pub trait Wrappable {
@@ -202,3 +231,19 @@ impl FromRedis for W<chrono::NaiveDateTime> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(125), "2m 5s");
+        assert_eq!(format_duration(7_260), "2h 1m");
+        assert_eq!(
+            format_duration(3 * 86_400 + 4 * 3_600 + 12 * 60),
+            "3d 4h 12m"
+        );
+    }
+}
@@ -0,0 +1,523 @@
+//! The rotation picks the song Liquidsoap plays next when nobody requested
+//! anything.
+//!
+//! Songs played within the repeat window are left out, as are songs by the
+//! artists or from the albums of the last few plays. Of the remaining songs,
+//! ones that are favourited or requested a lot are more likely to be picked.
+//! If the rules leave nothing to pick from, they are relaxed one by one, so
+//! the stream never runs dry because of them. Only the optional cap on how
+//! much music the rotation plays within the repeat window is never relaxed.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime};
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+use sea_orm::DatabaseConnection;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone)]
+pub struct RotationRules {
+    /// How long a song isn't played again after it was played.
+    pub repeat_window: Duration,
+    /// How many of the last plays' artists may not be picked.
+    pub artist_separation: usize,
+    /// How many of the last plays' albums may not be picked.
+    pub album_separation: usize,
+    /// How much every favourite (on a logarithmic scale) adds to a song's weight.
+    pub favourite_weight: f64,
+    /// How much every request (on a logarithmic scale) adds to a song's weight.
+    pub request_weight: f64,
+    /// How many seconds of music the rotation plays within the repeat window
+    /// at most, if there is a limit. Songs that would go over it aren't
+    /// picked, no matter how far the other rules are relaxed.
+    pub max_duration: Option<f64>,
+}
+
+impl Default for RotationRules {
+    fn default() -> Self {
+        Self {
+            repeat_window: Duration::hours(6),
+            artist_separation: 3,
+            album_separation: 5,
+            favourite_weight: 0.5,
+            request_weight: 0.25,
+            max_duration: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub song: Songs,
+    pub favourites: i64,
+    pub requests: i64,
+}
+
+impl Candidate {
+    /// Pairs the songs with their favourite and request counts, keyed by the
+    /// songs' hashes.
+    pub fn with_counts(
+        songs: Vec<Songs>,
+        favourites: &HashMap<String, i64>,
+        requests: &HashMap<String, i64>,
+    ) -> Vec<Self> {
+        songs
+            .into_iter()
+            .map(|song| Self {
+                favourites: favourites.get(&song.file_hash).copied().unwrap_or(0),
+                requests: requests.get(&song.file_hash).copied().unwrap_or(0),
+                song,
+            })
+            .collect()
+    }
+
+    fn weight(&self, rules: &RotationRules) -> f64 {
+        1.0 + rules.favourite_weight * (self.favourites.max(0) as f64).ln_1p()
+            + rules.request_weight * (self.requests.max(0) as f64).ln_1p()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecentPlay {
+    pub song_id: String,
+    pub artist: String,
+    pub album: String,
+    /// Length of the song in seconds.
+    pub duration: f64,
+    pub played_at: NaiveDateTime,
+}
+
+/// Which rules a pick has to follow, from strictest to most relaxed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Strictness {
+    All,
+    WithoutAlbumSeparation,
+    WithoutSeparation,
+    /// Only avoids the song that is playing right now.
+    NotTheLastSong,
+}
+
+const STRICTNESS_LEVELS: [Strictness; 4] = [
+    Strictness::All,
+    Strictness::WithoutAlbumSeparation,
+    Strictness::WithoutSeparation,
+    Strictness::NotTheLastSong,
+];
+
+fn same_name(a: &str, b: &str) -> bool {
+    // unknown artists and albums don't count as the same one
+    let (a, b) = (a.trim(), b.trim());
+    !a.is_empty() && a.eq_ignore_ascii_case(b)
+}
+
+/// Everything the rotation needs to know to pick a song.
+#[derive(Debug, Clone, Default)]
+pub struct Rotation {
+    pub candidates: Vec<Candidate>,
+    /// The latest plays, newest first.
+    pub recent: Vec<RecentPlay>,
+}
+
+impl Rotation {
+    /// Loads the songs the rules allow, the songs of the recent plays in case
+    /// the rules have to be relaxed, and how often they were favourited and
+    /// requested.
+    pub async fn load(
+        rules: &RotationRules,
+        now: NaiveDateTime,
+        db: &DatabaseConnection,
+    ) -> Result<Self> {
+        let separation = rules.artist_separation.max(rules.album_separation).max(1);
+        let plays =
+            PlayedSongs::get_recent(now - rules.repeat_window, separation as u64, db).await?;
+        let mut played = plays
+            .iter()
+            .map(|play| play.song_id.clone())
+            .collect::<Vec<_>>();
+        played.sort();
+        played.dedup();
+        let played_songs = Songs::get_by_hashes(played.clone(), db).await?;
+
+        let by_hash = played_songs
+            .iter()
+            .map(|song| (song.file_hash.as_str(), song))
+            .collect::<HashMap<_, _>>();
+        let recent = plays
+            .into_iter()
+            .map(|play| {
+                let song = by_hash.get(play.song_id.as_str());
+                RecentPlay {
+                    artist: song.map(|s| s.artist.clone()).unwrap_or_default(),
+                    album: song.map(|s| s.album.clone()).unwrap_or_default(),
+                    duration: song.map(|s| s.duration).unwrap_or_default(),
+                    song_id: play.song_id,
+                    played_at: play.played_at,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut rotation = Self {
+            candidates: vec![],
+            recent,
+        };
+        let remaining = rotation.remaining_duration(rules, now);
+        let mut songs = Songs::get_rotation_candidates(played, remaining, db).await?;
+        songs.extend(played_songs.into_iter().filter(|song| !song.hidden));
+
+        let favourites = FavouriteSongs::count_by_song(db).await?;
+        let requests = SongRequests::count_by_song(db).await?;
+        rotation.candidates = Candidate::with_counts(songs, &favourites, &requests);
+
+        Ok(rotation)
+    }
+
+    /// How many seconds of music the rotation may still play within the
+    /// repeat window, or `None` if there is no limit.
+    fn remaining_duration(&self, rules: &RotationRules, now: NaiveDateTime) -> Option<f64> {
+        let max_duration = rules.max_duration?;
+        let repeat_since = now - rules.repeat_window;
+        let played = self
+            .recent
+            .iter()
+            .filter(|play| play.played_at >= repeat_since)
+            .map(|play| play.duration)
+            .sum::<f64>();
+
+        Some(max_duration - played)
+    }
+
+    fn allows(
+        &self,
+        candidate: &Candidate,
+        strictness: Strictness,
+        remaining: Option<f64>,
+        rules: &RotationRules,
+        now: NaiveDateTime,
+    ) -> bool {
+        let song = &candidate.song;
+        if remaining.is_some_and(|remaining| song.duration > remaining) {
+            return false;
+        }
+        if let Some(last) = self.recent.first() {
+            if last.song_id == song.file_hash {
+                return false;
+            }
+        }
+        if strictness == Strictness::NotTheLastSong {
+            return true;
+        }
+
+        let repeat_since = now - rules.repeat_window;
+        if self
+            .recent
+            .iter()
+            .any(|play| play.song_id == song.file_hash && play.played_at >= repeat_since)
+        {
+            return false;
+        }
+        if strictness == Strictness::WithoutSeparation {
+            return true;
+        }
+
+        if self
+            .recent
+            .iter()
+            .take(rules.artist_separation)
+            .any(|play| same_name(&play.artist, &song.artist))
+        {
+            return false;
+        }
+        if strictness == Strictness::WithoutAlbumSeparation {
+            return true;
+        }
+
+        !self
+            .recent
+            .iter()
+            .take(rules.album_separation)
+            .any(|play| same_name(&play.album, &song.album))
+    }
+
+    /// Only rotates the songs `include` returns `true` for. If that would
+    /// leave nothing to rotate, the rotation stays as it is and `false` is
+    /// returned.
+    pub fn restrict(&mut self, include: impl Fn(&Songs) -> bool) -> bool {
+        if !self
+            .candidates
+            .iter()
+            .any(|candidate| include(&candidate.song))
+        {
+            return false;
+        }
+
+        self.candidates.retain(|candidate| include(&candidate.song));
+        true
+    }
+
+    /// Picks the next song following as many of the rules as possible, or
+    /// `None` if there are no songs at all or none fit into the duration cap.
+    pub fn pick<R: Rng + ?Sized>(
+        &self,
+        rules: &RotationRules,
+        now: NaiveDateTime,
+        rng: &mut R,
+    ) -> Option<&Songs> {
+        let remaining = self.remaining_duration(rules, now);
+        for strictness in STRICTNESS_LEVELS {
+            let eligible = self
+                .candidates
+                .iter()
+                .filter(|candidate| self.allows(candidate, strictness, remaining, rules, now))
+                .collect::<Vec<_>>();
+            if eligible.is_empty() {
+                continue;
+            }
+
+            let weights = eligible.iter().map(|candidate| candidate.weight(rules));
+            let index = WeightedIndex::new(weights)
+                .map(|distribution| distribution.sample(rng))
+                .unwrap_or_else(|_| rng.gen_range(0..eligible.len()));
+
+            return Some(&eligible[index].song);
+        }
+
+        // only the song that is playing right now is left, if it still fits
+        self.candidates
+            .iter()
+            .find(|candidate| {
+                remaining.is_none_or(|remaining| candidate.song.duration <= remaining)
+            })
+            .map(|candidate| &candidate.song)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    fn candidate(id: &str, artist: &str, album: &str, favourites: i64) -> Candidate {
+        Candidate {
+            song: Songs {
+                file_path: format!("/music/{id}.mp3"),
+                title: id.to_string(),
+                artist: artist.to_string(),
+                album: album.to_string(),
+                played: 0,
+                requested: 0,
+                tsvector: None,
+                duration: 180.0,
+                file_hash: id.to_string(),
+                bitrate: 320,
+                track_number: None,
+                disc_number: None,
+                year: None,
+                genre: None,
+                album_artist: None,
+                hidden: false,
+            },
+            favourites,
+            requests: 0,
+        }
+    }
+
+    fn play(candidate: &Candidate, minutes_ago: i64) -> RecentPlay {
+        RecentPlay {
+            song_id: candidate.song.file_hash.clone(),
+            artist: candidate.song.artist.clone(),
+            album: candidate.song.album.clone(),
+            duration: candidate.song.duration,
+            played_at: now() - Duration::minutes(minutes_ago),
+        }
+    }
+
+    fn picks(rotation: &Rotation, rules: &RotationRules, times: usize) -> HashMap<String, usize> {
+        let mut rng = StdRng::seed_from_u64(413);
+        let mut picks = HashMap::new();
+        for _ in 0..times {
+            let song = rotation.pick(rules, now(), &mut rng).unwrap();
+            *picks.entry(song.file_hash.clone()).or_default() += 1;
+        }
+
+        picks
+    }
+
+    #[test]
+    fn test_avoids_recently_played_songs() {
+        let a = candidate("a", "Toby Fox", "Hiveswap", 0);
+        let b = candidate("b", "Toby Fox", "Hiveswap", 0);
+        let c = candidate("c", "Toby Fox", "Hiveswap", 0);
+        let rules = RotationRules {
+            artist_separation: 0,
+            album_separation: 0,
+            ..Default::default()
+        };
+        let rotation = Rotation {
+            recent: vec![play(&b, 200), play(&a, 300), play(&c, 600)],
+            candidates: vec![a, b, c],
+        };
+
+        // a is within the six hour window, c isn't any more
+        assert_eq!(
+            picks(&rotation, &rules, 50).keys().collect::<Vec<_>>(),
+            ["c"]
+        );
+    }
+
+    #[test]
+    fn test_separates_artists_and_albums() {
+        let a = candidate("a", "Toby Fox", "Homestuck Vol. 5", 0);
+        let b = candidate("b", "toby fox", "Homestuck Vol. 6", 0);
+        let c = candidate("c", "Clark Powell", "Homestuck Vol. 5", 0);
+        let d = candidate("d", "Clark Powell", "Alternia", 0);
+        let rules = RotationRules {
+            artist_separation: 1,
+            album_separation: 2,
+            ..Default::default()
+        };
+        let rotation = Rotation {
+            recent: vec![play(&a, 3)],
+            candidates: vec![a, b, c, d],
+        };
+
+        assert_eq!(
+            picks(&rotation, &rules, 50).keys().collect::<Vec<_>>(),
+            ["d"]
+        );
+    }
+
+    #[test]
+    fn test_relaxes_rules_instead_of_running_dry() {
+        let a = candidate("a", "Toby Fox", "Hiveswap", 0);
+        let b = candidate("b", "Toby Fox", "Hiveswap", 0);
+        let rotation = Rotation {
+            recent: vec![play(&a, 3), play(&b, 6)],
+            candidates: vec![a, b],
+        };
+
+        let counts = picks(&rotation, &RotationRules::default(), 50);
+        assert_eq!(counts.keys().collect::<Vec<_>>(), ["b"]);
+
+        let only = candidate("a", "Toby Fox", "Hiveswap", 0);
+        let rotation = Rotation {
+            recent: vec![play(&only, 3)],
+            candidates: vec![only],
+        };
+        let mut rng = StdRng::seed_from_u64(413);
+        let song = rotation.pick(&RotationRules::default(), now(), &mut rng);
+        assert_eq!(song.map(|s| s.file_hash.as_str()), Some("a"));
+
+        let rotation = Rotation::default();
+        assert!(rotation
+            .pick(&RotationRules::default(), now(), &mut rng)
+            .is_none());
+    }
+
+    #[test]
+    fn test_caps_duration() {
+        let a = candidate("a", "Toby Fox", "Hiveswap", 0);
+        let b = candidate("b", "Clark Powell", "Alternia", 0);
+        let mut long = candidate("long", "Toby Fox", "Hiveswap", 0);
+        long.song.duration = 300.0;
+        let short = candidate("short", "Toby Fox", "Hiveswap", 0);
+        let rules = RotationRules {
+            artist_separation: 0,
+            album_separation: 0,
+            max_duration: Some(600.0),
+            ..Default::default()
+        };
+
+        // 360 of the 600 seconds are used up, only the short song still fits
+        let rotation = Rotation {
+            recent: vec![play(&a, 3), play(&b, 6)],
+            candidates: vec![a.clone(), b.clone(), long.clone(), short.clone()],
+        };
+        assert_eq!(
+            picks(&rotation, &rules, 50).keys().collect::<Vec<_>>(),
+            ["short"]
+        );
+
+        // plays from before the window don't count
+        let rotation = Rotation {
+            recent: vec![play(&a, 3), play(&b, 400)],
+            candidates: vec![long.clone()],
+        };
+        assert_eq!(
+            picks(&rotation, &rules, 50).keys().collect::<Vec<_>>(),
+            ["long"]
+        );
+
+        // the cap isn't relaxed when nothing fits any more
+        let rotation = Rotation {
+            recent: vec![play(&short, 3), play(&a, 6), play(&b, 9)],
+            candidates: vec![a, b, long, short],
+        };
+        let mut rng = StdRng::seed_from_u64(413);
+        assert!(rotation.pick(&rules, now(), &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_counts_requests_from_request_rows() {
+        let songs = vec![
+            candidate("plain", "Toby Fox", "Hiveswap", 0).song,
+            candidate("requested", "Toby Fox", "Hiveswap", 0).song,
+        ];
+        let rows = (1..=3)
+            .map(|id| SongRequests {
+                id,
+                song_id: "requested".to_string(),
+                user_id: id as i64,
+                created_at: now(),
+            })
+            .collect::<Vec<_>>();
+        // what `SongRequests::count_by_song` groups the rows into
+        let mut requests = HashMap::new();
+        for row in &rows {
+            *requests.entry(row.song_id.clone()).or_default() += 1;
+        }
+
+        let candidates = Candidate::with_counts(songs, &HashMap::new(), &requests);
+        assert_eq!(candidates[0].requests, 0);
+        assert_eq!(candidates[1].requests, 3);
+        // the songs' own, never updated counter plays no part
+        assert_eq!(candidates[1].song.requested, 0);
+
+        let rules = RotationRules::default();
+        assert!(candidates[1].weight(&rules) > candidates[0].weight(&rules));
+    }
+
+    #[test]
+    fn test_weights_favourites_and_requests() {
+        let plain = candidate("plain", "Toby Fox", "Hiveswap", 0);
+        let favourite = candidate("favourite", "Toby Fox", "Hiveswap", 50);
+        let mut requested = candidate("requested", "Toby Fox", "Hiveswap", 0);
+        requested.requests = 50;
+        let rules = RotationRules::default();
+        assert!(favourite.weight(&rules) > requested.weight(&rules));
+        assert!(requested.weight(&rules) > plain.weight(&rules));
+
+        let rotation = Rotation {
+            recent: vec![],
+            candidates: vec![plain, favourite, requested],
+        };
+        let counts = picks(&rotation, &rules, 3000);
+        assert!(counts["favourite"] > counts["requested"]);
+        assert!(counts["requested"] > counts["plain"]);
+
+        // the same seed always picks the same songs
+        assert_eq!(counts, picks(&rotation, &rules, 3000));
+    }
+}
@@ -0,0 +1,362 @@
+//! Scheduled programming.
+//!
+//! A schedule block takes over the rotation whenever its recurrence matches,
+//! e.g. `0 20 * * fri` for Fridays at 20:00 UTC, and lasts for its duration.
+//! While it runs, only songs from its albums or matching its filter are
+//! rotated.
+
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+
+use crate::prelude::*;
+
+/// Longest a block may last, in minutes.
+pub const MAX_DURATION: i32 = 7 * 24 * 60;
+
+/// How far ahead [`Recurrence::next_start`] looks, long enough for blocks that
+/// only happen on February 29th.
+const MAX_LOOKAHEAD_DAYS: i64 = 5 * 366;
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A cron-like `minute hour day-of-month month day-of-week` recurrence in UTC.
+///
+/// Fields take `*`, numbers, ranges (`1-5`), steps (`*/15`, `0-30/10`) and
+/// comma separated lists of those. Months and weekdays may also be given by
+/// their English three letter names, Sunday is both `0` and `7`. Like cron,
+/// if both the day of month and the day of week are restricted, a day
+/// matching either of them counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str]) -> Option<u32> {
+    if let Some(index) = names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(value))
+    {
+        return Some(index as u32 + min);
+    }
+
+    value
+        .parse()
+        .ok()
+        .filter(|value| (min..=max).contains(value))
+}
+
+/// Parses one field into a bit set of the values it matches.
+fn parse_field(
+    field: &str,
+    name: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+) -> std::result::Result<u64, String> {
+    let invalid = |part: &str| {
+        format!("invalid {name} `{part}`, expected `*` or values between {min} and {max}")
+    };
+
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step `{step}` in {name} `{part}`"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            let start = parse_value(start, min, max, names).ok_or_else(|| invalid(part))?;
+            let end = parse_value(end, min, max, names).ok_or_else(|| invalid(part))?;
+            if start > end {
+                return Err(invalid(part));
+            }
+            (start, end)
+        } else {
+            let value = parse_value(range, min, max, names).ok_or_else(|| invalid(part))?;
+            // `5/10` means every 10th value starting at 5
+            (value, if step > 1 { max } else { value })
+        };
+
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "expected 5 fields (minute hour day-of-month month day-of-week), got {}",
+                fields.len()
+            ));
+        };
+
+        let mut weekdays = parse_field(weekday, "day of week", 0, 7, &WEEKDAY_NAMES)?;
+        // Sunday is both 0 and 7
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+
+        Ok(Self {
+            minutes: parse_field(minute, "minute", 0, 59, &[])?,
+            hours: parse_field(hour, "hour", 0, 23, &[])?,
+            days: parse_field(day, "day of month", 1, 31, &[])?,
+            months: parse_field(month, "month", 1, 12, &MONTH_NAMES)?,
+            weekdays,
+            days_restricted: !day.starts_with('*'),
+            weekdays_restricted: !weekday.starts_with('*'),
+        })
+    }
+}
+
+impl Recurrence {
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    pub fn matches(&self, time: NaiveDateTime) -> bool {
+        self.minutes & (1 << time.minute()) != 0
+            && self.hours & (1 << time.hour()) != 0
+            && self.matches_date(time.date())
+    }
+
+    /// The start of the run that is ongoing at `now`, if a run lasting
+    /// `duration` minutes would be.
+    pub fn current_start(&self, now: NaiveDateTime, duration: i32) -> Option<NaiveDateTime> {
+        let now = now.with_second(0)?.with_nanosecond(0)?;
+
+        (0..duration.clamp(0, MAX_DURATION) as i64)
+            .map(|minutes| now - Duration::minutes(minutes))
+            .find(|start| self.matches(*start))
+    }
+
+    /// The first start after `after`.
+    pub fn next_start(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let first_day = after.date();
+
+        for offset in 0..MAX_LOOKAHEAD_DAYS {
+            let date = first_day + Duration::days(offset);
+            if !self.matches_date(date) {
+                continue;
+            }
+
+            for hour in (0..24).filter(|hour| self.hours & (1 << hour) != 0) {
+                for minute in (0..60).filter(|minute| self.minutes & (1 << minute) != 0) {
+                    let start = date.and_hms_opt(hour, minute, 0)?;
+                    if start > after {
+                        return Some(start);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl ScheduleBlocks {
+    /// When the block's ongoing run started, if it is running at `now`.
+    pub fn started_at(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let recurrence = self.recurrence.parse::<Recurrence>().ok()?;
+
+        recurrence.current_start(now, self.duration)
+    }
+
+    /// When the block runs next after `now`.
+    pub fn next_start(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let recurrence = self.recurrence.parse::<Recurrence>().ok()?;
+
+        recurrence.next_start(now)
+    }
+
+    /// Whether the block plays `song`, because it is from one of its albums or
+    /// its artist, album or title contains the block's filter.
+    pub fn includes(&self, song: &Songs) -> bool {
+        let on_album = self
+            .album_list()
+            .iter()
+            .any(|album| album.eq_ignore_ascii_case(song.album.trim()));
+        let matches_filter = self
+            .filter
+            .as_deref()
+            .map(|filter| filter.trim().to_lowercase())
+            .filter(|filter| !filter.is_empty())
+            .is_some_and(|filter| {
+                [&song.artist, &song.album, &song.title]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(&filter))
+            });
+
+        on_album || matches_filter
+    }
+}
+
+/// The block running at `now` with the start of its run. If blocks overlap,
+/// the one that started last wins.
+pub fn active_block(
+    blocks: &[ScheduleBlocks],
+    now: NaiveDateTime,
+) -> Option<(&ScheduleBlocks, NaiveDateTime)> {
+    blocks
+        .iter()
+        .filter_map(|block| block.started_at(now).map(|start| (block, start)))
+        .max_by_key(|(block, start)| (*start, block.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn block(id: i32, recurrence: &str, duration: i32) -> ScheduleBlocks {
+        ScheduleBlocks {
+            id,
+            name: format!("Block {id}"),
+            recurrence: recurrence.to_string(),
+            duration,
+            albums: "Homestuck Vol. 5\n\nHiveswap Act 1".to_string(),
+            filter: Some("Vast Error".to_string()),
+            created_by: None,
+            created_at: at(2026, 10, 1, 0, 0),
+            last_announced_at: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_recurrence() {
+        let friday_evenings = "0 20 * * fri".parse::<Recurrence>().unwrap();
+        // 2026-10-16 is a Friday
+        assert!(friday_evenings.matches(at(2026, 10, 16, 20, 0)));
+        assert!(!friday_evenings.matches(at(2026, 10, 16, 20, 1)));
+        assert!(!friday_evenings.matches(at(2026, 10, 17, 20, 0)));
+
+        let quarter_hours = "*/15 9-17 * * 1-5".parse::<Recurrence>().unwrap();
+        assert!(quarter_hours.matches(at(2026, 10, 19, 9, 45)));
+        assert!(!quarter_hours.matches(at(2026, 10, 19, 18, 0)));
+        assert!(!quarter_hours.matches(at(2026, 10, 18, 9, 45)));
+
+        let sundays = "0 0 * * 7".parse::<Recurrence>().unwrap();
+        assert!(sundays.matches(at(2026, 10, 18, 0, 0)));
+
+        // either the day of month or the day of week
+        let either = "0 12 13 * mon".parse::<Recurrence>().unwrap();
+        assert!(either.matches(at(2026, 10, 13, 12, 0)));
+        assert!(either.matches(at(2026, 10, 19, 12, 0)));
+        assert!(!either.matches(at(2026, 10, 20, 12, 0)));
+
+        assert!("0 20 * *".parse::<Recurrence>().is_err());
+        assert!("60 * * * *".parse::<Recurrence>().is_err());
+        assert!("* * 0 * *".parse::<Recurrence>().is_err());
+        assert!("*/0 * * * *".parse::<Recurrence>().is_err());
+        assert!("5-1 * * * *".parse::<Recurrence>().is_err());
+        assert!("* * * foo *".parse::<Recurrence>().is_err());
+    }
+
+    #[test]
+    fn test_current_and_next_start() {
+        let homestuck_day = "0 0 13 apr *".parse::<Recurrence>().unwrap();
+        assert_eq!(
+            homestuck_day.current_start(at(2027, 4, 13, 23, 59), 24 * 60),
+            Some(at(2027, 4, 13, 0, 0))
+        );
+        assert_eq!(
+            homestuck_day.current_start(at(2027, 4, 14, 0, 0), 24 * 60),
+            None
+        );
+        assert_eq!(
+            homestuck_day.next_start(at(2026, 10, 19, 12, 0)),
+            Some(at(2027, 4, 13, 0, 0))
+        );
+
+        let leap_day = "30 6 29 2 *".parse::<Recurrence>().unwrap();
+        assert_eq!(
+            leap_day.next_start(at(2026, 10, 19, 12, 0)),
+            Some(at(2028, 2, 29, 6, 30))
+        );
+    }
+
+    #[test]
+    fn test_active_block() {
+        let hour = block(1, "0 20 * * fri", 60);
+        let special = block(2, "30 20 16 10 *", 15);
+        let blocks = [hour, special];
+
+        assert!(active_block(&blocks, at(2026, 10, 16, 19, 59)).is_none());
+        let (active, start) = active_block(&blocks, at(2026, 10, 16, 20, 10)).unwrap();
+        assert_eq!((active.id, start), (1, at(2026, 10, 16, 20, 0)));
+        let (active, _) = active_block(&blocks, at(2026, 10, 16, 20, 40)).unwrap();
+        assert_eq!(active.id, 2);
+        let (active, _) = active_block(&blocks, at(2026, 10, 16, 20, 50)).unwrap();
+        assert_eq!(active.id, 1);
+        assert!(active_block(&blocks, at(2026, 10, 16, 21, 0)).is_none());
+    }
+
+    #[test]
+    fn test_block_includes() {
+        let block = block(1, "0 20 * * fri", 60);
+        let song = |artist: &str, album: &str| Songs {
+            file_path: "/music/a.mp3".to_string(),
+            title: "Title".to_string(),
+            artist: artist.to_string(),
+            album: album.to_string(),
+            played: 0,
+            requested: 0,
+            tsvector: None,
+            duration: 180.0,
+            file_hash: "a".to_string(),
+            bitrate: 320,
+            track_number: None,
+            disc_number: None,
+            year: None,
+            genre: None,
+            album_artist: None,
+            hidden: false,
+        };
+
+        assert_eq!(block.album_list(), ["Homestuck Vol. 5", "Hiveswap Act 1"]);
+        assert!(block.includes(&song("Toby Fox", "homestuck vol. 5")));
+        assert!(block.includes(&song("Vast Error OST", "Volume 1")));
+        assert!(!block.includes(&song("Toby Fox", "Homestuck Vol. 6")));
+    }
+}
@@ -0,0 +1,592 @@
+//! The song search query language.
+//!
+//! Words are matched against titles, artists and albums like before, filters
+//! narrow the results down:
+//!
+//! - `artist:"Toby Fox"`, `album:Vol.6`, `title:`, `genre:` match when the
+//!   field contains the value
+//! - `year:2012`, `year:>=2010` compare the release year
+//! - `duration:<3m`, `duration:>=2:30` compare the length
+//! - `tag:genre=chiptune` matches a raw tag's value, `tag:lyrics` only needs
+//!   the tag to be there
+//! - `-remix`, `-artist:Toby` exclude songs matching the word or filter
+//!
+//! Values with spaces are quoted, so are words that look like filters, e.g.
+//! `"Re:Union"`.
+
+use std::str::FromStr;
+
+use sea_orm::{
+    prelude::*,
+    sea_query::{extension::postgres::PgExpr, Func, Query, SimpleExpr},
+    Condition,
+};
+
+use crate::custom_entities::songs::{Column, Entity};
+use crate::entities::song_tags;
+
+/// The filters there are, for error messages.
+const FILTERS: &str = "artist:, album:, title:, genre:, year:, duration: and tag:";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery {
+    pub terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    /// Excludes the songs the term matches, written as `-term`.
+    pub negated: bool,
+    pub kind: TermKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TermKind {
+    /// A word or quoted phrase.
+    Text(String),
+    Artist(String),
+    Album(String),
+    Title(String),
+    Genre(String),
+    Year(Comparison<i32>),
+    /// In seconds.
+    Duration(Comparison<f64>),
+    Tag {
+        key: String,
+        value: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison<T> {
+    pub op: CompareOp,
+    pub value: T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} (at character {position})")]
+pub struct ParseError {
+    /// Where the offending term starts, counted in characters from 1.
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseErrorKind {
+    #[error("the quote is never closed")]
+    UnterminatedQuote,
+    #[error("`-` has to be followed by a word or filter to exclude")]
+    EmptyNegation,
+    #[error("there is no `{0}:` filter, try {FILTERS}, or put it in quotes to search for it")]
+    UnknownFilter(String),
+    #[error("`{0}:` needs a value, e.g. `{0}:something`")]
+    EmptyValue(&'static str),
+    #[error("`{0}` is not a year, try e.g. `year:2012` or `year:>=2010`")]
+    InvalidYear(String),
+    #[error(
+        "`{0}` is not a duration, try e.g. `duration:<3m`, `duration:>=2:30` or `duration:90s`"
+    )]
+    InvalidDuration(String),
+    #[error("`tag:` needs a tag name, e.g. `tag:genre=chiptune` or `tag:lyrics`")]
+    EmptyTag,
+}
+
+impl FromStr for SearchQuery {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        SearchQuery::parse(input)
+    }
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            chars: input.chars().collect(),
+            position: 0,
+        };
+        let mut terms = vec![];
+        while let Some(term) = parser.term()? {
+            terms.push(term);
+        }
+
+        Ok(Self { terms })
+    }
+
+    /// The words to match titles, artists and albums against, if any.
+    pub fn text(&self) -> Option<String> {
+        let words = self
+            .terms
+            .iter()
+            .filter(|term| !term.negated)
+            .filter_map(|term| match &term.kind {
+                TermKind::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        Some(words.join(" ")).filter(|text| !text.trim().is_empty())
+    }
+
+    /// What the filters and excluded words require of a song.
+    pub fn condition(&self) -> Condition {
+        self.terms
+            .iter()
+            .filter(|term| term.negated || !matches!(term.kind, TermKind::Text(_)))
+            .fold(Condition::all(), |condition, term| {
+                condition.add(term.condition())
+            })
+    }
+}
+
+impl Term {
+    fn condition(&self) -> Condition {
+        let matches = match &self.kind {
+            TermKind::Text(text) => {
+                let pattern = contains_pattern(text);
+                Condition::any()
+                    .add(col(Column::Title).ilike(&pattern))
+                    .add(col(Column::Artist).ilike(&pattern))
+                    .add(col(Column::Album).ilike(&pattern))
+            }
+            TermKind::Artist(artist) => {
+                let pattern = contains_pattern(artist);
+                Condition::any()
+                    .add(col(Column::Artist).ilike(&pattern))
+                    .add(or_empty(Column::AlbumArtist).ilike(&pattern))
+            }
+            TermKind::Album(album) => {
+                Condition::all().add(col(Column::Album).ilike(contains_pattern(album)))
+            }
+            TermKind::Title(title) => {
+                Condition::all().add(col(Column::Title).ilike(contains_pattern(title)))
+            }
+            TermKind::Genre(genre) => {
+                Condition::all().add(or_empty(Column::Genre).ilike(contains_pattern(genre)))
+            }
+            TermKind::Year(year) => Condition::all().add(year.expr(Column::Year)),
+            TermKind::Duration(duration) => Condition::all().add(duration.expr(Column::Duration)),
+            TermKind::Tag { key, value } => {
+                let mut tags = Query::select();
+                tags.column((song_tags::Entity, song_tags::Column::SongId))
+                    .from(song_tags::Entity)
+                    .and_where(
+                        Expr::expr(Func::lower(Expr::col((
+                            song_tags::Entity,
+                            song_tags::Column::Tag,
+                        ))))
+                        .eq(key.to_lowercase()),
+                    );
+                if let Some(value) = value {
+                    tags.and_where(
+                        Expr::col((song_tags::Entity, song_tags::Column::Value))
+                            .ilike(contains_pattern(value)),
+                    );
+                }
+
+                Condition::all().add(col(Column::FileHash).in_subquery(tags.to_owned()))
+            }
+        };
+
+        if self.negated {
+            matches.not()
+        } else {
+            matches
+        }
+    }
+}
+
+impl<T: Into<sea_orm::Value> + Copy> Comparison<T> {
+    fn expr(&self, column: Column) -> SimpleExpr {
+        let column = col(column);
+        match self.op {
+            CompareOp::Lt => column.lt(self.value),
+            CompareOp::Le => column.lte(self.value),
+            CompareOp::Eq => column.eq(self.value),
+            CompareOp::Ge => column.gte(self.value),
+            CompareOp::Gt => column.gt(self.value),
+        }
+    }
+}
+
+fn col(column: Column) -> Expr {
+    Expr::col((Entity, column))
+}
+
+/// A nullable text column, with `NULL` as an empty string so excluding a
+/// value keeps the songs that don't have one.
+fn or_empty(column: Column) -> Expr {
+    Expr::expr(Func::coalesce([col(column).into(), Expr::val("").into()]))
+}
+
+/// An `ILIKE` pattern matching text that contains `value` as it's written.
+fn contains_pattern(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{}%", escaped)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn error(&self, start: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            position: start + 1,
+            kind,
+        }
+    }
+
+    fn term(&mut self) -> Result<Option<Term>, ParseError> {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+        let start = self.position;
+        let Some(first) = self.peek() else {
+            return Ok(None);
+        };
+
+        let negated = first == '-';
+        if negated {
+            self.position += 1;
+            if self.peek().is_none_or(char::is_whitespace) {
+                return Err(self.error(start, ParseErrorKind::EmptyNegation));
+            }
+        }
+
+        if self.peek() == Some('"') {
+            let text = self.quoted(start)?;
+            return Ok(Some(Term {
+                negated,
+                kind: TermKind::Text(text),
+            }));
+        }
+
+        let word_start = self.position;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.position += 1;
+        }
+        if self.position > word_start && self.peek() == Some(':') {
+            let name = self.chars[word_start..self.position]
+                .iter()
+                .collect::<String>()
+                .to_lowercase();
+            self.position += 1;
+            let value = if self.peek() == Some('"') {
+                self.quoted(start)?
+            } else {
+                self.bare()
+            };
+            let kind = filter(&name, value).map_err(|kind| self.error(start, kind))?;
+
+            return Ok(Some(Term { negated, kind }));
+        }
+
+        self.position = word_start;
+        Ok(Some(Term {
+            negated,
+            kind: TermKind::Text(self.bare()),
+        }))
+    }
+
+    /// Reads up to the next whitespace.
+    fn bare(&mut self) -> String {
+        let start = self.position;
+        while self.peek().is_some_and(|c| !c.is_whitespace()) {
+            self.position += 1;
+        }
+
+        self.chars[start..self.position].iter().collect()
+    }
+
+    /// Reads a quoted string, the parser is on its opening quote.
+    fn quoted(&mut self, term_start: usize) -> Result<String, ParseError> {
+        self.position += 1;
+        let start = self.position;
+        while self.peek().is_some_and(|c| c != '"') {
+            self.position += 1;
+        }
+        if self.peek().is_none() {
+            return Err(self.error(term_start, ParseErrorKind::UnterminatedQuote));
+        }
+        let text = self.chars[start..self.position].iter().collect();
+        self.position += 1;
+
+        Ok(text)
+    }
+}
+
+fn filter(name: &str, value: String) -> Result<TermKind, ParseErrorKind> {
+    let name: &'static str = match name {
+        "artist" => "artist",
+        "album" => "album",
+        "title" => "title",
+        "genre" => "genre",
+        "year" => "year",
+        "duration" => "duration",
+        "tag" => "tag",
+        _ => return Err(ParseErrorKind::UnknownFilter(name.to_string())),
+    };
+    if value.trim().is_empty() {
+        return Err(ParseErrorKind::EmptyValue(name));
+    }
+
+    Ok(match name {
+        "artist" => TermKind::Artist(value),
+        "album" => TermKind::Album(value),
+        "title" => TermKind::Title(value),
+        "genre" => TermKind::Genre(value),
+        "year" => {
+            let (op, year) = comparison(&value);
+            let year = year
+                .parse()
+                .map_err(|_| ParseErrorKind::InvalidYear(value.clone()))?;
+            TermKind::Year(Comparison { op, value: year })
+        }
+        "duration" => {
+            let (op, duration) = comparison(&value);
+            let duration =
+                parse_duration(duration).ok_or(ParseErrorKind::InvalidDuration(value.clone()))?;
+            TermKind::Duration(Comparison {
+                op,
+                value: duration,
+            })
+        }
+        _ => {
+            let (key, value) = match value.split_once('=') {
+                Some((key, value)) => (key, Some(value.to_string())),
+                None => (value.as_str(), None),
+            };
+            if key.trim().is_empty() {
+                return Err(ParseErrorKind::EmptyTag);
+            }
+            TermKind::Tag {
+                key: key.trim().to_string(),
+                value: value.filter(|value| !value.is_empty()),
+            }
+        }
+    })
+}
+
+/// Splits a leading comparison operator off a value, `=` if there is none.
+fn comparison(value: &str) -> (CompareOp, &str) {
+    for (prefix, op) in [
+        ("<=", CompareOp::Le),
+        (">=", CompareOp::Ge),
+        ("<", CompareOp::Lt),
+        (">", CompareOp::Gt),
+        ("=", CompareOp::Eq),
+    ] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (op, rest);
+        }
+    }
+
+    (CompareOp::Eq, value)
+}
+
+/// Parses a length like `90`, `90s`, `3m`, `1h`, `3m30s` or `2:30` into
+/// seconds.
+fn parse_duration(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    if value.contains(':') {
+        let parts = value
+            .split(':')
+            .map(|part| part.parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        if parts.len() > 3 {
+            return None;
+        }
+        return Some(
+            parts
+                .into_iter()
+                .fold(0.0, |total, part| total * 60.0 + part as f64),
+        );
+    }
+
+    let mut total = 0.0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            'h' => 3_600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            _ => return None,
+        };
+        total += number.parse::<f64>().ok()? * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        total += number.parse::<f64>().ok()?;
+    }
+
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{QueryFilter, QueryTrait};
+
+    use super::*;
+
+    fn text(text: &str) -> Term {
+        Term {
+            negated: false,
+            kind: TermKind::Text(text.to_string()),
+        }
+    }
+
+    fn error(input: &str) -> ParseError {
+        SearchQuery::parse(input).unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_filters() {
+        let query = SearchQuery::parse(
+            r#"artist:"Toby Fox" album:Vol.6 duration:<3m tag:genre=chiptune -remix showtime"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.terms,
+            vec![
+                Term {
+                    negated: false,
+                    kind: TermKind::Artist("Toby Fox".to_string()),
+                },
+                Term {
+                    negated: false,
+                    kind: TermKind::Album("Vol.6".to_string()),
+                },
+                Term {
+                    negated: false,
+                    kind: TermKind::Duration(Comparison {
+                        op: CompareOp::Lt,
+                        value: 180.0,
+                    }),
+                },
+                Term {
+                    negated: false,
+                    kind: TermKind::Tag {
+                        key: "genre".to_string(),
+                        value: Some("chiptune".to_string()),
+                    },
+                },
+                Term {
+                    negated: true,
+                    kind: TermKind::Text("remix".to_string()),
+                },
+                text("showtime"),
+            ]
+        );
+        assert_eq!(query.text().as_deref(), Some("showtime"));
+    }
+
+    #[test]
+    fn test_parse_plain_text() {
+        let query = SearchQuery::parse(r#"  sburban  "jungle (remix)" "Re:Union" "#).unwrap();
+
+        assert_eq!(
+            query.terms,
+            vec![text("sburban"), text("jungle (remix)"), text("Re:Union")]
+        );
+        assert_eq!(
+            query.text().as_deref(),
+            Some("sburban jungle (remix) Re:Union")
+        );
+        assert_eq!(SearchQuery::parse("").unwrap().text(), None);
+        assert_eq!(SearchQuery::parse("-remix").unwrap().text(), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            error(r#"album:"Vol. 6"#),
+            ParseError {
+                position: 1,
+                kind: ParseErrorKind::UnterminatedQuote,
+            }
+        );
+        assert_eq!(
+            error("showtime bpm:120"),
+            ParseError {
+                position: 10,
+                kind: ParseErrorKind::UnknownFilter("bpm".to_string()),
+            }
+        );
+        assert_eq!(error("- remix").kind, ParseErrorKind::EmptyNegation);
+        assert_eq!(error("artist:").kind, ParseErrorKind::EmptyValue("artist"));
+        assert_eq!(
+            error("year:>soon").kind,
+            ParseErrorKind::InvalidYear(">soon".to_string())
+        );
+        assert_eq!(
+            error("duration:3x").kind,
+            ParseErrorKind::InvalidDuration("3x".to_string())
+        );
+        assert_eq!(error("tag:=chiptune").kind, ParseErrorKind::EmptyTag);
+        assert_eq!(
+            error("showtime bpm:120").to_string(),
+            "there is no `bpm:` filter, try artist:, album:, title:, genre:, year:, duration: and tag:, or put it in quotes to search for it (at character 10)"
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Some(90.0));
+        assert_eq!(parse_duration("90s"), Some(90.0));
+        assert_eq!(parse_duration("3m"), Some(180.0));
+        assert_eq!(parse_duration("3m30s"), Some(210.0));
+        assert_eq!(parse_duration("1h"), Some(3_600.0));
+        assert_eq!(parse_duration("2:30"), Some(150.0));
+        assert_eq!(parse_duration("1:02:03"), Some(3_723.0));
+        assert_eq!(parse_duration("2:3x"), None);
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn test_condition() {
+        let query =
+            SearchQuery::parse(r#"showtime -artist:Toby year:>=2010 -genre:50%_off tag:LYRICS"#)
+                .unwrap();
+        let sql = Entity::find()
+            .filter(query.condition())
+            .build(sea_orm::DatabaseBackend::Postgres)
+            .to_string();
+
+        assert!(
+            sql.ends_with(r#"WHERE (NOT (("songs"."artist" ILIKE '%Toby%') OR (COALESCE("songs"."album_artist", '') ILIKE '%Toby%'))) AND "songs"."year" >= 2010 AND (NOT (COALESCE("songs"."genre", '') ILIKE E'%50\\%\\_off%')) AND "songs"."file_hash" IN (SELECT "song_tags"."song_id" FROM "song_tags" WHERE LOWER("song_tags"."tag") = 'lyrics')"#),
+            "{}",
+            sql
+        );
+        // the words are searched by the full text index instead
+        assert!(!sql.contains("showtime"));
+    }
+}
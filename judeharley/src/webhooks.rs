@@ -0,0 +1,483 @@
+//! Outgoing webhooks for radio events.
+//!
+//! Services call [`trigger`] when something happens, which queues a delivery
+//! for every enabled webhook subscribed to the event. Frohike works the queue
+//! off with [`run_dispatcher`], POSTing the JSON payload signed the same way
+//! Langley expects its own webhook to be signed:
+//!
+//! - `X-LumiRadio-Timestamp`, the UNIX time the request was sent at
+//! - `X-LumiRadio-Signature: sha256=<hex>`, the HMAC-SHA256 of
+//!   `"{timestamp}.{body}"` keyed with the webhook's secret
+//!
+//! Failed deliveries are retried with an exponential backoff, and every
+//! delivery is kept in `webhook_deliveries` for [`LOG_RETENTION_DAYS`].
+//! Deliveries to a disabled webhook stay pending until it's enabled again.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use rand::{distributions::Alphanumeric, Rng};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use sha2::Sha256;
+use tracing::{debug, error, warn};
+
+use crate::now_playing::NowPlayingSong;
+use crate::prelude::*;
+
+pub const EVENT_HEADER: &str = "x-lumiradio-event";
+pub const DELIVERY_HEADER: &str = "x-lumiradio-delivery";
+pub const TIMESTAMP_HEADER: &str = "x-lumiradio-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-lumiradio-signature";
+
+/// How many pending deliveries are sent per pass.
+const BATCH_SIZE: u64 = 50;
+
+/// How many deliveries are sent at once, so a slow endpoint doesn't hold up
+/// the others.
+const CONCURRENT_DELIVERIES: usize = 8;
+
+/// Deliveries failing this often are given up on, with the backoff that is
+/// roughly an hour after the first attempt.
+const MAX_ATTEMPTS: i32 = 8;
+
+const MAX_RETRY_DELAY_SECONDS: i64 = 60 * 60;
+
+/// How long finished deliveries are kept for the delivery log.
+pub const LOG_RETENTION_DAYS: i64 = 14;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    SongStarted,
+    RequestQueued,
+    SongsIndexed,
+}
+
+impl WebhookEvent {
+    pub const ALL: [Self; 3] = [Self::SongStarted, Self::RequestQueued, Self::SongsIndexed];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SongStarted => "song.started",
+            Self::RequestQueued => "request.queued",
+            Self::SongsIndexed => "songs.indexed",
+        }
+    }
+}
+
+impl Display for WebhookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|event| event.as_str() == s.trim())
+            .ok_or_else(|| format!("unknown webhook event `{}`", s.trim()))
+    }
+}
+
+/// Parses a comma separated list of event names, e.g. `song.started,request.queued`.
+pub fn parse_events(input: &str) -> std::result::Result<Vec<WebhookEvent>, String> {
+    let mut events = vec![];
+    for name in input.split(',').filter(|name| !name.trim().is_empty()) {
+        let event = name.parse()?;
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+
+    if events.is_empty() {
+        return Err("no webhook events given".to_string());
+    }
+
+    Ok(events)
+}
+
+/// The data of a `request.queued` event.
+#[derive(Serialize, Debug, Clone)]
+pub struct RequestQueued {
+    pub song: NowPlayingSong,
+    /// Discord ID of the user who requested the song.
+    pub requested_by: String,
+}
+
+/// The data of a `songs.indexed` event, sent when the watcher indexes new files.
+#[derive(Serialize, Debug, Clone)]
+pub struct SongsIndexed {
+    pub songs: Vec<NowPlayingSong>,
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    event: &'static str,
+    occurred_at: DateTime<Utc>,
+    data: &'a T,
+}
+
+/// Queues `data` for every enabled webhook subscribed to `event`, returns
+/// how many deliveries were queued.
+pub async fn dispatch<T: Serialize>(
+    event: WebhookEvent,
+    data: &T,
+    db: &DatabaseConnection,
+) -> Result<usize> {
+    let webhook_ids = Webhooks::get_enabled(db)
+        .await?
+        .into_iter()
+        .filter(|webhook| webhook.event_names().any(|name| name == event.as_str()))
+        .map(|webhook| webhook.id)
+        .collect::<Vec<_>>();
+    if webhook_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let payload = serde_json::to_string(&Envelope {
+        event: event.as_str(),
+        occurred_at: Utc::now(),
+        data,
+    })?;
+    let queued = webhook_ids.len();
+    WebhookDeliveries::insert_many(webhook_ids, event.as_str(), &payload, db).await?;
+
+    Ok(queued)
+}
+
+/// Like [`dispatch`], but logs failures instead of returning them, as a
+/// webhook failing should never get in the way of the event itself.
+pub async fn trigger<T: Serialize>(event: WebhookEvent, data: &T, db: &DatabaseConnection) {
+    match dispatch(event, data, db).await {
+        Ok(0) => {}
+        Ok(queued) => debug!(%event, queued, "queued webhook deliveries"),
+        Err(e) => warn!(%event, error = %e, "failed to queue webhook deliveries"),
+    }
+}
+
+/// A random secret for a new webhook.
+pub fn generate_secret() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect()
+}
+
+/// The value of the signature header for `body` sent at `timestamp`.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Delivered(u16),
+    Retry(Option<u16>, String),
+    Failed(Option<u16>, String),
+}
+
+/// Client errors other than timeouts and rate limits won't go away by
+/// retrying, everything else might.
+fn classify_status(status: reqwest::StatusCode) -> Outcome {
+    let code = status.as_u16();
+    if status.is_success() {
+        Outcome::Delivered(code)
+    } else if status.is_client_error()
+        && status != reqwest::StatusCode::REQUEST_TIMEOUT
+        && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+    {
+        Outcome::Failed(Some(code), format!("responded with {}", status))
+    } else {
+        Outcome::Retry(Some(code), format!("responded with {}", status))
+    }
+}
+
+/// How long to wait before the next attempt after `attempts` failed ones.
+fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.clamp(1, 12) - 1;
+
+    chrono::Duration::seconds((30i64 << exponent).min(MAX_RETRY_DELAY_SECONDS))
+}
+
+async fn deliver(
+    client: &reqwest::Client,
+    webhook: &Webhooks,
+    delivery: &WebhookDeliveries,
+) -> Outcome {
+    let timestamp = Utc::now().timestamp();
+    let result = client
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.id)
+        .header(TIMESTAMP_HEADER, timestamp)
+        .header(
+            SIGNATURE_HEADER,
+            sign(&webhook.secret, timestamp, &delivery.payload),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    match result {
+        Ok(response) => classify_status(response.status()),
+        Err(e) => Outcome::Retry(None, e.to_string()),
+    }
+}
+
+pub fn client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent(concat!("lumiRadio/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(Into::into)
+}
+
+/// Sends the deliveries that are due, returns how many were delivered.
+pub async fn process_deliveries(
+    client: &reqwest::Client,
+    db: &DatabaseConnection,
+) -> Result<usize> {
+    let due = WebhookDeliveries::get_due(BATCH_SIZE, db).await?;
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    let webhooks = Webhooks::get_all(db)
+        .await?
+        .into_iter()
+        .map(|webhook| (webhook.id, webhook))
+        .collect::<HashMap<_, _>>();
+
+    let results = futures::stream::iter(due.into_iter().filter_map(|delivery| {
+        // deleted or disabled since the deliveries were fetched
+        let webhook = webhooks
+            .get(&delivery.webhook_id)
+            .filter(|webhook| webhook.enabled)?;
+
+        Some(async move {
+            let outcome = deliver(client, webhook, &delivery).await;
+            record_outcome(delivery, outcome, db).await
+        })
+    }))
+    .buffer_unordered(CONCURRENT_DELIVERIES)
+    .collect::<Vec<_>>()
+    .await;
+
+    let mut delivered = 0;
+    for result in results {
+        if result? {
+            delivered += 1;
+        }
+    }
+
+    Ok(delivered)
+}
+
+/// Stores how a delivery went, returns whether it was delivered.
+async fn record_outcome(
+    delivery: WebhookDeliveries,
+    outcome: Outcome,
+    db: &DatabaseConnection,
+) -> Result<bool> {
+    match outcome {
+        Outcome::Delivered(status) => {
+            delivery.mark_delivered(status, db).await?;
+            return Ok(true);
+        }
+        Outcome::Retry(status, error) if delivery.attempts + 1 < MAX_ATTEMPTS => {
+            debug!(
+                delivery_id = delivery.id,
+                error, "webhook delivery failed, will retry"
+            );
+            let next_attempt_at = Utc::now().naive_utc() + retry_delay(delivery.attempts + 1);
+            delivery
+                .record_failure(status, error, Some(next_attempt_at), db)
+                .await?;
+        }
+        Outcome::Retry(status, error) | Outcome::Failed(status, error) => {
+            warn!(
+                delivery_id = delivery.id,
+                webhook_id = delivery.webhook_id,
+                error,
+                "giving up on webhook delivery"
+            );
+            delivery.record_failure(status, error, None, db).await?;
+        }
+    }
+
+    Ok(false)
+}
+
+/// Works off the delivery queue forever and prunes the delivery log, failed
+/// passes are logged and retried on the next tick.
+pub async fn run_dispatcher(db: DatabaseConnection, interval: Duration) -> Result<()> {
+    let client = client()?;
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+
+        match process_deliveries(&client, &db).await {
+            Ok(0) => {}
+            Ok(delivered) => debug!(delivered, "delivered webhooks"),
+            Err(e) => error!(error = %e, "failed to process webhook deliveries"),
+        }
+
+        let before = Utc::now().naive_utc() - chrono::Duration::days(LOG_RETENTION_DAYS);
+        if let Err(e) = WebhookDeliveries::prune(before, &db).await {
+            error!(error = %e, "failed to prune webhook deliveries");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::stand_in;
+
+    async fn hook(status: &'static str) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let (base_url, request) = stand_in(status, "").await;
+
+        (format!("{base_url}/hook"), request)
+    }
+
+    fn webhook(url: String) -> Webhooks {
+        Webhooks {
+            id: 1,
+            url,
+            events: "song.started,request.queued".to_string(),
+            secret: "secret".to_string(),
+            enabled: true,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    fn delivery() -> WebhookDeliveries {
+        WebhookDeliveries {
+            id: 42,
+            webhook_id: 1,
+            event: "song.started".to_string(),
+            payload: r#"{"event":"song.started"}"#.to_string(),
+            status: "pending".to_string(),
+            attempts: 0,
+            next_attempt_at: None,
+            response_status: None,
+            last_error: None,
+            created_at: Utc::now().naive_utc(),
+            delivered_at: None,
+        }
+    }
+
+    fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+        request.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("secret", 1_760_788_800, r#"{"event":"song.started"}"#),
+            "sha256=a48fc7e730385b3a1c41571af568e6c32519642a4b30078b08e9dd51e374aa55"
+        );
+    }
+
+    #[test]
+    fn test_parse_events() {
+        assert_eq!(
+            " request.queued".parse::<WebhookEvent>(),
+            Ok(WebhookEvent::RequestQueued)
+        );
+        assert!("song.ended".parse::<WebhookEvent>().is_err());
+        assert_eq!(
+            parse_events("songs.indexed, song.started,songs.indexed"),
+            Ok(vec![WebhookEvent::SongsIndexed, WebhookEvent::SongStarted])
+        );
+        assert!(parse_events(" , ").is_err());
+        assert!(parse_events("song.started,nope").is_err());
+        assert_eq!(
+            webhook(String::new()).event_names().collect::<Vec<_>>(),
+            ["song.started", "request.queued"]
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), chrono::Duration::seconds(30));
+        assert_eq!(retry_delay(2), chrono::Duration::minutes(1));
+        assert_eq!(retry_delay(7), chrono::Duration::minutes(32));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), chrono::Duration::hours(1));
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payloads() {
+        let (url, request) = hook("204 No Content").await;
+        let client = client().unwrap();
+
+        let outcome = deliver(&client, &webhook(url), &delivery()).await;
+        assert_eq!(outcome, Outcome::Delivered(204));
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1"));
+        assert_eq!(header(&request, EVENT_HEADER), Some("song.started"));
+        assert_eq!(header(&request, DELIVERY_HEADER), Some("42"));
+
+        let timestamp = header(&request, TIMESTAMP_HEADER)
+            .unwrap()
+            .parse::<i64>()
+            .unwrap();
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        assert_eq!(body, r#"{"event":"song.started"}"#);
+        assert_eq!(
+            header(&request, SIGNATURE_HEADER),
+            Some(sign("secret", timestamp, body).as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn classifies_failed_deliveries() {
+        let client = client().unwrap();
+        for (status, retried) in [
+            ("400 Bad Request", false),
+            ("410 Gone", false),
+            ("429 Too Many Requests", true),
+            ("500 Internal Server Error", true),
+        ] {
+            let (url, _request) = hook(status).await;
+
+            match deliver(&client, &webhook(url), &delivery()).await {
+                Outcome::Retry(Some(_), _) => assert!(retried, "{status}"),
+                Outcome::Failed(Some(_), _) => assert!(!retried, "{status}"),
+                outcome => panic!("unexpected outcome for {status}: {outcome:?}"),
+            }
+        }
+
+        // nothing listening at all
+        let outcome = deliver(
+            &client,
+            &webhook("http://127.0.0.1:1/hook".to_string()),
+            &delivery(),
+        )
+        .await;
+        assert!(matches!(outcome, Outcome::Retry(None, _)));
+    }
+}
@@ -0,0 +1,444 @@
+//! Authentication for the webhooks Liquidsoap calls.
+//!
+//! Every request must carry an `X-Langley-Timestamp` header containing the UNIX
+//! time it was sent at, fractions of a second allowed, and an
+//! `X-Langley-Signature: sha256=<hex>` header, the HMAC-SHA256 of
+//! `"{timestamp}.{body}"` keyed with the shared secret. Requests without a body,
+//! like `GET /next`, are signed as if the body was empty.
+//!
+//! Requests whose timestamp is more than [`MAX_CLOCK_SKEW`] seconds away from
+//! our clock are rejected, and the signatures of accepted requests are kept in
+//! [`SeenSignatures`] until their timestamp has expired, so a captured request
+//! can't be replayed at all.
+
+use std::sync::Arc;
+
+use axum::async_trait;
+use axum::body::{Body, Bytes};
+use axum::extract::{FromRef, FromRequest, FromRequestParts};
+use axum::http::{request::Parts, HeaderMap, Request, StatusCode};
+use fred::pool::RedisPool;
+use fred::prelude::KeysInterface;
+use fred::types::{Expiration, SetOptions};
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use sha2::Sha256;
+use tracing::{error, warn};
+
+use judeharley::metrics;
+
+use crate::ApiError;
+
+pub const TIMESTAMP_HEADER: &str = "x-langley-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-langley-signature";
+
+/// Largest request body langley accepts, `/played` payloads are a few hundred bytes.
+pub const MAX_BODY_SIZE: usize = 16 * 1024;
+
+/// How many seconds a request's timestamp may differ from the current time.
+pub const MAX_CLOCK_SKEW: i64 = 5 * 60;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The signatures of requests that were accepted recently.
+#[async_trait]
+pub trait SeenSignatures: Send + Sync {
+    /// Remembers `signature` for `ttl` seconds, returns `false` if it was
+    /// already remembered.
+    async fn insert(&self, signature: &str, ttl: i64) -> Result<bool, ApiError>;
+}
+
+#[async_trait]
+impl SeenSignatures for RedisPool {
+    async fn insert(&self, signature: &str, ttl: i64) -> Result<bool, ApiError> {
+        let inserted: Option<String> = self
+            .set(
+                format!("langley:webhook_signature:{signature}"),
+                1,
+                Some(Expiration::EX(ttl)),
+                Some(SetOptions::NX),
+                false,
+            )
+            .await
+            .map_err(|e| {
+                error!(error = %e, "failed to record webhook signature");
+                metrics::ERRORS.with_label_values(&["redis"]).inc();
+                ApiError::new(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "could not check the request for replays",
+                )
+            })?;
+
+        Ok(inserted.is_some())
+    }
+}
+
+#[derive(Clone)]
+pub struct WebhookAuth {
+    secret: Arc<[u8]>,
+    seen: Arc<dyn SeenSignatures>,
+}
+
+impl WebhookAuth {
+    pub fn new(secret: impl AsRef<[u8]>, seen: impl SeenSignatures + 'static) -> Self {
+        Self {
+            secret: Arc::from(secret.as_ref()),
+            seen: Arc::new(seen),
+        }
+    }
+
+    fn mac(&self, timestamp: &str, body: &[u8]) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(timestamp.as_bytes());
+        mac.update(b".");
+        mac.update(body);
+        mac
+    }
+
+    /// Checks the request's timestamp and signature against the shared secret,
+    /// with `now` being the current UNIX time. Returns the signature in
+    /// lowercase hex.
+    pub fn verify(&self, headers: &HeaderMap, body: &[u8], now: f64) -> Result<String, ApiError> {
+        let timestamp = headers
+            .get(TIMESTAMP_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| unauthorized("missing or invalid X-Langley-Timestamp header"))?;
+        let sent_at = timestamp
+            .parse::<f64>()
+            .ok()
+            .filter(|sent_at| sent_at.is_finite())
+            .ok_or_else(|| unauthorized("missing or invalid X-Langley-Timestamp header"))?;
+        if (now - sent_at).abs() > MAX_CLOCK_SKEW as f64 {
+            return Err(unauthorized(
+                "request timestamp is too far from the current time",
+            ));
+        }
+
+        let signature = headers
+            .get(SIGNATURE_HEADER)
+            .ok_or_else(|| unauthorized("missing X-Langley-Signature header"))?
+            .to_str()
+            .ok()
+            .and_then(|v| v.strip_prefix("sha256="))
+            .and_then(|v| hex::decode(v).ok())
+            .ok_or_else(|| unauthorized("malformed X-Langley-Signature header"))?;
+
+        self.mac(timestamp, body)
+            .verify_slice(&signature)
+            .map_err(|_| unauthorized("invalid signature"))?;
+
+        Ok(hex::encode(signature))
+    }
+
+    /// [`verify`](Self::verify)s the request and rejects it if it was accepted
+    /// before. Signatures are remembered for twice the clock skew, the longest
+    /// a timestamp can be accepted for.
+    pub async fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: f64,
+    ) -> Result<(), ApiError> {
+        let signature = self.verify(headers, body, now)?;
+        if !self.seen.insert(&signature, 2 * MAX_CLOCK_SKEW).await? {
+            return Err(unauthorized("request was already received"));
+        }
+
+        Ok(())
+    }
+}
+
+fn unauthorized(message: &str) -> ApiError {
+    ApiError::new(StatusCode::UNAUTHORIZED, message)
+}
+
+/// The current UNIX time with millisecond precision.
+fn now() -> f64 {
+    chrono::Utc::now().timestamp_millis() as f64 / 1000.0
+}
+
+/// A JSON body that has been authenticated with [`WebhookAuth`].
+///
+/// The body is limited by the router's `DefaultBodyLimit`.
+pub struct SignedJson<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S, Body> for SignedJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+    WebhookAuth: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request<Body>, state: &S) -> Result<Self, Self::Rejection> {
+        let auth = WebhookAuth::from_ref(state);
+        let headers = req.headers().clone();
+
+        let body = Bytes::from_request(req, state).await.map_err(|rejection| {
+            warn!(error = %rejection.body_text(), "rejected webhook body");
+            ApiError::new(rejection.status(), rejection.body_text())
+        })?;
+
+        auth.authenticate(&headers, &body, now())
+            .await
+            .inspect_err(|e| {
+                warn!(error = %e.message, "rejected unauthenticated webhook");
+            })?;
+
+        let value = serde_json::from_slice(&body).map_err(|e| {
+            warn!(error = %e, "rejected malformed webhook payload");
+            ApiError::new(
+                StatusCode::BAD_REQUEST,
+                format!("invalid JSON payload: {e}"),
+            )
+        })?;
+
+        Ok(Self(value))
+    }
+}
+
+/// A request without a body that has been authenticated with [`WebhookAuth`].
+pub struct Authenticated;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Authenticated
+where
+    S: Send + Sync,
+    WebhookAuth: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let auth = WebhookAuth::from_ref(state);
+        auth.authenticate(&parts.headers, b"", now())
+            .await
+            .inspect_err(|e| {
+                warn!(error = %e.message, "rejected unauthenticated request");
+            })?;
+
+        Ok(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    use axum::extract::DefaultBodyLimit;
+    use axum::http::header;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    const SECRET: &str = "correct horse battery staple";
+
+    #[derive(Default)]
+    struct MemorySignatures(Mutex<HashSet<String>>);
+
+    #[async_trait]
+    impl SeenSignatures for MemorySignatures {
+        async fn insert(&self, signature: &str, _ttl: i64) -> Result<bool, ApiError> {
+            Ok(self.0.lock().unwrap().insert(signature.to_string()))
+        }
+    }
+
+    fn auth(secret: &str) -> WebhookAuth {
+        WebhookAuth::new(secret, MemorySignatures::default())
+    }
+
+    impl WebhookAuth {
+        /// Computes the `X-Langley-Signature` header value for a request body.
+        fn sign(&self, timestamp: f64, body: &[u8]) -> String {
+            let signature = self.mac(&timestamp.to_string(), body).finalize();
+            format!("sha256={}", hex::encode(signature.into_bytes()))
+        }
+    }
+
+    async fn echo(SignedJson(value): SignedJson<Value>) -> Json<Value> {
+        Json(value)
+    }
+
+    async fn next(_: Authenticated) -> &'static str {
+        "/music/a.mp3"
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/played", post(echo))
+            .route("/next", get(next))
+            .layer(DefaultBodyLimit::max(MAX_BODY_SIZE))
+            .with_state(auth(SECRET))
+    }
+
+    fn request(headers: &[(&str, String)], body: impl Into<Body>) -> Request<Body> {
+        let mut builder = Request::post("/played").header(header::CONTENT_TYPE, "application/json");
+        for (name, value) in headers {
+            builder = builder.header(*name, value);
+        }
+        builder.body(body.into()).unwrap()
+    }
+
+    fn signed(timestamp: f64, body: &str) -> Request<Body> {
+        let signature = auth(SECRET).sign(timestamp, body.as_bytes());
+        request(
+            &[
+                (TIMESTAMP_HEADER, timestamp.to_string()),
+                (SIGNATURE_HEADER, signature),
+            ],
+            body.to_string(),
+        )
+    }
+
+    async fn status(req: Request<Body>) -> StatusCode {
+        app().oneshot(req).await.unwrap().status()
+    }
+
+    const BODY: &str = r#"{"filename":"a.mp3","title":"t","artist":"a","album":"b"}"#;
+
+    #[tokio::test]
+    async fn accepts_valid_signature() {
+        assert_eq!(status(signed(now(), BODY)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn accepts_whole_second_timestamps() {
+        assert_eq!(status(signed(now().floor(), BODY)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_signature() {
+        let req = request(&[(TIMESTAMP_HEADER, now().to_string())], BODY);
+
+        assert_eq!(status(req).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_timestamp() {
+        let signature = auth(SECRET).sign(now(), BODY.as_bytes());
+        let req = request(&[(SIGNATURE_HEADER, signature)], BODY);
+
+        assert_eq!(status(req).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_non_finite_timestamp() {
+        let signature = auth(SECRET).mac("NaN", BODY.as_bytes()).finalize();
+        let req = request(
+            &[
+                (TIMESTAMP_HEADER, "NaN".to_string()),
+                (
+                    SIGNATURE_HEADER,
+                    format!("sha256={}", hex::encode(signature.into_bytes())),
+                ),
+            ],
+            BODY,
+        );
+
+        assert_eq!(status(req).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_bearer_token() {
+        let req = request(
+            &[
+                (TIMESTAMP_HEADER, now().to_string()),
+                ("authorization", format!("Bearer {SECRET}")),
+            ],
+            BODY,
+        );
+
+        assert_eq!(status(req).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_signature_from_other_secret() {
+        let ts = now();
+        let signature = auth("not the secret").sign(ts, BODY.as_bytes());
+        let req = request(
+            &[
+                (TIMESTAMP_HEADER, ts.to_string()),
+                (SIGNATURE_HEADER, signature),
+            ],
+            BODY,
+        );
+
+        assert_eq!(status(req).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_tampered_body() {
+        let ts = now();
+        let signature = auth(SECRET).sign(ts, BODY.as_bytes());
+        let req = request(
+            &[
+                (TIMESTAMP_HEADER, ts.to_string()),
+                (SIGNATURE_HEADER, signature),
+            ],
+            BODY.replace("a.mp3", "b.mp3"),
+        );
+
+        assert_eq!(status(req).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_expired_request() {
+        let ts = now() - MAX_CLOCK_SKEW as f64 - 1.0;
+
+        assert_eq!(status(signed(ts, BODY)).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_replayed_request() {
+        let app = app();
+        let ts = now();
+
+        let response = app.clone().oneshot(signed(ts, BODY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.oneshot(signed(ts, BODY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_body() {
+        let body = format!(r#"{{"filename":"{}"}}"#, "a".repeat(MAX_BODY_SIZE));
+
+        assert_eq!(
+            status(signed(now(), &body)).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+
+    #[tokio::test]
+    async fn authenticates_requests_without_body() {
+        let ts = now();
+        let signature = auth(SECRET).sign(ts, b"");
+        let req = Request::get("/next")
+            .header(TIMESTAMP_HEADER, ts.to_string())
+            .header(SIGNATURE_HEADER, signature)
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(req).await, StatusCode::OK);
+
+        let req = Request::get("/next")
+            .header(TIMESTAMP_HEADER, ts.to_string())
+            .header("authorization", format!("Bearer {SECRET}"))
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(req).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_json() {
+        assert_eq!(
+            status(signed(now(), "{not json")).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
@@ -1,13 +1,32 @@
-use axum::extract::State;
-use axum::http::StatusCode;
+use std::path::{Path, PathBuf};
+
+use axum::extract::{DefaultBodyLimit, FromRef, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
+use chrono::NaiveDateTime;
 use fred::pool::RedisPool;
 use fred::prelude::PubsubInterface;
 use fred::types::{PerformanceConfig, ReconnectPolicy, RedisConfig};
 
+use judeharley::alerts::{self, Alert, Severity};
+use judeharley::controllers::unknown_plays::NewUnknownPlay;
+use judeharley::health::{check_dependencies, HealthReport};
+use judeharley::jingles::{self, JingleRules};
+use judeharley::live;
+use judeharley::metrics;
+use judeharley::now_playing::{NowPlaying, NOW_PLAYING_CHANNEL};
+use judeharley::rotation::{Rotation, RotationRules};
+use judeharley::schedule;
 use judeharley::sea_orm::DatabaseConnection;
+use judeharley::webhooks::{self, WebhookEvent};
+use judeharley::JudeHarleyError;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
+
+use crate::auth::{Authenticated, SignedJson, WebhookAuth};
+
+mod auth;
 
 #[derive(Deserialize, Debug)]
 struct Song {
@@ -17,53 +36,463 @@ struct Song {
     album: String,
 }
 
+#[derive(Deserialize)]
+struct HarborLogin {
+    user: String,
+    password: String,
+}
+
 #[derive(Serialize, Debug)]
 struct SongResponse {
     success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(SongResponse {
+                success: false,
+                error: Some(self.message),
+            }),
+        )
+            .into_response()
+    }
+}
+
+/// Counts the error and raises an alert for it, `key` names the failing step.
+async fn report_error(e: &JudeHarleyError, key: &str, title: &str, app_state: &AppState) {
+    metrics::record_error(e);
+    alerts::raise(
+        &app_state.redis_pool,
+        Alert::new("langley", Severity::Error, key, title, e.to_string()),
+    )
+    .await;
+}
+
+async fn get_song(
+    filename: &str,
+    app_state: &AppState,
+) -> Result<Option<judeharley::Songs>, ApiError> {
+    match judeharley::Songs::get(filename, &app_state.db).await {
+        Ok(song) => Ok(song),
+        Err(e) => {
+            error!(filename, error = %e, "failed to query song");
+            report_error(&e, "query_song", "Failed to query a played song", app_state).await;
+            Err(ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to query song",
+            ))
+        }
+    }
+}
+
+/// Looks up the song that Liquidsoap reported, indexing it on the fly if the
+/// watcher hasn't caught up with it yet.
+async fn find_or_index_song(
+    song: &Song,
+    app_state: &AppState,
+) -> Result<Option<judeharley::Songs>, ApiError> {
+    if let Some(db_song) = get_song(&song.filename, app_state).await? {
+        return Ok(Some(db_song));
+    }
+
+    warn!(
+        filename = %song.filename,
+        music_path = %app_state.music_path.display(),
+        "song is not indexed, indexing it now"
+    );
+    if let Err(e) = judeharley::maintenance::indexing::index_file(
+        &app_state.db,
+        Path::new(&song.filename),
+        &app_state.music_path,
+    )
+    .await
+    {
+        warn!(filename = %song.filename, error = %e, "failed to index unknown song");
+        return Ok(None);
+    }
+
+    get_song(&song.filename, app_state).await
+}
+
+async fn record_unknown_play(song: Song, reason: &str, app_state: &AppState) {
+    let result = judeharley::UnknownPlays::insert(
+        NewUnknownPlay {
+            filename: song.filename.clone(),
+            title: song.title,
+            artist: song.artist,
+            album: song.album,
+            reason: reason.to_string(),
+        },
+        &app_state.db,
+    )
+    .await;
+
+    match result {
+        Ok(play) => info!(
+            filename = %song.filename,
+            unknown_play_id = play.id,
+            reason,
+            "recorded unknown play"
+        ),
+        Err(e) => {
+            error!(
+                filename = %song.filename,
+                error = %e,
+                reason,
+                "failed to record unknown play"
+            );
+            report_error(
+                &e,
+                "insert_unknown_play",
+                "Failed to record an unknown play",
+                app_state,
+            )
+            .await;
+        }
+    }
+}
+
+/// Records a play of the jingle at `filename` and returns `true`, or returns
+/// `false` if it isn't a jingle. Jingle plays aren't song plays, so nothing
+/// else happens for them.
+async fn record_jingle_play(filename: &str, app_state: &AppState) -> Result<bool, ApiError> {
+    let now = chrono::Utc::now().naive_utc();
+    let jingle = match judeharley::Jingles::mark_played(filename, now, &app_state.db).await {
+        Ok(jingle) => jingle,
+        Err(e) => {
+            error!(filename, error = %e, "failed to query jingle");
+            report_error(
+                &e,
+                "query_jingle",
+                "Failed to query a played jingle",
+                app_state,
+            )
+            .await;
+            return Err(ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to query jingle",
+            ));
+        }
+    };
+    let Some(jingle) = jingle else {
+        return Ok(false);
+    };
+
+    if let Err(e) = judeharley::PlayedSongs::finish_latest(now, &app_state.db).await {
+        metrics::record_error(&e);
+        warn!(filename, error = %e, "failed to finish previous play");
+    }
+    debug!(filename, jingle_id = jingle.id, "played jingle");
+
+    Ok(true)
 }
 
 async fn played(
     State(app_state): State<AppState>,
-    Json(song): Json<Song>,
-) -> (StatusCode, Json<SongResponse>) {
+    SignedJson(song): SignedJson<Song>,
+) -> Result<Json<SongResponse>, ApiError> {
     if song.filename.is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(SongResponse { success: false }),
+        warn!(
+            title = %song.title,
+            artist = %song.artist,
+            "rejected /played payload without a filename"
         );
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "filename must not be empty",
+        ));
     }
 
-    let db_song = judeharley::Songs::get(&song.filename, &app_state.db)
-        .await
-        .expect("Failed to query database")
-        .expect("Song not found");
+    if record_jingle_play(&song.filename, &app_state).await? {
+        return Ok(Json(SongResponse {
+            success: true,
+            error: None,
+        }));
+    }
 
-    judeharley::PlayedSongs::insert(&db_song, &app_state.db)
-        .await
-        .expect("Failed to insert played song");
+    let Some(db_song) = find_or_index_song(&song, &app_state).await? else {
+        let filename = song.filename.clone();
+        record_unknown_play(song, "not indexed", &app_state).await;
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("song {filename} is not indexed"),
+        ));
+    };
+
+    let started_at = chrono::Utc::now();
+    if let Err(e) =
+        judeharley::PlayedSongs::finish_latest(started_at.naive_utc(), &app_state.db).await
+    {
+        metrics::record_error(&e);
+        warn!(filename = %song.filename, error = %e, "failed to finish previous play");
+    }
+
+    if let Err(e) = judeharley::PlayedSongs::insert(&db_song, &app_state.db).await {
+        error!(
+            filename = %song.filename,
+            song_id = %db_song.file_hash,
+            error = %e,
+            "failed to insert played song"
+        );
+        report_error(
+            &e,
+            "insert_played_song",
+            "Failed to record a played song",
+            &app_state,
+        )
+        .await;
+        return Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to insert played song",
+        ));
+    }
+    metrics::SONGS_PLAYED.inc();
+
+    if let Err(e) = judeharley::maintenance::scrobbling::enqueue_play(
+        &db_song,
+        started_at.naive_utc(),
+        app_state.scrobble_station,
+        &app_state.db,
+    )
+    .await
+    {
+        metrics::record_error(&e);
+        warn!(filename = %song.filename, error = %e, "failed to queue scrobbles");
+    }
 
-    let _ = app_state
+    if let Err(e) = app_state
         .redis_pool
         .publish::<i32, _, _>(
             "byers:status",
             format!("{} - {} - {}", song.album, song.artist, song.title),
         )
-        .await;
+        .await
+    {
+        metrics::ERRORS.with_label_values(&["redis"]).inc();
+        warn!(filename = %song.filename, error = %e, "failed to publish status");
+    }
 
-    debug!("Played song: {}", song.filename);
+    let song_id = db_song.file_hash.clone();
+    let now_playing = NowPlaying::new(db_song, started_at);
+    webhooks::trigger(WebhookEvent::SongStarted, &now_playing, &app_state.db).await;
+    match serde_json::to_string(&now_playing) {
+        Ok(payload) => {
+            if let Err(e) = app_state
+                .redis_pool
+                .publish::<i32, _, _>(NOW_PLAYING_CHANNEL, payload)
+                .await
+            {
+                metrics::ERRORS.with_label_values(&["redis"]).inc();
+                warn!(filename = %song.filename, error = %e, "failed to publish now playing");
+            }
+        }
+        Err(e) => warn!(filename = %song.filename, error = %e, "failed to serialize now playing"),
+    }
+
+    debug!(filename = %song.filename, song_id = %song_id, "played song");
+
+    Ok(Json(SongResponse {
+        success: true,
+        error: None,
+    }))
+}
+
+/// Narrows the rotation down to the schedule block running at `now`, if any.
+async fn apply_schedule(rotation: &mut Rotation, now: NaiveDateTime, app_state: &AppState) {
+    let blocks = match judeharley::ScheduleBlocks::get_all(&app_state.db).await {
+        Ok(blocks) => blocks,
+        Err(e) => {
+            metrics::record_error(&e);
+            warn!(error = %e, "failed to load schedule blocks, rotating everything");
+            return;
+        }
+    };
 
-    (StatusCode::OK, Json(SongResponse { success: true }))
+    let Some((block, _)) = schedule::active_block(&blocks, now) else {
+        return;
+    };
+    if !rotation.restrict(|song| block.includes(song)) {
+        warn!(
+            block_id = block.id,
+            name = %block.name,
+            "schedule block matches no songs, rotating everything"
+        );
+    }
+}
+
+/// Picks the next song for Liquidsoap's rotation and responds with its path,
+/// or with no content if the library is empty. A jingle is picked instead
+/// when one is due.
+async fn next(State(app_state): State<AppState>, _: Authenticated) -> Result<Response, ApiError> {
+    let now = chrono::Utc::now().naive_utc();
+    match jingles::next_jingle(&app_state.jingle_rules, now, &app_state.db).await {
+        Ok(Some(jingle)) => {
+            debug!(filename = %jingle.file_path, jingle_id = jingle.id, "picked jingle");
+            return Ok(jingle.file_path.into_response());
+        }
+        Ok(None) => {}
+        Err(e) => {
+            metrics::record_error(&e);
+            warn!(error = %e, "failed to check for a due jingle");
+        }
+    }
+
+    let mut rotation = match Rotation::load(&app_state.rotation_rules, now, &app_state.db).await {
+        Ok(rotation) => rotation,
+        Err(e) => {
+            error!(error = %e, "failed to load rotation");
+            report_error(
+                &e,
+                "load_rotation",
+                "Failed to pick the next song",
+                &app_state,
+            )
+            .await;
+            return Err(ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to load rotation",
+            ));
+        }
+    };
+    apply_schedule(&mut rotation, now, &app_state).await;
+
+    let Some(song) = rotation.pick(&app_state.rotation_rules, now, &mut rand::thread_rng()) else {
+        warn!("the library is empty, nothing to rotate");
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    };
+    debug!(filename = %song.file_path, song_id = %song.file_hash, "picked next song");
+
+    Ok(song.file_path.clone().into_response())
+}
+
+/// Checks the credentials a DJ connects to Liquidsoap's harbor with. Only the
+/// DJ whose session is on air may connect.
+async fn live_auth(
+    State(app_state): State<AppState>,
+    SignedJson(login): SignedJson<HarborLogin>,
+) -> Result<StatusCode, ApiError> {
+    match live::authenticate(&login.user, &login.password, &app_state.db).await {
+        Ok(Some(dj)) => {
+            info!(username = %login.user, dj_name = %dj.name, "DJ connected to the harbor");
+            Ok(StatusCode::OK)
+        }
+        Ok(None) => {
+            warn!(username = %login.user, "rejected harbor login");
+            Ok(StatusCode::FORBIDDEN)
+        }
+        Err(e) => {
+            error!(username = %login.user, error = %e, "failed to check harbor login");
+            report_error(
+                &e,
+                "live_auth",
+                "Failed to check a harbor login",
+                &app_state,
+            )
+            .await;
+            Err(ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to check harbor login",
+            ))
+        }
+    }
+}
+
+/// Parses the environment variable `name`, if it is set.
+fn parse_env<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let value = std::env::var(name).ok().filter(|value| !value.is_empty())?;
+
+    Some(
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("{name} must be a number")),
+    )
+}
+
+fn rotation_rules_from_env() -> RotationRules {
+    let defaults = RotationRules::default();
+
+    RotationRules {
+        repeat_window: parse_env::<f64>("ROTATION_REPEAT_HOURS")
+            .map(|hours| chrono::Duration::minutes((hours * 60.0) as i64))
+            .unwrap_or(defaults.repeat_window),
+        artist_separation: parse_env("ROTATION_ARTIST_SEPARATION")
+            .unwrap_or(defaults.artist_separation),
+        album_separation: parse_env("ROTATION_ALBUM_SEPARATION")
+            .unwrap_or(defaults.album_separation),
+        favourite_weight: parse_env("ROTATION_FAVOURITE_WEIGHT")
+            .unwrap_or(defaults.favourite_weight),
+        request_weight: parse_env("ROTATION_REQUEST_WEIGHT").unwrap_or(defaults.request_weight),
+        max_duration: parse_env("ROTATION_MAX_DURATION"),
+    }
+}
+
+fn jingle_rules_from_env() -> JingleRules {
+    JingleRules {
+        every_songs: parse_env("JINGLE_EVERY_SONGS"),
+        every_minutes: parse_env("JINGLE_EVERY_MINUTES"),
+    }
+}
+
+async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        metrics::gather(),
+    )
+}
+
+async fn health(State(app_state): State<AppState>) -> impl IntoResponse {
+    let dependencies = check_dependencies(&app_state.db, &app_state.redis_pool).await;
+    let report = HealthReport::new("langley", env!("CARGO_PKG_VERSION"), dependencies);
+    let status = if report.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(report))
 }
 
 #[derive(Clone)]
 struct AppState {
     redis_pool: RedisPool,
     db: DatabaseConnection,
+    music_path: PathBuf,
+    auth: WebhookAuth,
+    /// Whether plays are scrobbled to the station's own ListenBrainz account.
+    scrobble_station: bool,
+    rotation_rules: RotationRules,
+    jingle_rules: JingleRules,
+}
+
+impl FromRef<AppState> for WebhookAuth {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.auth.clone()
+    }
 }
 
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
+    judeharley::health::init();
+    judeharley::metrics::init();
 
     let redis_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
 
@@ -74,15 +503,48 @@ async fn main() {
         RedisPool::new(config, Some(perf), Some(policy), 1).expect("Failed to create redis pool");
     redis_pool.connect();
 
+    let mut redis_error_rx = redis_pool.on_error();
+    tokio::spawn(async move {
+        while let Ok(e) = redis_error_rx.recv().await {
+            metrics::ERRORS.with_label_values(&["redis"]).inc();
+            error!(error = %e, "redis error");
+        }
+    });
+
     let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let db = judeharley::connect_database(&db_url)
         .await
         .expect("Failed to connect to database");
 
-    let app_state = AppState { redis_pool, db };
+    let music_path = std::env::var("MUSIC_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("/music"));
+
+    let secret = std::env::var("LANGLEY_SECRET").expect("LANGLEY_SECRET must be set");
+    assert!(!secret.is_empty(), "LANGLEY_SECRET must not be empty");
+
+    let scrobble_station = std::env::var("SCROBBLE_STATION")
+        .map(|value| value == "true" || value == "1")
+        .unwrap_or(false);
+
+    let auth = WebhookAuth::new(secret, redis_pool.clone());
+    let app_state = AppState {
+        redis_pool,
+        db,
+        music_path,
+        auth,
+        scrobble_station,
+        rotation_rules: rotation_rules_from_env(),
+        jingle_rules: jingle_rules_from_env(),
+    };
 
     let app = axum::Router::new()
         .route("/played", axum::routing::post(played))
+        .route("/live/auth", axum::routing::post(live_auth))
+        .layer(DefaultBodyLimit::max(auth::MAX_BODY_SIZE))
+        .route("/next", axum::routing::get(next))
+        .route("/metrics", axum::routing::get(metrics_handler))
+        .route("/health", axum::routing::get(health))
         .with_state(app_state);
 
     info!("Listening on 0.0.0.0:8000");
@@ -2,6 +2,24 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20240506_215517_initial;
 mod m20240530_174050_edit_users_change_watched_hours;
+mod m20261018_120000_create_unknown_plays;
+mod m20261018_130000_create_listener_samples;
+mod m20261018_140000_add_played_songs_end;
+mod m20261018_150000_create_indexer_runs;
+mod m20261018_160000_add_ops_alert_channels;
+mod m20261018_170000_create_scrobbling;
+mod m20261018_180000_create_webhooks;
+mod m20261018_190000_create_feeds;
+mod m20261018_200000_create_schedule_blocks;
+mod m20261018_210000_create_jingles;
+mod m20261018_220000_create_live_sessions;
+mod m20261018_230000_add_album_queueing;
+mod m20261019_000000_add_song_metadata;
+mod m20261019_010000_add_fuzzy_search;
+mod m20261019_020000_create_song_metadata_overrides;
+mod m20261019_030000_create_song_reports;
+mod m20261019_040000_create_song_lyrics;
+mod m20261019_050000_add_songs_hidden;
 
 pub struct Migrator;
 
@@ -11,6 +29,24 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20240506_215517_initial::Migration),
             Box::new(m20240530_174050_edit_users_change_watched_hours::Migration),
+            Box::new(m20261018_120000_create_unknown_plays::Migration),
+            Box::new(m20261018_130000_create_listener_samples::Migration),
+            Box::new(m20261018_140000_add_played_songs_end::Migration),
+            Box::new(m20261018_150000_create_indexer_runs::Migration),
+            Box::new(m20261018_160000_add_ops_alert_channels::Migration),
+            Box::new(m20261018_170000_create_scrobbling::Migration),
+            Box::new(m20261018_180000_create_webhooks::Migration),
+            Box::new(m20261018_190000_create_feeds::Migration),
+            Box::new(m20261018_200000_create_schedule_blocks::Migration),
+            Box::new(m20261018_210000_create_jingles::Migration),
+            Box::new(m20261018_220000_create_live_sessions::Migration),
+            Box::new(m20261018_230000_add_album_queueing::Migration),
+            Box::new(m20261019_000000_add_song_metadata::Migration),
+            Box::new(m20261019_010000_add_fuzzy_search::Migration),
+            Box::new(m20261019_020000_create_song_metadata_overrides::Migration),
+            Box::new(m20261019_030000_create_song_reports::Migration),
+            Box::new(m20261019_040000_create_song_lyrics::Migration),
+            Box::new(m20261019_050000_add_songs_hidden::Migration),
         ]
     }
 }
@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UnknownPlays::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UnknownPlays::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UnknownPlays::Filename).text().not_null())
+                    .col(ColumnDef::new(UnknownPlays::Title).text().not_null())
+                    .col(ColumnDef::new(UnknownPlays::Artist).text().not_null())
+                    .col(ColumnDef::new(UnknownPlays::Album).text().not_null())
+                    .col(ColumnDef::new(UnknownPlays::Reason).text().not_null())
+                    .col(
+                        ColumnDef::new(UnknownPlays::PlayedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UnknownPlays::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UnknownPlays {
+    Table,
+    Id,
+    Filename,
+    Title,
+    Artist,
+    Album,
+    Reason,
+    PlayedAt,
+}
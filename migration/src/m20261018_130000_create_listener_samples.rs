@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ListenerSamples::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ListenerSamples::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ListenerSamples::Mount).text().not_null())
+                    .col(
+                        ColumnDef::new(ListenerSamples::Listeners)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ListenerSamples::SampledAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_listener_samples_sampled_at")
+                    .table(ListenerSamples::Table)
+                    .col(ListenerSamples::SampledAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlayedSongs::Table)
+                    .add_column(ColumnDef::new(PlayedSongs::PeakListeners).integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlayedSongs::Table)
+                    .drop_column(PlayedSongs::PeakListeners)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ListenerSamples::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ListenerSamples {
+    Table,
+    Id,
+    Mount,
+    Listeners,
+    SampledAt,
+}
+
+#[derive(DeriveIden)]
+enum PlayedSongs {
+    Table,
+    PeakListeners,
+}
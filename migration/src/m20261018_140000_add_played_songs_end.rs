@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlayedSongs::Table)
+                    .add_column(ColumnDef::new(PlayedSongs::EndedAt).timestamp().null())
+                    .add_column(ColumnDef::new(PlayedSongs::PlayedDuration).double().null())
+                    .add_column(ColumnDef::new(PlayedSongs::Skipped).boolean().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlayedSongs::Table)
+                    .drop_column(PlayedSongs::EndedAt)
+                    .drop_column(PlayedSongs::PlayedDuration)
+                    .drop_column(PlayedSongs::Skipped)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayedSongs {
+    Table,
+    EndedAt,
+    PlayedDuration,
+    Skipped,
+}
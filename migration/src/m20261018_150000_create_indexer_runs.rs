@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IndexerRuns::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IndexerRuns::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(IndexerRuns::StartedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(IndexerRuns::FinishedAt).timestamp().null())
+                    .col(ColumnDef::new(IndexerRuns::IndexedFiles).integer().null())
+                    .col(ColumnDef::new(IndexerRuns::FailedFiles).integer().null())
+                    .col(ColumnDef::new(IndexerRuns::Error).text().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_indexer_runs_started_at")
+                    .table(IndexerRuns::Table)
+                    .col(IndexerRuns::StartedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IndexerRuns::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum IndexerRuns {
+    Table,
+    Id,
+    StartedAt,
+    FinishedAt,
+    IndexedFiles,
+    FailedFiles,
+    Error,
+}
@@ -0,0 +1,142 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ListenbrainzAccounts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ListenbrainzAccounts::UserId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ListenbrainzAccounts::Token)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ListenbrainzAccounts::UserName)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ListenbrainzAccounts::LinkedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-listenbrainz_accounts-user_id")
+                            .from(ListenbrainzAccounts::Table, ListenbrainzAccounts::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScrobbleQueue::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ScrobbleQueue::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ScrobbleQueue::UserId).big_integer().null())
+                    .col(ColumnDef::new(ScrobbleQueue::Title).text().not_null())
+                    .col(ColumnDef::new(ScrobbleQueue::Artist).text().not_null())
+                    .col(ColumnDef::new(ScrobbleQueue::Album).text().not_null())
+                    .col(ColumnDef::new(ScrobbleQueue::Duration).double().not_null())
+                    .col(
+                        ColumnDef::new(ScrobbleQueue::ListenedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScrobbleQueue::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ScrobbleQueue::NextAttemptAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ScrobbleQueue::LastError).text().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-scrobble_queue-user_id")
+                            .from(ScrobbleQueue::Table, ScrobbleQueue::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_scrobble_queue_next_attempt_at")
+                    .table(ScrobbleQueue::Table)
+                    .col(ScrobbleQueue::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ScrobbleQueue::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ListenbrainzAccounts::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ListenbrainzAccounts {
+    Table,
+    UserId,
+    Token,
+    UserName,
+    LinkedAt,
+}
+
+#[derive(DeriveIden)]
+enum ScrobbleQueue {
+    Table,
+    Id,
+    UserId,
+    Title,
+    Artist,
+    Album,
+    Duration,
+    ListenedAt,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
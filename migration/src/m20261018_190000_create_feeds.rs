@@ -0,0 +1,157 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IndexedAlbums::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IndexedAlbums::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(IndexedAlbums::Artist).text().not_null())
+                    .col(ColumnDef::new(IndexedAlbums::Album).text().not_null())
+                    .col(
+                        ColumnDef::new(IndexedAlbums::FirstIndexedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_indexed_albums_artist_album")
+                    .table(IndexedAlbums::Table)
+                    .col(IndexedAlbums::Artist)
+                    .col(IndexedAlbums::Album)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_indexed_albums_first_indexed_at")
+                    .table(IndexedAlbums::Table)
+                    .col(IndexedAlbums::FirstIndexedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // the albums already in the library count as indexed now
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "INSERT INTO indexed_albums (artist, album) SELECT DISTINCT artist, album FROM songs WHERE album <> '' ON CONFLICT DO NOTHING;",
+        )
+        .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FeedTokens::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FeedTokens::UserId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(FeedTokens::Token)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(FeedTokens::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-feed_tokens-user_id")
+                            .from(FeedTokens::Table, FeedTokens::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FavouriteSongs::Table)
+                    .add_column(
+                        ColumnDef::new(FavouriteSongs::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FavouriteSongs::Table)
+                    .drop_column(FavouriteSongs::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(FeedTokens::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(IndexedAlbums::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum IndexedAlbums {
+    Table,
+    Id,
+    Artist,
+    Album,
+    FirstIndexedAt,
+}
+
+#[derive(DeriveIden)]
+enum FeedTokens {
+    Table,
+    UserId,
+    Token,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum FavouriteSongs {
+    Table,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduleBlocks::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ScheduleBlocks::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ScheduleBlocks::Name).text().not_null())
+                    .col(ColumnDef::new(ScheduleBlocks::Recurrence).text().not_null())
+                    .col(
+                        ColumnDef::new(ScheduleBlocks::Duration)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleBlocks::Albums)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(ColumnDef::new(ScheduleBlocks::Filter).text().null())
+                    .col(
+                        ColumnDef::new(ScheduleBlocks::CreatedBy)
+                            .big_integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleBlocks::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleBlocks::LastAnnouncedAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ServerChannelConfig::Table)
+                    .add_column(
+                        ColumnDef::new(ServerChannelConfig::Announcements)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ServerChannelConfig::Table)
+                    .drop_column(ServerChannelConfig::Announcements)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ScheduleBlocks::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScheduleBlocks {
+    Table,
+    Id,
+    Name,
+    /// Cron-like `minute hour day-of-month month day-of-week`, in UTC
+    Recurrence,
+    /// Minutes
+    Duration,
+    /// Newline separated album names
+    Albums,
+    Filter,
+    CreatedBy,
+    CreatedAt,
+    LastAnnouncedAt,
+}
+
+#[derive(DeriveIden)]
+enum ServerChannelConfig {
+    Table,
+    Announcements,
+}
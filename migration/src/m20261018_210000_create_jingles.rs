@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Jingles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Jingles::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Jingles::FilePath)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Jingles::Title).text().not_null())
+                    .col(ColumnDef::new(Jingles::Duration).double().not_null())
+                    .col(
+                        ColumnDef::new(Jingles::Enabled)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(Jingles::PlayCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Jingles::LastPlayedAt).timestamp().null())
+                    .col(
+                        ColumnDef::new(Jingles::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Jingles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Jingles {
+    Table,
+    Id,
+    /// Path as Liquidsoap sees it, below `/jingles`
+    FilePath,
+    Title,
+    Duration,
+    Enabled,
+    PlayCount,
+    LastPlayedAt,
+    CreatedAt,
+}
@@ -0,0 +1,125 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LiveDjs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LiveDjs::UserId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LiveDjs::Name).text().not_null())
+                    .col(
+                        ColumnDef::new(LiveDjs::Username)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(LiveDjs::PasswordHash).text().not_null())
+                    .col(
+                        ColumnDef::new(LiveDjs::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-live_djs-user_id")
+                            .from(LiveDjs::Table, LiveDjs::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(LiveSessions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LiveSessions::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LiveSessions::UserId).big_integer().null())
+                    .col(ColumnDef::new(LiveSessions::DjName).text().not_null())
+                    .col(
+                        ColumnDef::new(LiveSessions::StartedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(LiveSessions::EndedAt).timestamp().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-live_sessions-user_id")
+                            .from(LiveSessions::Table, LiveSessions::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-live_sessions-started_at")
+                    .table(LiveSessions::Table)
+                    .col(LiveSessions::StartedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LiveSessions::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(LiveDjs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum LiveDjs {
+    Table,
+    UserId,
+    /// Name shown while the DJ is on air
+    Name,
+    /// Harbor login
+    Username,
+    /// Argon2 hash of the harbor password
+    PasswordHash,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum LiveSessions {
+    Table,
+    Id,
+    UserId,
+    /// The DJ's name at the time, kept if the DJ is removed
+    DjName,
+    StartedAt,
+    EndedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,123 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Songs::Table)
+                    .add_column(ColumnDef::new(Songs::TrackNumber).integer().null())
+                    .add_column(ColumnDef::new(Songs::DiscNumber).integer().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // songs indexed before carry their numbers in the raw tags, e.g. `3/12`
+        let db = manager.get_connection();
+        for (column, tag) in [("track_number", "track"), ("disc_number", "disc")] {
+            db.execute_unprepared(&format!(
+                r#"
+                UPDATE songs SET {column} = substring(song_tags.value FROM '^\s*(\d{{1,6}})')::integer
+                FROM song_tags
+                WHERE song_tags.song_id = songs.file_hash AND lower(song_tags.tag) = '{tag}'
+                "#
+            ))
+            .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-songs-album")
+                    .table(Songs::Table)
+                    .col(Songs::Album)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(QueuedAlbums::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(QueuedAlbums::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(QueuedAlbums::Album).text().not_null())
+                    .col(ColumnDef::new(QueuedAlbums::SongIds).text().not_null())
+                    .col(
+                        ColumnDef::new(QueuedAlbums::StartsAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QueuedAlbums::RequestedBy)
+                            .big_integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(QueuedAlbums::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(QueuedAlbums::QueuedAt).timestamp().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(QueuedAlbums::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-songs-album")
+                    .table(Songs::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Songs::Table)
+                    .drop_column(Songs::TrackNumber)
+                    .drop_column(Songs::DiscNumber)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Songs {
+    Table,
+    Album,
+    TrackNumber,
+    DiscNumber,
+}
+
+#[derive(DeriveIden)]
+enum QueuedAlbums {
+    Table,
+    Id,
+    Album,
+    /// Newline separated song hashes, in the order they are queued
+    SongIds,
+    StartsAt,
+    RequestedBy,
+    CreatedAt,
+    /// When the songs were pushed into the priority queue
+    QueuedAt,
+}
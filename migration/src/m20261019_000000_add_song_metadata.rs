@@ -0,0 +1,126 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The raw tag keys each column is read from, formats name them differently,
+/// and how their values are parsed. The expressions mirror `parse_position`
+/// and `parse_year` in `judeharley::maintenance::metadata`, except that
+/// positions over nine digits are left for the next reindex.
+const BACKFILLS: [(&str, &str, &str); 5] = [
+    (
+        "track_number",
+        "'track', 'tracknumber', 'trck'",
+        r"nullif(substring(song_tags.value FROM '^\s*(\d{1,9})(?!\d)')::integer, 0)",
+    ),
+    (
+        "disc_number",
+        "'disc', 'discnumber', 'disk', 'tpos'",
+        r"nullif(substring(song_tags.value FROM '^\s*(\d{1,9})(?!\d)')::integer, 0)",
+    ),
+    (
+        "year",
+        "'date', 'year', 'tdrc', 'tyer', 'originaldate', 'tdor'",
+        r"substring(song_tags.value FROM '(?:^|\D)(\d{4})(?!\d)')::integer",
+    ),
+    (
+        "genre",
+        "'genre', 'tcon'",
+        "nullif(trim(song_tags.value), '')",
+    ),
+    (
+        "album_artist",
+        "'album_artist', 'albumartist', 'album artist', 'tpe2'",
+        "nullif(trim(song_tags.value), '')",
+    ),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Songs::Table)
+                    .add_column(ColumnDef::new(Songs::Year).integer().null())
+                    .add_column(ColumnDef::new(Songs::Genre).text().null())
+                    .add_column(ColumnDef::new(Songs::AlbumArtist).text().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        for (column, keys, value) in BACKFILLS {
+            db.execute_unprepared(&format!(
+                r#"
+                UPDATE songs SET {column} = {value}
+                FROM song_tags
+                WHERE song_tags.song_id = songs.file_hash
+                    AND songs.{column} IS NULL
+                    AND lower(song_tags.tag) IN ({keys})
+                    AND {value} IS NOT NULL
+                "#
+            ))
+            .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-songs-genre")
+                    .table(Songs::Table)
+                    .col(Songs::Genre)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-songs-year")
+                    .table(Songs::Table)
+                    .col(Songs::Year)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-songs-year")
+                    .table(Songs::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-songs-genre")
+                    .table(Songs::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Songs::Table)
+                    .drop_column(Songs::Year)
+                    .drop_column(Songs::Genre)
+                    .drop_column(Songs::AlbumArtist)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Songs {
+    Table,
+    Year,
+    Genre,
+    AlbumArtist,
+}
@@ -0,0 +1,136 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm")
+            .await?;
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS unaccent")
+            .await?;
+
+        // `unaccent` is only stable since its dictionary could change, which
+        // keeps it out of generated columns and indexes
+        db.execute_unprepared(
+            r#"
+            CREATE OR REPLACE FUNCTION immutable_unaccent(text) RETURNS text
+            LANGUAGE sql IMMUTABLE PARALLEL SAFE STRICT
+            AS $$ SELECT public.unaccent('public.unaccent'::regdictionary, $1) $$
+            "#,
+        )
+        .await?;
+        // qualified, since restoring a dump builds the index below with an
+        // empty search path
+        db.execute_unprepared(
+            r#"
+            CREATE OR REPLACE FUNCTION song_search_text(title text, artist text, album text)
+            RETURNS text
+            LANGUAGE sql IMMUTABLE PARALLEL SAFE
+            AS $$ SELECT lower(public.immutable_unaccent(concat_ws(' ', artist, album, title))) $$
+            "#,
+        )
+        .await?;
+
+        // the simple dictionary doesn't stem, so names like "Showtime" or
+        // "Sburban" are indexed as they are written
+        db.execute_unprepared("ALTER TABLE songs DROP COLUMN tsvector")
+            .await?;
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE songs ADD COLUMN tsvector TSVECTOR GENERATED ALWAYS AS (
+                setweight(to_tsvector('simple', immutable_unaccent(title)), 'A') ||
+                setweight(to_tsvector('simple', immutable_unaccent(artist)), 'B') ||
+                setweight(to_tsvector('simple', immutable_unaccent(album)), 'C')
+            ) STORED
+            "#,
+        )
+        .await?;
+        db.execute_unprepared("CREATE INDEX idx_songs_tsvector ON songs USING gin (tsvector)")
+            .await?;
+        // fuzzy searches compare the query to every song, `<%` uses this
+        // instead of scanning them all
+        db.execute_unprepared(
+            r#"
+            CREATE INDEX idx_songs_search_text_trgm ON songs
+            USING gin (song_search_text(title, artist, album) gin_trgm_ops)
+            "#,
+        )
+        .await?;
+
+        // search results are ordered by how often songs were played and requested
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_played_songs_song_id")
+                    .table(PlayedSongs::Table)
+                    .col(PlayedSongs::SongId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_song_requests_song_id")
+                    .table(SongRequests::Table)
+                    .col(SongRequests::SongId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_song_requests_song_id")
+                    .table(SongRequests::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_played_songs_song_id")
+                    .table(PlayedSongs::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_songs_search_text_trgm")
+            .await?;
+        db.execute_unprepared("ALTER TABLE songs DROP COLUMN tsvector")
+            .await?;
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE songs ADD COLUMN tsvector TSVECTOR GENERATED ALWAYS AS (
+                to_tsvector('english', title) ||
+                to_tsvector('english', artist) ||
+                to_tsvector('english', album)
+            ) STORED
+            "#,
+        )
+        .await?;
+        db.execute_unprepared("DROP FUNCTION IF EXISTS song_search_text(text, text, text)")
+            .await?;
+        db.execute_unprepared("DROP FUNCTION IF EXISTS immutable_unaccent(text)")
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayedSongs {
+    Table,
+    SongId,
+}
+
+#[derive(DeriveIden)]
+enum SongRequests {
+    Table,
+    SongId,
+}
@@ -0,0 +1,131 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // keyed by the song hash rather than referencing songs, a reindex
+        // empties the songs table and the overrides have to outlive that
+        manager
+            .create_table(
+                Table::create()
+                    .table(SongMetadataOverrides::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SongMetadataOverrides::SongId)
+                            .string_len(64)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SongMetadataOverrides::FilePath)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SongMetadataOverrides::Title).text().null())
+                    .col(ColumnDef::new(SongMetadataOverrides::Artist).text().null())
+                    .col(ColumnDef::new(SongMetadataOverrides::Album).text().null())
+                    .col(
+                        ColumnDef::new(SongMetadataOverrides::Hidden)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(SongMetadataOverrides::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(SongMetadataEdits::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SongMetadataEdits::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SongMetadataEdits::SongId)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SongMetadataEdits::UserId)
+                            .big_integer()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(SongMetadataEdits::Field).text().not_null())
+                    .col(ColumnDef::new(SongMetadataEdits::OldValue).text().null())
+                    .col(ColumnDef::new(SongMetadataEdits::NewValue).text().null())
+                    .col(
+                        ColumnDef::new(SongMetadataEdits::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-song_metadata_edits-song_id")
+                    .table(SongMetadataEdits::Table)
+                    .col(SongMetadataEdits::SongId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SongMetadataEdits::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(SongMetadataOverrides::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SongMetadataOverrides {
+    Table,
+    /// The song's file hash
+    SongId,
+    /// Where the song is, so hidden songs can still be found
+    FilePath,
+    Title,
+    Artist,
+    Album,
+    /// Hidden songs aren't indexed at all
+    Hidden,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum SongMetadataEdits {
+    Table,
+    Id,
+    SongId,
+    /// Who made the edit
+    UserId,
+    /// `title`, `artist`, `album` or `hidden`
+    Field,
+    /// The overridden value before the edit, null if there was none
+    OldValue,
+    /// The overridden value after the edit, null if it was reset
+    NewValue,
+    CreatedAt,
+}
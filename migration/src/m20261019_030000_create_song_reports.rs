@@ -0,0 +1,129 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ServerChannelConfig::Table)
+                    .add_column(
+                        ColumnDef::new(ServerChannelConfig::SongReports)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(SongReports::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SongReports::Id)
+                            .integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SongReports::SongId)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SongReports::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(SongReports::Title).text().null())
+                    .col(ColumnDef::new(SongReports::Artist).text().null())
+                    .col(ColumnDef::new(SongReports::Album).text().null())
+                    .col(
+                        ColumnDef::new(SongReports::Broken)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(SongReports::Note).text().null())
+                    .col(
+                        ColumnDef::new(SongReports::Status)
+                            .text()
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(ColumnDef::new(SongReports::ReviewedBy).big_integer().null())
+                    .col(ColumnDef::new(SongReports::ReviewedAt).timestamp().null())
+                    .col(
+                        ColumnDef::new(SongReports::Reward)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(SongReports::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-song_reports-status")
+                    .table(SongReports::Table)
+                    .col(SongReports::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SongReports::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ServerChannelConfig::Table)
+                    .drop_column(ServerChannelConfig::SongReports)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ServerChannelConfig {
+    Table,
+    /// Song reports are posted for review in here
+    SongReports,
+}
+
+#[derive(DeriveIden)]
+enum SongReports {
+    Table,
+    Id,
+    SongId,
+    /// Who reported the song
+    UserId,
+    /// The corrected title, if any
+    Title,
+    Artist,
+    Album,
+    /// Whether the file itself is broken
+    Broken,
+    Note,
+    /// `pending`, `approved` or `rejected`
+    Status,
+    ReviewedBy,
+    ReviewedAt,
+    /// Boondollars the reporter was credited with
+    Reward,
+    CreatedAt,
+}
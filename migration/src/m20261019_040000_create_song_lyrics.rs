@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SongLyrics::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SongLyrics::SongId)
+                            .string_len(64)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(SongLyrics::Source).text().not_null())
+                    .col(
+                        ColumnDef::new(SongLyrics::Synced)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(SongLyrics::Lyrics).text().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SongLyrics::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SongLyrics {
+    Table,
+    /// The song's file hash
+    SongId,
+    /// `tag`, `lrc` or `txt`
+    Source,
+    /// Whether the lyrics are LRC with timestamps
+    Synced,
+    Lyrics,
+}